@@ -8,5 +8,5 @@ pub mod server;
 #[cfg(test)]
 mod tests;
 
-pub use scene::{camera::CameraParams, context::SceneContext, error::SceneError, id::EntityId, mesh::MeshData, shape::KernelShape, tessellation::TessParams, transform::Transform, visual::VisualFlags};
+pub use scene::{appearance::Appearance, camera::{Camera, CameraParams}, context::SceneContext, error::SceneError, id::EntityId, mesh::MeshData, shape::KernelShape, tessellation::TessParams, transform::Transform, visual::VisualFlags};
 pub use server::command_server;
@@ -0,0 +1,44 @@
+use crate::scene::error::{SceneError, SceneResult};
+
+/// Display properties resolved by the CAD core (ByLayer/Explicit already applied).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Appearance {
+    /// Base colour, sRGB 8-bit.
+    pub color: [u8; 3],
+    /// `1` is opaque, `0` fully transparent.
+    pub opacity: f32,
+    /// Linetype name looked up in the context's library; unknown names draw continuous.
+    pub linetype: String,
+    /// Plotted line width in millimetres, scaled by the viewport's plot scale.
+    pub lineweight: f32,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            color: [184, 189, 199],
+            opacity: 1.0,
+            linetype: "Continuous".to_owned(),
+            lineweight: 0.25,
+        }
+    }
+}
+
+impl Appearance {
+    pub fn validate(&self) -> SceneResult<()> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(SceneError::InvalidState("opacity must be within 0..=1"));
+        }
+        if !self.lineweight.is_finite() || self.lineweight < 0.0 {
+            return Err(SceneError::InvalidState("lineweight must be a non-negative width"));
+        }
+        if self.linetype.trim().is_empty() {
+            return Err(SceneError::InvalidState("linetype name must not be empty"));
+        }
+        Ok(())
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.opacity >= 1.0
+    }
+}
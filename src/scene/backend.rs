@@ -0,0 +1,463 @@
+//! Reference backend standing in for the wgpu layer.
+//!
+//! It holds per-entity copies of what GPU buffers would contain (mesh,
+//! instance matrices, visual state), updated only by `sync`, and draws
+//! viewports with the CPU rasterizer so screenshots are deterministic.
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+};
+
+use glam::{DMat4, DVec3, Mat3, Mat4, Vec3};
+
+use crate::scene::{
+    appearance::Appearance,
+    bounds::Aabb,
+    camera::CameraParams,
+    color::Rgba,
+    edges::{Edge, FeatureEdges, DEFAULT_CREASE_ANGLE},
+    frame::{FrameStats, SyncReport},
+    id::EntityId,
+    linetype::{Linetype, LinetypeLibrary, PatternUnits},
+    lod::{self, LodPolicy},
+    mesh::MeshData,
+    raster::{self, ClipVertex},
+    section::{CapStyle, Section},
+    transform::FrontFace,
+    viewport::{DisplayMode, Viewport},
+    visual::{DirtyFlags, HighlightStyle, VisualFlags},
+    world::SceneWorld,
+};
+
+pub(crate) const BACKGROUND: Rgba = [38, 41, 46, 255];
+pub(crate) const WIRE: Rgba = [220, 224, 230, 255];
+/// Edge colour over shaded surfaces.
+pub(crate) const EDGE: Rgba = [24, 26, 30, 255];
+/// Coverage of each surface layer in x-ray mode.
+const XRAY_ALPHA: f32 = 0.35;
+/// Share of the selection colour mixed into selected surfaces.
+const SELECTION_TINT: f32 = 0.5;
+/// Depth offset pulling edges in front of the surfaces they lie on.
+const EDGE_DEPTH_BIAS: f32 = 1e-5;
+/// Share of surface brightness that does not depend on the light direction.
+const AMBIENT: f32 = 0.3;
+
+#[derive(Debug, Clone)]
+struct Instance {
+    /// Finest mesh first, then coarser levels by increasing error.
+    levels: Vec<Level>,
+    appearance: Appearance,
+    /// Surface colour and outline resolved from `visual` by the Visual stage.
+    surface: Vec3,
+    outline: Option<Outline>,
+    /// Scene-space model matrix, rounded to f32.
+    model: Mat4,
+    /// Local transform and the f64 offset of its origin from the rebase
+    /// origin, combined with the view in f64 when projecting.
+    local: Mat4,
+    offset: DVec3,
+    /// World-space bounds for culling, refreshed with the mesh or transform.
+    bounds: Option<Aabb>,
+    normal: Mat3,
+    front_face: FrontFace,
+    visual: VisualFlags,
+}
+
+#[derive(Debug, Clone)]
+struct Level {
+    mesh: MeshData,
+    /// Rebuilt with the mesh on every GEOMETRY sync.
+    edges: FeatureEdges,
+    error: f32,
+}
+
+/// Bytes per vertex in the vertex buffer: position, normal and UV.
+pub(crate) const VERTEX_BYTES: usize = 32;
+pub(crate) const INDEX_BYTES: usize = 4;
+
+impl Level {
+    fn new(mesh: &MeshData, error: f32) -> Self {
+        Self { mesh: mesh.clone(), edges: FeatureEdges::extract(mesh, DEFAULT_CREASE_ANGLE), error }
+    }
+
+    fn bytes(&self) -> usize {
+        self.mesh.vertices.len() * VERTEX_BYTES + (self.mesh.indices.len() + self.mesh.polylines.iter().map(Vec::len).sum::<usize>()) * INDEX_BYTES
+    }
+}
+
+/// Ranges of an entity's finest mesh changed since the last sync, so that
+/// only those bytes are uploaded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MeshPatch {
+    pub vertices: Option<Range<usize>>,
+    pub indices: Option<Range<usize>>,
+}
+
+impl MeshPatch {
+    /// Grows the patch to also cover `other`.
+    pub fn merge(&mut self, other: MeshPatch) {
+        let hull = |a: &mut Option<Range<usize>>, b: Option<Range<usize>>| {
+            *a = match (a.take(), b) {
+                (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+                (a, b) => a.or(b),
+            };
+        };
+        hull(&mut self.vertices, other.vertices);
+        hull(&mut self.indices, other.indices);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Outline {
+    color: Rgba,
+    width: f32,
+    on_top: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SoftwareBackend {
+    instances: BTreeMap<EntityId, Instance>,
+    pub style: HighlightStyle,
+    pub linetypes: LinetypeLibrary,
+    pub section: Section,
+    pub lod_policy: LodPolicy,
+}
+
+impl SoftwareBackend {
+    /// Applies per-entity dirty state in Geometry → Transform → Visual order.
+    ///
+    /// A GEOMETRY entity with a patch copies only the patched ranges.
+    pub fn sync(&mut self, world: &SceneWorld, dirty: &BTreeMap<EntityId, DirtyFlags>, patches: &BTreeMap<EntityId, MeshPatch>) -> SyncReport {
+        let mut report = SyncReport::default();
+        let marked = |flag: DirtyFlags| dirty.iter().filter(move |(_, flags)| flags.contains(flag)).map(|(id, _)| *id);
+        for id in marked(DirtyFlags::GEOMETRY) {
+            report.geometry += 1;
+            let Some((record, mesh)) = world.entities.get(&id).and_then(|record| Some((record, record.mesh.as_ref()?))) else {
+                self.instances.remove(&id);
+                continue;
+            };
+            if let (Some(patch), Some(instance)) = (patches.get(&id), self.instances.get_mut(&id)) {
+                if let Some(bytes) = instance.patch(mesh, patch) {
+                    instance.bounds = record.world_bounds();
+                    report.uploaded_bytes += bytes;
+                    continue;
+                }
+            }
+            // A rebuild replaces the whole instance, so the Visual stage's
+            // resolved style is applied again even without VISUAL dirty.
+            let mut instance = Instance {
+                levels: std::iter::once(Level::new(mesh, 0.0)).chain(record.lods.iter().map(|lod| Level::new(&lod.mesh, lod.error))).collect(),
+                appearance: record.appearance.clone(),
+                surface: color_vec(record.appearance.color),
+                outline: None,
+                model: record.model_matrix,
+                local: record.transform.matrix,
+                offset: record.origin - world.rebase_origin,
+                bounds: record.world_bounds(),
+                normal: record.normal_matrix,
+                front_face: record.front_face,
+                visual: record.visual,
+            };
+            apply_style(&mut instance, &self.style);
+            report.uploaded_bytes += instance.levels.iter().map(Level::bytes).sum::<usize>();
+            self.instances.insert(id, instance);
+        }
+        for id in marked(DirtyFlags::TRANSFORM) {
+            if let (Some(instance), Some(record)) = (self.instances.get_mut(&id), world.entities.get(&id)) {
+                instance.model = record.model_matrix;
+                instance.local = record.transform.matrix;
+                instance.offset = record.origin - world.rebase_origin;
+                instance.bounds = record.world_bounds();
+                instance.normal = record.normal_matrix;
+                instance.front_face = record.front_face;
+                report.transform += 1;
+            }
+        }
+        for id in marked(DirtyFlags::VISUAL) {
+            if let (Some(instance), Some(record)) = (self.instances.get_mut(&id), world.entities.get(&id)) {
+                instance.visual = record.visual;
+                instance.appearance = record.appearance.clone();
+                apply_style(instance, &self.style);
+                report.visual += 1;
+            }
+        }
+        report
+    }
+
+    /// Draws all instances shown in `viewport` into its render target.
+    ///
+    /// Surfaces are drawn first for every entity so that the edge pass of
+    /// the edge modes is tested against the complete depth buffer.
+    pub fn draw(&self, viewport: &mut Viewport, camera: &CameraParams) -> FrameStats {
+        let mut stats = FrameStats::default();
+        let Viewport { display_mode, visibility, cull_back_faces, feature_wireframe, pixels_per_mm, lod, target, .. } = viewport;
+        let mode = *display_mode;
+        target.resize(camera.viewport);
+        let reversed = camera.reversed_z();
+        target.clear(BACKGROUND, if reversed { 0.0 } else { 1.0 });
+        let size = target.size();
+        let eye = camera.view.inverse().w_axis.truncate();
+        let closer = |a: f32, b: f32| if reversed { a >= b } else { a <= b };
+
+        let keep = self.section.keep_planes();
+        let frustum = camera.frustum();
+        let shown: Vec<(&Instance, &Level, Vec<ClipVertex>)> = self
+            .instances
+            .iter()
+            .filter(|(id, instance)| {
+                let visible = instance.visual.contains(VisualFlags::VISIBLE);
+                visibility.get(id).copied().unwrap_or(visible)
+            })
+            .filter(|(_, instance)| {
+                stats.entities_considered += 1;
+                // Entities entirely off-screen or cut away are never projected.
+                let culled = instance.bounds.is_some_and(|bounds| !frustum.intersects(&bounds) || keep.iter().any(|&plane| bounds.outside(plane)));
+                stats.entities_culled += culled as usize;
+                !culled
+            })
+            .map(|(id, instance)| {
+                let level = &instance.levels[self.select_level(*id, instance, lod, camera)];
+                (instance, level, project(instance, &level.mesh, camera))
+            })
+            .collect();
+
+        // Opaque entities first in id order, then translucent ones back to front
+        // so that each blends over everything behind it.
+        let alpha = |instance: &Instance| match mode {
+            DisplayMode::XRay => XRAY_ALPHA * instance.appearance.opacity,
+            DisplayMode::HiddenLine => 1.0,
+            _ => instance.appearance.opacity,
+        };
+        let view_depth = |point: Vec3| camera.view.transform_point3(point).z;
+        let mut order: Vec<(&Instance, &Level, &Vec<ClipVertex>, f32)> = shown
+            .iter()
+            .map(|(instance, level, vertices)| {
+                let center = instance.bounds.map_or(Vec3::ZERO, |b| b.center());
+                (*instance, *level, vertices, view_depth(center))
+            })
+            .collect();
+        order.sort_by(|a, b| {
+            let (ta, tb) = (alpha(a.0) < 1.0, alpha(b.0) < 1.0);
+            ta.cmp(&tb).then(if ta && tb { a.3.total_cmp(&b.3) } else { Ordering::Equal })
+        });
+
+        let capping = self.section.is_active() && self.section.cap != CapStyle::None && mode != DisplayMode::XRay;
+        let continuous = Linetype::continuous();
+        for (instance, level, vertices, _) in order {
+            stats.entities_drawn += 1;
+            let alpha = alpha(instance);
+            if mode != DisplayMode::Wireframe {
+                let mut tris: Vec<_> = triangles(&level.mesh, vertices).collect();
+                if alpha < 1.0 {
+                    let centroid = |tri: &[ClipVertex; 3]| view_depth((tri[0].world + tri[1].world + tri[2].world) / 3.0);
+                    tris.sort_by(|a, b| centroid(&a.0).total_cmp(&centroid(&b.0)));
+                }
+                stats.triangles += tris.len();
+                for (tri, indices) in tris {
+                    let color = if mode == DisplayMode::HiddenLine { BACKGROUND } else { shade(instance, &level.mesh, indices, &tri, eye) };
+                    raster::triangle(size, tri, &keep, |f| {
+                        let front_facing = f.ccw == (instance.front_face == FrontFace::Ccw);
+                        // Through a cut, the inside of a solid is seen from the back.
+                        let cap = capping && alpha >= 1.0 && !front_facing;
+                        if (*cull_back_faces && !front_facing && !cap) || !closer(f.depth, target.depth(f.index)) {
+                            return;
+                        }
+                        // Translucent surfaces blend without occluding what is drawn after them.
+                        if alpha < 1.0 {
+                            target.blend(f.index, color, alpha);
+                            return;
+                        }
+                        target.set_depth(f.index, f.depth);
+                        let color = if cap { self.section.cap_pixel(f.index as u32 % size.x, f.index as u32 / size.x) } else { color };
+                        target.set_color(f.index, color);
+                    });
+                }
+            }
+            // Lines are unlit; unknown linetype names draw continuous.
+            let linetype = self.linetypes.get(&instance.appearance.linetype).unwrap_or(&continuous);
+            let width = (instance.appearance.lineweight * *pixels_per_mm).max(1.0);
+            let line_color = to_rgba(instance.surface);
+            for polyline in &level.mesh.polylines {
+                // Pattern distance carries over from segment to segment.
+                let mut distance = 0.0;
+                for pair in polyline.windows(2) {
+                    let (Some(&a), Some(&b)) = (vertices.get(pair[0] as usize), vertices.get(pair[1] as usize)) else {
+                        continue;
+                    };
+                    let pixels = raster::screen_length(size, &a, &b);
+                    let (length, dot) = match linetype.units {
+                        PatternUnits::World => {
+                            let length = a.world.distance(b.world);
+                            (length, pixels.map_or(0.0, |pixels| length / pixels.max(1.0)))
+                        }
+                        PatternUnits::Screen => (pixels.unwrap_or(0.0), 1.0),
+                    };
+                    stats.lines += 1;
+                    raster::line(size, a, b, width, &keep, |f| {
+                        let biased = if reversed { f.depth + EDGE_DEPTH_BIAS } else { f.depth - EDGE_DEPTH_BIAS };
+                        if !linetype.inked(distance + f.along * length, dot) || !closer(biased, target.depth(f.index)) {
+                            return;
+                        }
+                        target.set_depth(f.index, f.depth);
+                        target.set_color(f.index, line_color);
+                    });
+                    distance += length;
+                }
+            }
+        }
+
+        let edge_style = match mode {
+            DisplayMode::Shaded | DisplayMode::XRay => None,
+            DisplayMode::Wireframe => Some((WIRE, false)),
+            DisplayMode::ShadedWithEdges => Some((EDGE, true)),
+            DisplayMode::HiddenLine => Some((WIRE, true)),
+        };
+        let viewer = camera.viewer();
+        let mut lines = |vertices: &[ClipVertex], edges: Vec<Edge>, color: Rgba, width: f32, depth_tested: bool| {
+            stats.lines += edges.len();
+            for [a, b] in edges {
+                let (Some(&a), Some(&b)) = (vertices.get(a as usize), vertices.get(b as usize)) else {
+                    continue;
+                };
+                raster::line(size, a, b, width, &keep, |f| {
+                    let biased = if reversed { f.depth + EDGE_DEPTH_BIAS } else { f.depth - EDGE_DEPTH_BIAS };
+                    if !depth_tested || closer(biased, target.depth(f.index)) {
+                        target.set_color(f.index, color);
+                    }
+                });
+            }
+        };
+        if let Some((color, depth_tested)) = edge_style {
+            for (instance, level, vertices) in &shown {
+                let edges = if !*feature_wireframe {
+                    triangle_edges(&level.mesh)
+                } else {
+                    level.edges.outline(instance.model.inverse() * viewer)
+                };
+                lines(vertices, edges, color, 1.0, depth_tested);
+            }
+        }
+        // Outlines last so that on-top ones are not overdrawn by later entities.
+        for (instance, level, vertices) in &shown {
+            if let Some(outline) = instance.outline {
+                let edges = level.edges.contour(instance.model.inverse() * viewer);
+                lines(vertices, edges, outline.color, outline.width, !outline.on_top);
+            }
+        }
+        stats
+    }
+
+    /// Level of detail for this frame, remembered per viewport for hysteresis.
+    fn select_level(&self, id: EntityId, instance: &Instance, lod: &mut HashMap<EntityId, usize>, camera: &CameraParams) -> usize {
+        let level = match instance.bounds {
+            Some(bounds) if instance.levels.len() > 1 => {
+                let errors: Vec<f32> = instance.levels[1..].iter().map(|level| lod::projected_error(camera, &instance.model, &bounds, level.error)).collect();
+                self.lod_policy.select(lod.get(&id).copied().unwrap_or(0), &errors)
+            }
+            _ => 0,
+        };
+        lod.insert(id, level);
+        level
+    }
+}
+
+fn project(instance: &Instance, mesh: &MeshData, camera: &CameraParams) -> Vec<ClipVertex> {
+    // The origin offset and the view cancel in f64, so coordinates are
+    // rounded to f32 relative to the eye rather than to the rebase origin.
+    let model_view = camera.view.as_dmat4() * DMat4::from_translation(instance.offset) * instance.local.as_dmat4();
+    let mvp = camera.proj * model_view.as_mat4();
+    mesh
+        .vertices
+        .iter()
+        .map(|v| ClipVertex {
+            clip: mvp * v.position.extend(1.0),
+            world: instance.model.transform_point3(v.position),
+        })
+        .collect()
+}
+
+/// Projected triangles with their mesh indices; out-of-range indices are skipped.
+fn triangles<'a>(mesh: &'a MeshData, vertices: &'a [ClipVertex]) -> impl Iterator<Item = ([ClipVertex; 3], [u32; 3])> + 'a {
+    mesh.indices.chunks_exact(3).filter_map(|tri| {
+        let fetch = |i: u32| vertices.get(i as usize).copied();
+        Some(([fetch(tri[0])?, fetch(tri[1])?, fetch(tri[2])?], [tri[0], tri[1], tri[2]]))
+    })
+}
+
+impl Instance {
+    /// Copies the patched ranges of `mesh` into the finest level and returns
+    /// the bytes copied, or `None` when the buffers no longer line up and
+    /// need a full upload.
+    fn patch(&mut self, mesh: &MeshData, patch: &MeshPatch) -> Option<usize> {
+        let [finest] = self.levels.as_mut_slice() else {
+            return None;
+        };
+        let target = &mut finest.mesh;
+        if target.vertices.len() != mesh.vertices.len() || target.indices.len() != mesh.indices.len() {
+            return None;
+        }
+        let mut bytes = 0;
+        if let Some(range) = patch.vertices.clone() {
+            bytes += range.len() * VERTEX_BYTES;
+            target.vertices[range.clone()].clone_from_slice(&mesh.vertices[range]);
+        }
+        if let Some(range) = patch.indices.clone() {
+            bytes += range.len() * INDEX_BYTES;
+            target.indices[range.clone()].copy_from_slice(&mesh.indices[range]);
+        }
+        finest.edges = FeatureEdges::extract(&finest.mesh, DEFAULT_CREASE_ANGLE);
+        Some(bytes)
+    }
+}
+
+/// Resolves surface colour and outline from the visual flags.
+fn apply_style(instance: &mut Instance, style: &HighlightStyle) {
+    let color = |rgba: Rgba| color_vec([rgba[0], rgba[1], rgba[2]]);
+    let base = color_vec(instance.appearance.color);
+    let outline = |color: Rgba| Outline { color, width: style.outline_px, on_top: style.draw_on_top };
+    let visual = instance.visual;
+    (instance.surface, instance.outline) = if visual.contains(VisualFlags::SELECTED) {
+        (base.lerp(color(style.selection_color), SELECTION_TINT), Some(outline(style.selection_color)))
+    } else if visual.contains(VisualFlags::HIGHLIGHTED) {
+        (base.lerp(color(style.highlight_color), style.hover_tint.clamp(0.0, 1.0)), Some(outline(style.highlight_color)))
+    } else {
+        (base, None)
+    };
+    if style.outline_px <= 0.0 {
+        instance.outline = None;
+    }
+}
+
+/// Every triangle edge once, in first-seen order.
+fn triangle_edges(mesh: &MeshData) -> Vec<Edge> {
+    let mut seen = HashSet::new();
+    mesh.indices
+        .chunks_exact(3)
+        .flat_map(|tri| [[tri[0], tri[1]], [tri[1], tri[2]], [tri[2], tri[0]]])
+        .filter(|[a, b]| seen.insert((*a.min(b), *a.max(b))))
+        .collect()
+}
+
+/// Flat, two-sided headlight shading of one triangle.
+fn shade(instance: &Instance, mesh: &MeshData, indices: [u32; 3], tri: &[ClipVertex; 3], eye: Vec3) -> Rgba {
+    let normal = indices
+        .iter()
+        .map(|&i| mesh.vertices[i as usize].normal)
+        .fold(Vec3::ZERO, |acc, n| acc + n);
+    let normal = (instance.normal * normal).normalize_or_zero();
+    let centroid = (tri[0].world + tri[1].world + tri[2].world) / 3.0;
+    let to_eye = (eye - centroid).normalize_or_zero();
+    let intensity = AMBIENT + (1.0 - AMBIENT) * normal.dot(to_eye).abs();
+    to_rgba(instance.surface * intensity)
+}
+
+fn color_vec(rgb: [u8; 3]) -> Vec3 {
+    Vec3::new(rgb[0] as f32, rgb[1] as f32, rgb[2] as f32) / 255.0
+}
+
+pub(crate) fn to_rgba(color: Vec3) -> Rgba {
+    let c = (color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0).round();
+    [c.x as u8, c.y as u8, c.z as u8, 255]
+}
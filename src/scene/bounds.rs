@@ -0,0 +1,85 @@
+use glam::{Mat4, Vec3, Vec4};
+
+/// Axis-aligned bounding box in world units (meters).
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Bounds of a point set, or `None` when the set is empty.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut iter = points.into_iter();
+        let first = iter.next()?;
+        Some(iter.fold(Self::new(first, first), |acc, p| Self::new(acc.min.min(p), acc.max.max(p))))
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Radius of the bounding sphere around `center()`.
+    pub fn radius(&self) -> f32 {
+        self.size().length() * 0.5
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// True when the whole box lies where `plane · (p, 1) < 0`.
+    pub fn outside(&self, plane: Vec4) -> bool {
+        // The corner furthest along the plane normal decides.
+        let nearest = Vec3::select(plane.truncate().cmpge(Vec3::ZERO), self.max, self.min);
+        plane.dot(nearest.extend(1.0)) < 0.0
+    }
+
+    /// Bounds of this box after an affine transform.
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        Aabb::from_points(self.corners().map(|c| matrix.transform_point3(c))).expect("box has corners")
+    }
+}
+
+/// View volume as six inward-facing world-space planes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// `plane · (p, 1) >= 0` inside; left, right, bottom, top, near, far.
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Planes of a view-projection mapping depth to `[0, 1]` (either direction).
+    pub fn from_view_proj(view_proj: &Mat4) -> Self {
+        let [r0, r1, r2, r3] = [0, 1, 2, 3].map(|i| view_proj.row(i));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2].map(|plane| plane / plane.truncate().length().max(f32::MIN_POSITIVE));
+        Self { planes }
+    }
+
+    /// Conservative: boxes near a corner of the frustum may pass without being visible.
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        !self.planes.iter().any(|&plane| aabb.outside(plane))
+    }
+}
@@ -1,4 +1,12 @@
-use glam::{Mat4, UVec2};
+use glam::{Mat4, Quat, UVec2, Vec2, Vec3, Vec4};
+
+use crate::scene::{
+    bounds::{Aabb, Frustum},
+    error::{SceneError, SceneResult},
+};
+
+/// Smallest polar angle (radians) kept between the view direction and the up axis while orbiting.
+const MIN_POLAR: f32 = 1e-3;
 
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct CameraParams {
@@ -11,4 +19,300 @@ impl CameraParams {
     pub fn new(view: Mat4, proj: Mat4, viewport: UVec2) -> Self {
         Self { view, proj, viewport }
     }
+
+    pub fn view_proj(&self) -> Mat4 {
+        self.proj * self.view
+    }
+
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_view_proj(&self.view_proj())
+    }
+
+    /// True when nearer points map to larger depth (reversed-Z projection).
+    pub fn reversed_z(&self) -> bool {
+        let near = self.proj.project_point3(Vec3::new(0.0, 0.0, -1.0)).z;
+        let far = self.proj.project_point3(Vec3::new(0.0, 0.0, -2.0)).z;
+        near > far
+    }
+
+    /// World-space ray through a pixel position, starting on the near plane.
+    pub fn ray(&self, cursor: Vec2) -> (Vec3, Vec3) {
+        let size = self.viewport.as_vec2().max(Vec2::ONE);
+        let ndc = Vec2::new(cursor.x / size.x * 2.0 - 1.0, 1.0 - cursor.y / size.y * 2.0);
+        let inverse = self.view_proj().inverse();
+        let (near_z, far_z) = if self.reversed_z() { (1.0, 0.0) } else { (0.0, 1.0) };
+        let near = inverse.project_point3(ndc.extend(near_z));
+        let far = inverse.project_point3(ndc.extend(far_z));
+        (near, (far - near).normalize_or_zero())
+    }
+
+    /// Pixel position of a world point, the inverse of `ray`; `None` behind the eye.
+    pub fn to_screen(&self, point: Vec3) -> Option<Vec2> {
+        let clip = self.view_proj() * point.extend(1.0);
+        if clip.w <= 1e-6 {
+            return None;
+        }
+        let ndc = clip.truncate().truncate() / clip.w;
+        let size = self.viewport.as_vec2();
+        Some(Vec2::new((ndc.x * 0.5 + 0.5) * size.x, (0.5 - ndc.y * 0.5) * size.y))
+    }
+
+    /// World-space viewer: the eye point (`w = 1`) for perspective
+    /// projections, the direction towards the viewer (`w = 0`) for orthographic ones.
+    pub fn viewer(&self) -> Vec4 {
+        let orthographic = self.proj.row(3) == Vec4::W;
+        self.view.inverse() * if orthographic { Vec4::Z } else { Vec4::W }
+    }
+}
+
+/// World axis that points "up" on screen. Right-handed in both cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum UpAxis {
+    Y,
+    #[default]
+    Z,
+}
+
+impl UpAxis {
+    pub fn vector(self) -> Vec3 {
+        match self {
+            UpAxis::Y => Vec3::Y,
+            UpAxis::Z => Vec3::Z,
+        }
+    }
+
+    /// Screen-up used when looking straight along the up axis (plan views).
+    fn fallback(self) -> Vec3 {
+        match self {
+            UpAxis::Y => Vec3::NEG_Z,
+            UpAxis::Z => Vec3::Y,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Projection {
+    /// Vertical field of view in radians.
+    Perspective { fov_y: f32 },
+    /// Visible height of the view volume in meters.
+    Orthographic { height: f32 },
+}
+
+/// Orbit-style CAD camera that produces `CameraParams`.
+///
+/// Right-handed, meters, depth mapped to `[0, 1]` (reversed when `reversed_z`).
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Camera {
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub up: UpAxis,
+    pub projection: Projection,
+    pub near: f32,
+    pub far: f32,
+    pub reversed_z: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::look_at(Vec3::new(10.0, -10.0, 10.0), Vec3::ZERO, UpAxis::Z)
+    }
+}
+
+impl Camera {
+    pub fn look_at(eye: Vec3, target: Vec3, up: UpAxis) -> Self {
+        Self {
+            eye,
+            target,
+            up,
+            projection: Projection::Perspective { fov_y: 45f32.to_radians() },
+            near: 0.1,
+            far: 10_000.0,
+            reversed_z: true,
+        }
+    }
+
+    pub fn perspective(mut self, fov_y: f32) -> Self {
+        self.projection = Projection::Perspective { fov_y };
+        self
+    }
+
+    pub fn orthographic(mut self, height: f32) -> Self {
+        self.projection = Projection::Orthographic { height };
+        self
+    }
+
+    /// Rejects cameras whose view or projection matrix would be degenerate.
+    pub fn validate(&self) -> SceneResult<()> {
+        if !self.eye.is_finite() || !self.target.is_finite() || self.eye == self.target {
+            return Err(SceneError::InvalidState("camera eye and target must be finite and distinct"));
+        }
+        if !(self.near > 0.0 && self.far > self.near && self.far.is_finite()) {
+            return Err(SceneError::InvalidState("camera clip planes must satisfy 0 < near < far"));
+        }
+        let valid = match self.projection {
+            Projection::Perspective { fov_y } => fov_y > 0.0 && fov_y < std::f32::consts::PI,
+            Projection::Orthographic { height } => height > 0.0 && height.is_finite(),
+        };
+        if !valid {
+            return Err(SceneError::InvalidState("camera field of view or height out of range"));
+        }
+        Ok(())
+    }
+
+    pub fn distance(&self) -> f32 {
+        (self.target - self.eye).length()
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        (self.target - self.eye).normalize_or_zero()
+    }
+
+    /// Right and screen-up vectors in world space, robust to looking along the up axis.
+    fn basis(&self) -> (Vec3, Vec3) {
+        let forward = self.forward();
+        let up = self.up.vector();
+        let up = if forward.cross(up).length_squared() < 1e-8 { self.up.fallback() } else { up };
+        let right = forward.cross(up).normalize();
+        (right, right.cross(forward))
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.basis().0
+    }
+
+    pub fn screen_up(&self) -> Vec3 {
+        self.basis().1
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_at_rh(self.eye, self.target, self.screen_up())
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Mat4 {
+        let (near, far) = if self.reversed_z { (self.far, self.near) } else { (self.near, self.far) };
+        match self.projection {
+            Projection::Perspective { fov_y } => Mat4::perspective_rh(fov_y, aspect, near, far),
+            Projection::Orthographic { height } => {
+                let (hw, hh) = (height * aspect * 0.5, height * 0.5);
+                Mat4::orthographic_rh(-hw, hw, -hh, hh, near, far)
+            }
+        }
+    }
+
+    pub fn params(&self, viewport: UVec2) -> CameraParams {
+        CameraParams::new(self.view_matrix(), self.projection_matrix(aspect(viewport)), viewport)
+    }
+
+    /// World units covered by one pixel on the plane through `target`.
+    pub fn world_per_pixel(&self, viewport: UVec2) -> f32 {
+        let height = match self.projection {
+            Projection::Perspective { fov_y } => 2.0 * self.distance() * (fov_y * 0.5).tan(),
+            Projection::Orthographic { height } => height,
+        };
+        height / viewport.y.max(1) as f32
+    }
+
+    /// Point on the plane through `target` (facing the camera) under a pixel.
+    pub fn target_plane_point(&self, cursor: Vec2, viewport: UVec2) -> Vec3 {
+        let center = Vec2::new(viewport.x as f32, viewport.y as f32) * 0.5;
+        let offset = (cursor - center) * self.world_per_pixel(viewport);
+        self.target + self.right() * offset.x - self.screen_up() * offset.y
+    }
+
+    /// Rotates the eye around `target`: `yaw` about the up axis, `pitch` towards it.
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        let up = self.up.vector();
+        let offset = self.eye - self.target;
+        let radius = offset.length();
+        if radius == 0.0 {
+            return;
+        }
+        let polar = (offset.dot(up) / radius).clamp(-1.0, 1.0).acos();
+        let new_polar = (polar - pitch).clamp(MIN_POLAR, std::f32::consts::PI - MIN_POLAR);
+        let right = self.right();
+        let tilted = Quat::from_axis_angle(right, new_polar - polar) * offset;
+        self.eye = self.target + Quat::from_axis_angle(up, yaw) * tilted;
+    }
+
+    /// Moves eye and target so the target plane follows the cursor by `delta` pixels.
+    pub fn pan(&mut self, delta: Vec2, viewport: UVec2) {
+        let scale = self.world_per_pixel(viewport);
+        let shift = -self.right() * delta.x * scale + self.screen_up() * delta.y * scale;
+        self.eye += shift;
+        self.target += shift;
+    }
+
+    /// Scales the viewing distance (or orthographic height) by `factor`; `< 1` zooms in.
+    pub fn dolly(&mut self, factor: f32) -> SceneResult<()> {
+        validate_factor(factor)?;
+        self.scale(factor);
+        Ok(())
+    }
+
+    fn scale(&mut self, factor: f32) {
+        self.eye = self.target - self.forward() * self.distance() * factor;
+        if let Projection::Orthographic { height } = &mut self.projection {
+            *height *= factor;
+        }
+    }
+
+    /// Dolly that keeps the world point under `cursor` fixed on screen.
+    pub fn zoom_at(&mut self, cursor: Vec2, viewport: UVec2, factor: f32) -> SceneResult<()> {
+        validate_factor(factor)?;
+        let anchor = self.target_plane_point(cursor, viewport);
+        let shift = (self.target - anchor) * factor + anchor - self.target;
+        self.eye += shift;
+        self.target += shift;
+        self.scale(factor);
+        Ok(())
+    }
+
+    /// Fits `bounds` into the viewport, keeping the view direction.
+    pub fn zoom_extents(&mut self, bounds: &Aabb, viewport: UVec2) {
+        let radius = bounds.radius().max(1e-3);
+        let aspect = aspect(viewport);
+        let forward = match self.forward() {
+            f if f == Vec3::ZERO => -self.up.vector(),
+            f => f,
+        };
+        let distance = match &mut self.projection {
+            Projection::Perspective { fov_y } => {
+                let half_fov_x = ((*fov_y * 0.5).tan() * aspect).atan();
+                radius / (*fov_y * 0.5).min(half_fov_x).sin()
+            }
+            Projection::Orthographic { height } => {
+                *height = 2.0 * radius / aspect.min(1.0);
+                radius * 2.0
+            }
+        };
+        self.target = bounds.center();
+        self.eye = self.target - forward * distance;
+        self.near = self.near.min((distance - radius) * 0.5).max(distance * 1e-4);
+        self.far = self.far.max((distance + radius) * 2.0);
+    }
+
+    /// Zooms so the pixel rectangle `min..max` fills the viewport.
+    pub fn zoom_window(&mut self, min: Vec2, max: Vec2, viewport: UVec2) {
+        let size = (max - min).abs();
+        let factor = (size.x / viewport.x.max(1) as f32).max(size.y / viewport.y.max(1) as f32);
+        if validate_factor(factor).is_err() {
+            return;
+        }
+        let center = self.target_plane_point((min + max) * 0.5, viewport);
+        let shift = center - self.target;
+        self.eye += shift;
+        self.target += shift;
+        self.scale(factor);
+    }
+}
+
+fn validate_factor(factor: f32) -> SceneResult<()> {
+    if !(factor > 0.0 && factor.is_finite()) {
+        return Err(SceneError::InvalidState("zoom factor must be positive and finite"));
+    }
+    Ok(())
+}
+
+fn aspect(viewport: UVec2) -> f32 {
+    viewport.x.max(1) as f32 / viewport.y.max(1) as f32
 }
@@ -0,0 +1,2 @@
+/// 8-bit RGBA colour, shared by scene state and the renderer.
+pub type Rgba = [u8; 4];
@@ -0,0 +1,77 @@
+//! Direction constraints on point input.
+//!
+//! Applied before snapping: the cursor point is projected onto a line through
+//! the base point, and the snap engine then only looks for points on that
+//! line. Ortho offers the screen's horizontal and vertical, Parallel the
+//! direction of a guide segment and Perpendicular the direction square to it
+//! on screen. With several on, the direction closest to the cursor wins.
+
+use glam::Vec3;
+
+use crate::scene::camera::Camera;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintKind {
+    Ortho,
+    Parallel,
+    Perpendicular,
+}
+
+/// Which constraints are on; all off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ConstraintSettings {
+    pub ortho: bool,
+    /// Needs a guide segment.
+    pub parallel: bool,
+    /// Needs a guide segment.
+    pub perpendicular: bool,
+}
+
+/// A point held to a line through the base point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constrained {
+    pub point: Vec3,
+    pub kind: ConstraintKind,
+    /// Unit direction of the line, pointing from the base towards the cursor.
+    pub direction: Vec3,
+}
+
+impl Constrained {
+    /// Distance from `point` to the constraint line through `base`.
+    pub fn distance_to_line(&self, base: Vec3, point: Vec3) -> f32 {
+        let offset = point - base;
+        (offset - self.direction * offset.dot(self.direction)).length()
+    }
+}
+
+/// Holds `raw` to the enabled direction through `base` closest to it, or
+/// `None` when no constraint applies or the cursor is on the base point.
+pub fn constrain(settings: &ConstraintSettings, camera: &Camera, base: Vec3, raw: Vec3, guide: Option<[Vec3; 2]>) -> Option<Constrained> {
+    let offset = raw - base;
+    if offset.length_squared() <= f32::EPSILON * base.length_squared().max(1.0) {
+        return None;
+    }
+    let guide = guide.map(|[a, b]| (b - a).normalize_or_zero()).filter(|d| *d != Vec3::ZERO);
+    let mut directions = Vec::new();
+    if settings.ortho {
+        directions.push((ConstraintKind::Ortho, camera.right()));
+        directions.push((ConstraintKind::Ortho, camera.screen_up()));
+    }
+    if let Some(guide) = guide {
+        if settings.parallel {
+            directions.push((ConstraintKind::Parallel, guide));
+        }
+        // Square to the guide as seen on screen; nothing when it is edge-on.
+        let across = guide.cross(camera.forward()).normalize_or_zero();
+        if settings.perpendicular && across != Vec3::ZERO {
+            directions.push((ConstraintKind::Perpendicular, across));
+        }
+    }
+    // Earlier directions win ties, so Ortho is kept when a guide shares its axis.
+    let (kind, direction) = directions.into_iter().reduce(|best, next| if next.1.dot(offset).abs() > best.1.dot(offset).abs() { next } else { best })?;
+    let along = direction.dot(offset);
+    let direction = if along < 0.0 { -direction } else { direction };
+    Some(Constrained { point: base + direction * along.abs(), kind, direction })
+}
@@ -1,27 +1,80 @@
-use glam::{Mat3, Mat4};
+use std::{collections::BTreeMap, ops::Range, time::Instant};
+
+use glam::{DVec3, Mat3, Mat4, Vec2, Vec3};
 
 use crate::scene::{
-    camera::CameraParams,
+    appearance::Appearance,
+    backend::{MeshPatch, SoftwareBackend},
+    bounds::Aabb,
+    camera::{Camera, CameraParams},
+    constraint::{self, ConstraintSettings},
+    decimate::{decimate, DecimateTarget, DecimationReport},
+    edges::{FeatureEdges, DEFAULT_CREASE_ANGLE},
     error::{SceneError, SceneResult},
+    export::{self, ExportOptions, GltfDocument},
+    frame::{FrameHistory, FrameRecord, SyncReport},
     id::EntityId,
+    import::{parse_obj, parse_stl, ImportedMesh},
+    linetype::{parse_lin, Linetype},
+    lod::{LodLevel, LodPolicy, MIN_LOD_ERROR},
+    mesh::{MeshData, Vertex},
+    overlay::{Overlay, OverlayHit, OverlayId, OverlayItem},
+    pick::{self, PickHit},
+    raster::RenderTarget,
+    section::{ClipPlane, Section},
     shape::KernelShape,
+    slice::{self, PlanCut, PlanSlice},
+    snap::{self, PointInputResult, SnapGeometry, SnapQuery, SnapSettings},
+    tracking::{self, GuideLine, Tracker, TrackingSettings},
     tessellation::TessParams,
-    transform::Transform,
-    visual::{DirtyFlags, VisualFlags},
+    transform::{FrontFace, Transform},
+    view::{StandardView, View},
+    viewport::{DisplayMode, Viewport, ViewportId},
+    visual::{DirtyFlags, HighlightStyle, VisualFlags},
     world::{EntityRecord, SceneWorld},
 };
 
 #[derive(Debug)]
 pub struct SceneContext {
     world: SceneWorld,
-    dirty: DirtyFlags,
+    /// Pending dirty categories per entity, resolved by `sync_gpu`.
+    dirty: BTreeMap<EntityId, DirtyFlags>,
+    /// Ranges behind a pending GEOMETRY mark; absent for full uploads.
+    patches: BTreeMap<EntityId, MeshPatch>,
+    affine_only: bool,
+    viewports: BTreeMap<ViewportId, Viewport>,
+    next_viewport: u32,
+    named_views: BTreeMap<String, Camera>,
+    backend: SoftwareBackend,
+    /// Transient items drawn over every viewport, outside the world.
+    overlay: Overlay,
+    snap_settings: SnapSettings,
+    constraint_settings: ConstraintSettings,
+    tracking_settings: TrackingSettings,
+    /// Points acquired for object snap tracking.
+    tracker: Tracker,
+    frames: FrameHistory,
+    next_frame: u64,
 }
 
 impl SceneContext {
     pub fn new() -> Self {
         Self {
             world: SceneWorld::new(),
-            dirty: DirtyFlags::empty(),
+            dirty: BTreeMap::new(),
+            patches: BTreeMap::new(),
+            affine_only: false,
+            viewports: BTreeMap::from([(ViewportId::MAIN, Viewport::new(View::default()))]),
+            next_viewport: ViewportId::MAIN.0 + 1,
+            named_views: BTreeMap::new(),
+            backend: SoftwareBackend::default(),
+            overlay: Overlay::default(),
+            snap_settings: SnapSettings::default(),
+            constraint_settings: ConstraintSettings::default(),
+            tracking_settings: TrackingSettings::default(),
+            tracker: Tracker::default(),
+            frames: FrameHistory::default(),
+            next_frame: 1,
         }
     }
 
@@ -35,85 +88,850 @@ impl SceneContext {
         if mesh.is_empty() {
             return Err(SceneError::ResourceMissing("tessellation produced empty mesh"));
         }
+        mesh.validate()?;
 
-        let record = EntityRecord {
+        let mut record = EntityRecord {
             visual: VisualFlags::empty(),
             transform: Transform::identity(),
             mesh: Some(mesh),
             model_matrix: Mat4::IDENTITY,
             normal_matrix: Mat3::IDENTITY,
+            front_face: FrontFace::Ccw,
+            appearance: Appearance::default(),
+            lods: Vec::new(),
+            origin: DVec3::ZERO,
         };
+        record.update_model(self.world.rebase_origin);
         self.world.entities.insert(entity_id, record);
-        self.dirty.insert(DirtyFlags::GEOMETRY | DirtyFlags::TRANSFORM | DirtyFlags::VISUAL);
+        self.mark_dirty(entity_id, DirtyFlags::all());
         Ok(entity_id)
     }
 
+    /// Submits each group of an OBJ file as a new entity, in file order.
+    /// Nothing is submitted on a parse error.
+    pub fn import_obj(&mut self, source: &str) -> SceneResult<Vec<(String, EntityId)>> {
+        self.submit_imported(parse_obj(source)?)
+    }
+
+    /// Submits each solid of a binary or ASCII STL file as a new entity.
+    pub fn import_stl(&mut self, bytes: &[u8]) -> SceneResult<Vec<(String, EntityId)>> {
+        self.submit_imported(parse_stl(bytes)?)
+    }
+
+    /// Submits each imported mesh as a new entity. Every mesh is checked
+    /// first, so either all become entities or none do.
+    pub(crate) fn submit_imported(&mut self, meshes: Vec<ImportedMesh>) -> SceneResult<Vec<(String, EntityId)>> {
+        for imported in &meshes {
+            imported.mesh.validate()?;
+        }
+        let mut submitted = Vec::with_capacity(meshes.len());
+        for imported in meshes {
+            let id = self.submit_shape(None, &imported, &TessParams::default())?;
+            submitted.push((imported.name, id));
+        }
+        Ok(submitted)
+    }
+
+    /// Overwrites vertices `range` of the entity's mesh in place, for live
+    /// previews. Only that range is uploaded on the next sync; levels of
+    /// detail no longer match and are dropped.
+    pub fn update_vertices(&mut self, id: EntityId, range: Range<usize>, vertices: &[Vertex]) -> SceneResult<()> {
+        let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        let mesh = record.mesh.as_mut().ok_or(SceneError::ResourceMissing("mesh"))?;
+        if range.start > range.end || range.end > mesh.vertices.len() {
+            return Err(SceneError::InvalidState("update range is outside the mesh"));
+        }
+        if vertices.len() != range.len() {
+            return Err(SceneError::InvalidState("update data does not match the range length"));
+        }
+        if !vertices.iter().all(Vertex::is_finite) {
+            return Err(SceneError::InvalidState("mesh vertex attributes must be finite"));
+        }
+        mesh.vertices[range.clone()].clone_from_slice(vertices);
+        let stale_lods = !std::mem::take(&mut record.lods).is_empty();
+        self.mark_patched(id, MeshPatch { vertices: Some(range), indices: None }, stale_lods);
+        Ok(())
+    }
+
+    /// Overwrites triangle indices `range` of the entity's mesh in place.
+    /// Levels of detail are dropped as with [`Self::update_vertices`].
+    pub fn update_indices(&mut self, id: EntityId, range: Range<usize>, indices: &[u32]) -> SceneResult<()> {
+        let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        let mesh = record.mesh.as_mut().ok_or(SceneError::ResourceMissing("mesh"))?;
+        if range.start > range.end || range.end > mesh.indices.len() {
+            return Err(SceneError::InvalidState("update range is outside the mesh"));
+        }
+        if indices.len() != range.len() {
+            return Err(SceneError::InvalidState("update data does not match the range length"));
+        }
+        if indices.iter().any(|&i| i as usize >= mesh.vertices.len()) {
+            return Err(SceneError::InvalidState("mesh index out of range"));
+        }
+        mesh.indices[range.clone()].copy_from_slice(indices);
+        let stale_lods = !std::mem::take(&mut record.lods).is_empty();
+        self.mark_patched(id, MeshPatch { vertices: None, indices: Some(range) }, stale_lods);
+        Ok(())
+    }
+
     pub fn remove(&mut self, id: EntityId) -> SceneResult<()> {
         let removed = self.world.entities.remove(&id);
         if removed.is_none() {
             return Err(SceneError::UnknownEntity(id.0));
         }
-        self.dirty.insert(DirtyFlags::GEOMETRY | DirtyFlags::TRANSFORM | DirtyFlags::VISUAL);
+        for viewport in self.viewports.values_mut() {
+            viewport.visibility.remove(&id);
+            viewport.lod.remove(&id);
+        }
+        self.mark_dirty(id, DirtyFlags::all());
+        Ok(())
+    }
+
+    /// Adds coarser levels of detail by re-tessellating `shape` with each of
+    /// `params`. Every level is checked first, so either all are added or none.
+    pub fn add_lods<T: KernelShape>(&mut self, id: EntityId, shape: &T, params: &[TessParams]) -> SceneResult<()> {
+        self.world.entities.get(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        let levels = params
+            .iter()
+            .map(|params| {
+                let mesh = shape.tessellate(params);
+                if mesh.is_empty() {
+                    return Err(SceneError::ResourceMissing("tessellation produced empty mesh"));
+                }
+                let level = LodLevel { mesh, error: params.max_error };
+                level.validate()?;
+                Ok(level)
+            })
+            .collect::<SceneResult<Vec<_>>>()?;
+        for level in levels {
+            self.insert_lod(id, level);
+        }
+        Ok(())
+    }
+
+    /// Adds a coarser level of detail, e.g. a decimated mesh, with the
+    /// geometric error it deviates from the finest mesh by.
+    pub fn add_lod_mesh(&mut self, id: EntityId, mesh: MeshData, error: f32) -> SceneResult<()> {
+        if !self.world.entities.contains_key(&id) {
+            return Err(SceneError::UnknownEntity(id.0));
+        }
+        let level = LodLevel { mesh, error };
+        level.validate()?;
+        self.insert_lod(id, level);
         Ok(())
     }
 
+    /// Adds a decimated copy of the entity's finest mesh as a level of
+    /// detail, with the error the decimator achieved but at least
+    /// [`MIN_LOD_ERROR`]. Nothing is added when no triangle could be removed.
+    pub fn add_decimated_lod(&mut self, id: EntityId, target: DecimateTarget) -> SceneResult<DecimationReport> {
+        let mesh = self.world.entities.get(&id).and_then(|record| record.mesh.as_ref()).ok_or(SceneError::UnknownEntity(id.0))?;
+        let (mesh, report) = decimate(mesh, target)?;
+        if report.triangles != report.triangles_before {
+            let level = LodLevel { mesh, error: report.error.max(MIN_LOD_ERROR) };
+            level.validate()?;
+            self.insert_lod(id, level);
+        }
+        Ok(report)
+    }
+
+    /// Inserts `level` by error into an existing entity's levels.
+    fn insert_lod(&mut self, id: EntityId, level: LodLevel) {
+        if let Some(record) = self.world.entities.get_mut(&id) {
+            let at = record.lods.partition_point(|existing| existing.error <= level.error);
+            record.lods.insert(at, level);
+            self.reset_lod(id);
+        }
+    }
+
+    pub fn clear_lods(&mut self, id: EntityId) -> SceneResult<()> {
+        let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        record.lods.clear();
+        self.reset_lod(id);
+        Ok(())
+    }
+
+    /// Errors of an entity's coarser levels, in level order starting at level 1.
+    pub fn lod_errors(&self, id: EntityId) -> SceneResult<Vec<f32>> {
+        let record = self.world.entities.get(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        Ok(record.lods.iter().map(|level| level.error).collect())
+    }
+
+    /// Level last drawn for an entity in a viewport; `0` is the finest.
+    pub fn drawn_lod(&self, viewport: ViewportId, id: EntityId) -> SceneResult<Option<usize>> {
+        Ok(self.viewport(viewport)?.lod.get(&id).copied())
+    }
+
+    pub fn lod_policy(&self) -> LodPolicy {
+        self.backend.lod_policy
+    }
+
+    pub fn set_lod_policy(&mut self, policy: LodPolicy) -> SceneResult<()> {
+        policy.validate()?;
+        self.backend.lod_policy = policy;
+        self.mark_viewports_stale();
+        Ok(())
+    }
+
+    /// Levels changed: forget the remembered selections and upload them.
+    fn reset_lod(&mut self, id: EntityId) {
+        for viewport in self.viewports.values_mut() {
+            viewport.lod.remove(&id);
+        }
+        self.mark_dirty(id, DirtyFlags::GEOMETRY);
+    }
+
     pub fn set_visibility(&mut self, id: EntityId, visible: bool) -> SceneResult<()> {
         let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
         record.visual = record.visual.visible(visible);
-        self.dirty.insert(DirtyFlags::VISUAL);
+        self.mark_dirty(id, DirtyFlags::VISUAL);
         Ok(())
     }
 
     pub fn set_highlight(&mut self, id: EntityId, highlighted: bool) -> SceneResult<()> {
         let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
         record.visual = record.visual.highlighted(highlighted);
-        self.dirty.insert(DirtyFlags::VISUAL);
+        self.mark_dirty(id, DirtyFlags::VISUAL);
         Ok(())
     }
 
     pub fn set_selected(&mut self, id: EntityId, selected: bool) -> SceneResult<()> {
         let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
         record.visual = record.visual.selected(selected);
-        self.dirty.insert(DirtyFlags::VISUAL);
+        self.mark_dirty(id, DirtyFlags::VISUAL);
+        Ok(())
+    }
+
+    /// Replaces resolved colour, opacity, linetype and lineweight; geometry is kept.
+    pub fn set_appearance(&mut self, id: EntityId, appearance: Appearance) -> SceneResult<()> {
+        let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        appearance.validate()?;
+        record.appearance = appearance;
+        self.mark_dirty(id, DirtyFlags::VISUAL);
+        Ok(())
+    }
+
+    pub fn highlight_style(&self) -> HighlightStyle {
+        self.backend.style
+    }
+
+    /// Replaces the selection/highlight style; every entity is restyled on the next Visual sync.
+    pub fn set_highlight_style(&mut self, style: HighlightStyle) -> SceneResult<()> {
+        if !style.outline_px.is_finite() || style.outline_px < 0.0 || !(0.0..=1.0).contains(&style.hover_tint) {
+            return Err(SceneError::InvalidState("highlight style out of range"));
+        }
+        self.backend.style = style;
+        let ids: Vec<EntityId> = self.world.entities.keys().copied().collect();
+        for id in ids {
+            self.mark_dirty(id, DirtyFlags::VISUAL);
+        }
         Ok(())
     }
 
     pub fn set_transform(&mut self, id: EntityId, transform: Transform) -> SceneResult<()> {
         let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        // Validate before touching the record so a rejected matrix leaves it intact.
+        transform.validate(self.affine_only)?;
+        let normal_matrix = transform.normal_matrix()?;
         record.transform = transform;
-        // model matrix is identical to transform.matrix for now
-        record.model_matrix = transform.matrix;
-        record.normal_matrix = Mat3::from_mat4(transform.matrix).inverse().transpose();
-        self.dirty.insert(DirtyFlags::TRANSFORM);
+        record.update_model(self.world.rebase_origin);
+        record.normal_matrix = normal_matrix;
+        record.front_face = transform.front_face();
+        self.mark_dirty(id, DirtyFlags::TRANSFORM);
         Ok(())
     }
 
-    pub fn render(&mut self, _camera: &CameraParams) -> SceneResult<()> {
-        if !self.dirty.is_empty() {
-            self.sync_gpu()?;
+    /// Places an entity's local origin at a world position kept in f64.
+    pub fn set_origin(&mut self, id: EntityId, origin: DVec3) -> SceneResult<()> {
+        if !origin.is_finite() {
+            return Err(SceneError::InvalidTransform("origin must be finite"));
+        }
+        let record = self.world.entities.get_mut(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        record.origin = origin;
+        record.update_model(self.world.rebase_origin);
+        self.mark_dirty(id, DirtyFlags::TRANSFORM);
+        Ok(())
+    }
+
+    pub fn origin(&self, id: EntityId) -> SceneResult<DVec3> {
+        Ok(self.world.entities.get(&id).ok_or(SceneError::UnknownEntity(id.0))?.origin)
+    }
+
+    pub fn rebase_origin(&self) -> DVec3 {
+        self.world.rebase_origin
+    }
+
+    /// Moves the origin of scene space, the f32 space cameras, sections,
+    /// picking and plans work in, to a world point near the work.
+    ///
+    /// Views and sections move with it, so the picture does not change.
+    pub fn set_rebase_origin(&mut self, origin: DVec3) -> SceneResult<()> {
+        if !origin.is_finite() {
+            return Err(SceneError::InvalidTransform("rebase origin must be finite"));
+        }
+        let shift = (self.world.rebase_origin - origin).as_vec3();
+        self.world.rebase_origin = origin;
+        let ids: Vec<EntityId> = self.world.entities.keys().copied().collect();
+        for id in ids {
+            if let Some(record) = self.world.entities.get_mut(&id) {
+                record.update_model(origin);
+            }
+            self.mark_dirty(id, DirtyFlags::TRANSFORM);
+        }
+        let moved = |mut camera: Camera| {
+            camera.eye += shift;
+            camera.target += shift;
+            camera
+        };
+        for viewport in self.viewports.values_mut() {
+            viewport.view.camera = moved(viewport.view.camera);
+        }
+        for camera in self.named_views.values_mut() {
+            *camera = moved(*camera);
+        }
+        let section = &mut self.backend.section;
+        for plane in &mut section.planes {
+            plane.distance += plane.normal.dot(shift);
+        }
+        if let Some(section_box) = &mut section.section_box {
+            section_box.center += shift;
+        }
+        self.mark_viewports_stale();
+        Ok(())
+    }
+
+    /// World point of a scene-space position.
+    pub fn to_world(&self, point: Vec3) -> DVec3 {
+        self.world.rebase_origin + point.as_dvec3()
+    }
+
+    /// Scene-space position of a world point; precise near the rebase origin.
+    pub fn to_scene(&self, point: DVec3) -> Vec3 {
+        (point - self.world.rebase_origin).as_vec3()
+    }
+
+    /// Restricts `set_transform` to affine matrices (bottom row `0, 0, 0, 1`).
+    pub fn set_affine_only(&mut self, affine_only: bool) {
+        self.affine_only = affine_only;
+    }
+
+    /// Renders the main viewport with an explicit camera.
+    pub fn render(&mut self, camera: &CameraParams) -> SceneResult<()> {
+        self.render_viewport_with(ViewportId::MAIN, camera)
+    }
+
+    /// Renders the current view; used when the caller supplies no camera.
+    pub fn render_current(&mut self) -> SceneResult<()> {
+        self.render_viewport(ViewportId::MAIN)
+    }
+
+    /// Renders a viewport with its own view.
+    pub fn render_viewport(&mut self, id: ViewportId) -> SceneResult<()> {
+        let camera = self.viewport(id)?.view.params();
+        self.render_viewport_with(id, &camera)
+    }
+
+    pub fn render_viewport_with(&mut self, id: ViewportId, camera: &CameraParams) -> SceneResult<()> {
+        self.viewport(id)?;
+        let started = Instant::now();
+        let sync = if !self.dirty.is_empty() {
+            let report = self.sync_gpu()?;
             debug_assert!(self.dirty.is_empty(), "dirty flags should be cleared before render");
+            report
+        } else {
+            SyncReport::default()
+        };
+        let sync_micros = if sync == SyncReport::default() { 0 } else { started.elapsed().as_micros() as u64 };
+        let viewport = self.viewports.get_mut(&id).ok_or(SceneError::UnknownViewport(id.0))?;
+        let started = Instant::now();
+        let mut stats = self.backend.draw(viewport, camera);
+        self.overlay.draw(&mut viewport.target, camera);
+        stats.sync_micros = sync_micros;
+        stats.raster_micros = started.elapsed().as_micros() as u64;
+        viewport.stale = false;
+        self.frames.push(FrameRecord {
+            frame_id: self.next_frame,
+            viewport: id,
+            camera: *camera,
+            sync,
+            stats,
+            image: None,
+        });
+        self.next_frame += 1;
+        Ok(())
+    }
+
+    /// PNG of a viewport, rendering it first if its target is out of date.
+    ///
+    /// The image is kept with the frame it shows.
+    pub fn screenshot(&mut self, id: ViewportId) -> SceneResult<Vec<u8>> {
+        let current = self.frames.latest_for(id).map(|frame| frame.frame_id);
+        let frame_id = match current {
+            Some(frame_id) if !self.viewport(id)?.stale && self.dirty.is_empty() => frame_id,
+            _ => {
+                self.render_viewport(id)?;
+                self.frames.latest().map(|frame| frame.frame_id).expect("render records a frame")
+            }
+        };
+        self.capture(frame_id)
+    }
+
+    /// PNG of a recent frame. Frames that were never captured can only be
+    /// fetched while they are still the latest frame of their viewport.
+    pub fn frame_screenshot(&mut self, frame_id: u64) -> SceneResult<Vec<u8>> {
+        let frame = self.frames.get(frame_id).ok_or(SceneError::UnknownFrame(frame_id))?;
+        if let Some(image) = &frame.image {
+            return Ok(image.clone());
         }
-        // GPU rendering to be implemented; placeholder ensures the call path exists.
+        let viewport = frame.viewport;
+        let is_latest = self.frames.latest_for(viewport).map(|latest| latest.frame_id) == Some(frame_id);
+        if !is_latest || !self.viewports.contains_key(&viewport) {
+            return Err(SceneError::ResourceMissing("frame image was not captured"));
+        }
+        self.capture(frame_id)
+    }
+
+    fn capture(&mut self, frame_id: u64) -> SceneResult<Vec<u8>> {
+        let frame = self.frames.get_mut(frame_id).ok_or(SceneError::UnknownFrame(frame_id))?;
+        if frame.image.is_none() {
+            let viewport = self.viewports.get(&frame.viewport).ok_or(SceneError::UnknownViewport(frame.viewport.0))?;
+            frame.image = Some(viewport.target.encode_png()?);
+        }
+        Ok(frame.image.clone().unwrap_or_default())
+    }
+
+    /// Most recently rendered frame across all viewports.
+    pub fn last_frame(&self) -> Option<&FrameRecord> {
+        self.frames.latest()
+    }
+
+    /// Most recently rendered frame of one viewport.
+    pub fn latest_frame(&self, id: ViewportId) -> Option<&FrameRecord> {
+        self.frames.latest_for(id)
+    }
+
+    pub fn frame(&self, frame_id: u64) -> SceneResult<&FrameRecord> {
+        self.frames.get(frame_id).ok_or(SceneError::UnknownFrame(frame_id))
+    }
+
+    /// Last rendered image of a viewport.
+    pub fn render_target(&self, id: ViewportId) -> SceneResult<&RenderTarget> {
+        Ok(&self.viewport(id)?.target)
+    }
+
+    pub fn create_viewport(&mut self, view: View) -> ViewportId {
+        let id = ViewportId(self.next_viewport);
+        self.next_viewport += 1;
+        self.viewports.insert(id, Viewport::new(view.capped()));
+        id
+    }
+
+    pub fn remove_viewport(&mut self, id: ViewportId) -> SceneResult<()> {
+        if id == ViewportId::MAIN {
+            return Err(SceneError::InvalidState("the main viewport cannot be removed"));
+        }
+        self.viewports.remove(&id).map(|_| ()).ok_or(SceneError::UnknownViewport(id.0))
+    }
+
+    pub fn viewport_ids(&self) -> Vec<ViewportId> {
+        self.viewports.keys().copied().collect()
+    }
+
+    pub fn viewport_view(&self, id: ViewportId) -> SceneResult<View> {
+        Ok(self.viewport(id)?.view)
+    }
+
+    pub fn set_viewport_view(&mut self, id: ViewportId, view: View) -> SceneResult<()> {
+        self.viewport_mut(id)?.view = view.capped();
         Ok(())
     }
 
-    pub fn sync_gpu(&mut self) -> SceneResult<()> {
-        // TODO: Implement Geometry->Transform->Visual incremental sync.
-        self.dirty = DirtyFlags::empty();
+    pub fn display_mode(&self, id: ViewportId) -> SceneResult<DisplayMode> {
+        Ok(self.viewport(id)?.display_mode)
+    }
+
+    pub fn set_display_mode(&mut self, id: ViewportId, mode: DisplayMode) -> SceneResult<()> {
+        self.viewport_mut(id)?.display_mode = mode;
         Ok(())
     }
 
+    pub fn set_back_face_culling(&mut self, id: ViewportId, enabled: bool) -> SceneResult<()> {
+        self.viewport_mut(id)?.cull_back_faces = enabled;
+        Ok(())
+    }
+
+    /// Edge passes with model edges only instead of every triangle edge.
+    pub fn set_feature_wireframe(&mut self, id: ViewportId, enabled: bool) -> SceneResult<()> {
+        self.viewport_mut(id)?.feature_wireframe = enabled;
+        Ok(())
+    }
+
+    /// Sets how many pixels one millimetre of lineweight covers in a viewport.
+    pub fn set_plot_scale(&mut self, id: ViewportId, pixels_per_mm: f32) -> SceneResult<()> {
+        if !pixels_per_mm.is_finite() || pixels_per_mm <= 0.0 {
+            return Err(SceneError::InvalidState("plot scale must be positive"));
+        }
+        self.viewport_mut(id)?.pixels_per_mm = pixels_per_mm;
+        Ok(())
+    }
+
+    /// Adds the linetypes of an AutoCAD `.lin` file, replacing same-named ones.
+    /// Returns the names defined by `source`; nothing is added on a parse error.
+    pub fn load_linetypes(&mut self, source: &str) -> SceneResult<Vec<String>> {
+        let linetypes = parse_lin(source)?;
+        let names = linetypes.iter().map(|linetype| linetype.name.clone()).collect();
+        for linetype in linetypes {
+            self.backend.linetypes.insert(linetype);
+        }
+        self.mark_viewports_stale();
+        Ok(names)
+    }
+
+    pub fn define_linetype(&mut self, linetype: Linetype) -> SceneResult<()> {
+        linetype.validate()?;
+        self.backend.linetypes.insert(linetype);
+        self.mark_viewports_stale();
+        Ok(())
+    }
+
+    pub fn linetype_names(&self) -> Vec<String> {
+        self.backend.linetypes.names().map(str::to_owned).collect()
+    }
+
+    pub fn section(&self) -> &Section {
+        &self.backend.section
+    }
+
+    /// Replaces clip planes, section box and cap style; affects rendering and picking.
+    pub fn set_section(&mut self, section: Section) -> SceneResult<()> {
+        section.validate()?;
+        self.backend.section = section;
+        self.mark_viewports_stale();
+        Ok(())
+    }
+
+    /// Adds one clip plane and returns its index.
+    pub fn add_clip_plane(&mut self, plane: ClipPlane) -> SceneResult<usize> {
+        let mut section = self.backend.section.clone();
+        section.planes.push(plane);
+        self.set_section(section)?;
+        Ok(self.backend.section.planes.len() - 1)
+    }
+
+    pub fn clear_section(&mut self) {
+        self.backend.section.planes.clear();
+        self.backend.section.section_box = None;
+        self.mark_viewports_stale();
+    }
+
+    /// Cuts the visible model horizontally for a floor plan.
+    pub fn slice_plan(&self, cut: &PlanCut) -> SceneResult<PlanSlice> {
+        cut.validate()?;
+        Ok(slice::slice_world(&self.world, cut))
+    }
+
+    /// Nearest surface under a pixel of a viewport, ignoring hidden and cut-away geometry.
+    pub fn pick(&self, id: ViewportId, cursor: Vec2) -> SceneResult<Option<PickHit>> {
+        let viewport = self.viewport(id)?;
+        let (origin, dir) = viewport.view.params().ray(cursor);
+        Ok(pick::pick(self.shown(viewport), &self.backend.section, origin, dir))
+    }
+
+    /// Entities a viewport draws: visible ones, subject to its overrides.
+    fn shown<'a>(&'a self, viewport: &'a Viewport) -> impl Iterator<Item = (EntityId, &'a EntityRecord)> {
+        self.world.entities.iter().map(|(entity, record)| (*entity, record)).filter(|(entity, record)| {
+            let visible = record.visual.contains(VisualFlags::VISIBLE);
+            viewport.visibility.get(entity).copied().unwrap_or(visible)
+        })
+    }
+
+    pub fn snap_settings(&self) -> SnapSettings {
+        self.snap_settings
+    }
+
+    pub fn set_snap_settings(&mut self, settings: SnapSettings) -> SceneResult<()> {
+        settings.validate()?;
+        self.snap_settings = settings;
+        Ok(())
+    }
+
+    pub fn constraint_settings(&self) -> ConstraintSettings {
+        self.constraint_settings
+    }
+
+    pub fn set_constraint_settings(&mut self, settings: ConstraintSettings) {
+        self.constraint_settings = settings;
+    }
+
+    pub fn tracking_settings(&self) -> TrackingSettings {
+        self.tracking_settings
+    }
+
+    pub fn set_tracking_settings(&mut self, settings: TrackingSettings) -> SceneResult<()> {
+        settings.validate()?;
+        self.tracking_settings = settings;
+        self.tracker.limit(settings.max_points);
+        Ok(())
+    }
+
+    /// Acquires a point, usually one just snapped to, for object tracking.
+    pub fn acquire_tracking_point(&mut self, point: Vec3) -> SceneResult<bool> {
+        if !point.is_finite() {
+            return Err(SceneError::InvalidState("tracking points must be finite"));
+        }
+        Ok(self.tracker.acquire(point, self.tracking_settings.max_points))
+    }
+
+    pub fn release_tracking_point(&mut self, point: Vec3) -> bool {
+        self.tracker.release(point)
+    }
+
+    /// Drops every acquired point, as a command does when it ends.
+    pub fn clear_tracking(&mut self) {
+        self.tracker.clear();
+    }
+
+    pub fn tracking_points(&self) -> &[Vec3] {
+        self.tracker.points()
+    }
+
+    /// Scene point for the cursor, constrained and snapped to nearby
+    /// geometry, or else placed by tracking. `from` is the previous point of
+    /// the command: the base of direction constraints and polar tracking,
+    /// what perpendicular and tangent snaps measure from, and the height of
+    /// the work plane. `guide` is the segment Parallel and Perpendicular
+    /// constraints follow.
+    pub fn point_input(&self, id: ViewportId, cursor: Vec2, from: Option<Vec3>, guide: Option<[Vec3; 2]>) -> SceneResult<PointInputResult> {
+        Ok(self.tracked_point_input(id, cursor, from, guide)?.0)
+    }
+
+    /// [`Self::point_input`] with the tracking rays the point was placed on,
+    /// empty unless tracking rather than a snap or constraint placed it.
+    pub fn tracked_point_input(&self, id: ViewportId, cursor: Vec2, from: Option<Vec3>, guide: Option<[Vec3; 2]>) -> SceneResult<(PointInputResult, Vec<GuideLine>)> {
+        let finite = |point: &Vec3| point.is_finite();
+        if !cursor.is_finite() || !from.iter().chain(guide.iter().flatten()).all(finite) {
+            return Err(SceneError::InvalidState("point input must be finite"));
+        }
+        let viewport = self.viewport(id)?;
+        let camera = viewport.view.params();
+        let raw = match self.pick(id, cursor)? {
+            Some(hit) => hit.point,
+            None => snap::work_plane_point(&camera, cursor, from),
+        };
+        let constrained = from.and_then(|base| constraint::constrain(&self.constraint_settings, &viewport.view.camera, base, raw, guide));
+        let query = SnapQuery { cursor, from, constrained };
+        let search = constrained.and_then(|line| camera.to_screen(line.point)).unwrap_or(cursor);
+        let geometry = SnapGeometry::gather(self.shown(viewport), &camera, search, self.snap_settings.aperture_px);
+        let mut result = snap::snap(&geometry, &camera, &self.snap_settings, &self.backend.section, &query, raw);
+        if result.snap_type.is_none() && result.constraint.is_none() {
+            if let Some(tracked) = tracking::track(&self.tracking_settings, &self.tracker, &viewport.view.camera, &camera, cursor, raw, from) {
+                result.snapped = tracked.point;
+                return Ok((result, tracked.guides));
+            }
+        }
+        Ok((result, Vec::new()))
+    }
+
+    /// Topmost overlay item under the cursor. Ordinary picks ignore the overlay.
+    pub fn pick_overlay(&self, id: ViewportId, cursor: Vec2) -> SceneResult<Option<OverlayHit>> {
+        let viewport = self.viewport(id)?;
+        Ok(self.overlay.pick(&viewport.view.params(), cursor))
+    }
+
+    pub fn overlay(&self) -> &Overlay {
+        &self.overlay
+    }
+
+    pub fn add_overlay(&mut self, item: OverlayItem) -> SceneResult<OverlayId> {
+        let id = self.overlay.add(item)?;
+        self.mark_viewports_stale();
+        Ok(id)
+    }
+
+    /// Replaces an item in place, e.g. a rubber band following the cursor.
+    pub fn update_overlay(&mut self, id: OverlayId, item: OverlayItem) -> SceneResult<()> {
+        self.overlay.replace(id, item)?;
+        self.mark_viewports_stale();
+        Ok(())
+    }
+
+    pub fn remove_overlay(&mut self, id: OverlayId) -> SceneResult<()> {
+        self.overlay.remove(id)?;
+        self.mark_viewports_stale();
+        Ok(())
+    }
+
+    /// Drops every overlay item, as a command does when it ends.
+    pub fn clear_overlay(&mut self) {
+        if !self.overlay.is_empty() {
+            self.overlay.clear();
+            self.mark_viewports_stale();
+        }
+    }
+
+    /// Overrides an entity's visibility in one viewport; `None` follows `VisualFlags::VISIBLE`.
+    pub fn set_viewport_visibility(&mut self, id: ViewportId, entity: EntityId, visible: Option<bool>) -> SceneResult<()> {
+        if !self.world.entities.contains_key(&entity) {
+            return Err(SceneError::UnknownEntity(entity.0));
+        }
+        let viewport = self.viewport_mut(id)?;
+        match visible {
+            Some(visible) => viewport.visibility.insert(entity, visible),
+            None => viewport.visibility.remove(&entity),
+        };
+        Ok(())
+    }
+
+    fn viewport(&self, id: ViewportId) -> SceneResult<&Viewport> {
+        self.viewports.get(&id).ok_or(SceneError::UnknownViewport(id.0))
+    }
+
+    /// Mutable access for settings changes; marks the target stale.
+    fn viewport_mut(&mut self, id: ViewportId) -> SceneResult<&mut Viewport> {
+        let viewport = self.viewports.get_mut(&id).ok_or(SceneError::UnknownViewport(id.0))?;
+        viewport.stale = true;
+        Ok(viewport)
+    }
+
+    pub fn current_view(&self) -> View {
+        self.viewports[&ViewportId::MAIN].view
+    }
+
+    pub fn set_current_view(&mut self, view: View) {
+        let _ = self.set_viewport_view(ViewportId::MAIN, view);
+    }
+
+    /// Stores the current camera under `name`. Standard view names are reserved.
+    pub fn save_view(&mut self, name: &str) -> SceneResult<()> {
+        if StandardView::from_name(name).is_some() {
+            return Err(SceneError::InvalidState("standard views cannot be overwritten"));
+        }
+        if name.is_empty() {
+            return Err(SceneError::InvalidState("view name must not be empty"));
+        }
+        self.named_views.insert(name.to_string(), self.current_view().camera);
+        Ok(())
+    }
+
+    /// Makes a named view current. Standard views are fitted to the visible scene.
+    pub fn restore_view(&mut self, name: &str) -> SceneResult<View> {
+        self.restore_viewport_view(ViewportId::MAIN, name)
+    }
+
+    /// Applies a named view to one viewport.
+    pub fn restore_viewport_view(&mut self, id: ViewportId, name: &str) -> SceneResult<View> {
+        let view = self.viewport(id)?.view;
+        let camera = match StandardView::from_name(name) {
+            Some(standard) => standard.camera(&view.camera, self.scene_bounds().as_ref(), view.viewport),
+            None => *self.named_views.get(name).ok_or_else(|| SceneError::UnknownView(name.to_string()))?,
+        };
+        let view = View::new(camera, view.viewport);
+        self.set_viewport_view(id, view)?;
+        Ok(view)
+    }
+
+    pub fn delete_view(&mut self, name: &str) -> SceneResult<()> {
+        if StandardView::from_name(name).is_some() {
+            return Err(SceneError::InvalidState("standard views cannot be deleted"));
+        }
+        self.named_views.remove(name).map(|_| ()).ok_or_else(|| SceneError::UnknownView(name.to_string()))
+    }
+
+    /// Standard view names followed by user views in name order.
+    pub fn view_names(&self) -> Vec<String> {
+        StandardView::ALL
+            .iter()
+            .map(|view| view.name().to_string())
+            .chain(self.named_views.keys().cloned())
+            .collect()
+    }
+
+    pub fn sync_gpu(&mut self) -> SceneResult<SyncReport> {
+        let report = self.backend.sync(&self.world, &self.dirty, &self.patches);
+        if !self.dirty.is_empty() {
+            self.mark_viewports_stale();
+        }
+        self.dirty.clear();
+        self.patches.clear();
+        Ok(report)
+    }
+
+    fn mark_viewports_stale(&mut self) {
+        for viewport in self.viewports.values_mut() {
+            viewport.stale = true;
+        }
+    }
+
+    fn mark_dirty(&mut self, id: EntityId, flags: DirtyFlags) {
+        if flags.contains(DirtyFlags::GEOMETRY) {
+            self.patches.remove(&id);
+        }
+        *self.dirty.entry(id).or_default() |= flags;
+    }
+
+    /// Marks GEOMETRY for an in-place change of `patch`, unless a full
+    /// upload is already pending or dropped levels of detail need one.
+    fn mark_patched(&mut self, id: EntityId, patch: MeshPatch, stale_lods: bool) {
+        if stale_lods {
+            self.reset_lod(id);
+            return;
+        }
+        let pending = self.dirty.get(&id).is_some_and(|flags| flags.contains(DirtyFlags::GEOMETRY));
+        let previous = self.patches.remove(&id);
+        self.mark_dirty(id, DirtyFlags::GEOMETRY);
+        if pending && previous.is_none() {
+            return;
+        }
+        let mut merged = previous.unwrap_or_default();
+        merged.merge(patch);
+        self.patches.insert(id, merged);
+    }
+
+    /// Boundary and crease edges of an entity's current mesh.
+    pub fn feature_edges(&self, id: EntityId) -> SceneResult<FeatureEdges> {
+        let record = self.world.entities.get(&id).ok_or(SceneError::UnknownEntity(id.0))?;
+        let mesh = record.mesh.as_ref().ok_or(SceneError::ResourceMissing("mesh"))?;
+        Ok(FeatureEdges::extract(mesh, DEFAULT_CREASE_ANGLE))
+    }
+
     pub fn get_state(&self, id: EntityId) -> SceneResult<EntityState> {
         let record = self.world.entities.get(&id).ok_or(SceneError::UnknownEntity(id.0))?;
         Ok(EntityState {
             visual: record.visual,
             transform: record.transform,
             has_mesh: record.mesh.is_some(),
+            mirrored: record.front_face == FrontFace::Cw,
+            appearance: record.appearance.clone(),
+            origin: record.origin,
         })
     }
 
+    /// glTF 2.0 document of the visible entities, or only the selected ones,
+    /// turned +Y up when the current view is Z-up.
+    pub fn export_gltf(&self, options: &ExportOptions) -> GltfDocument {
+        export::export_gltf(&self.world, self.current_view().camera.up, options)
+    }
+
+    /// World bounds of all visible entities, used by zoom extents.
+    pub fn scene_bounds(&self) -> Option<Aabb> {
+        self.bounds_matching(VisualFlags::VISIBLE)
+    }
+
+    /// World bounds of the visible, selected entities.
+    pub fn selection_bounds(&self) -> Option<Aabb> {
+        self.bounds_matching(VisualFlags::VISIBLE | VisualFlags::SELECTED)
+    }
+
+    fn bounds_matching(&self, flags: VisualFlags) -> Option<Aabb> {
+        self.world
+            .entities
+            .values()
+            .filter(|record| record.visual.contains(flags))
+            .filter_map(|record| record.world_bounds())
+            .reduce(|a, b| a.union(&b))
+    }
+
     pub fn dirty_flags(&self) -> DirtyFlags {
-        self.dirty
+        self.dirty.values().fold(DirtyFlags::empty(), |acc, flags| acc | *flags)
     }
 }
 
@@ -122,6 +940,9 @@ pub struct EntityState {
     pub visual: VisualFlags,
     pub transform: Transform,
     pub has_mesh: bool,
+    pub mirrored: bool,
+    pub appearance: Appearance,
+    pub origin: DVec3,
 }
 
 impl Default for SceneContext {
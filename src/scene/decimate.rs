@@ -0,0 +1,337 @@
+//! Edge-collapse simplification with quadric error metrics.
+//!
+//! Collapses are half-edge collapses on welded positions: a vertex moves
+//! onto a neighbour, so surviving vertices keep their exact position, normal
+//! and UV. A collapse is only allowed when every attribute vertex of the
+//! moving position has a matching one across the collapsed edge, which keeps
+//! normal and UV seams intact. Boundary and seam vertices may only slide
+//! along their boundary or seam, and extra quadrics perpendicular to those
+//! edges keep them in place.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use glam::{DMat4, DVec3};
+
+use crate::scene::{
+    error::{SceneError, SceneResult},
+    mesh::MeshData,
+};
+
+/// When to stop collapsing.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum DecimateTarget {
+    /// Collapse until at most this many triangles remain.
+    Triangles(usize),
+    /// Collapse while the error stays within this distance, e.g. `TessParams::max_error`.
+    MaxError(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DecimationReport {
+    pub triangles_before: usize,
+    pub triangles: usize,
+    /// Upper bound on how far any kept vertex lies from the original planes
+    /// around it, in mesh units.
+    pub error: f32,
+}
+
+/// Simplifies the triangles of `mesh`; polyline vertices are left untouched.
+pub fn decimate(mesh: &MeshData, target: DecimateTarget) -> SceneResult<(MeshData, DecimationReport)> {
+    let limit = match target {
+        DecimateTarget::Triangles(count) => (count, f64::INFINITY),
+        DecimateTarget::MaxError(error) if error.is_finite() && error >= 0.0 => (0, (error as f64).powi(2)),
+        DecimateTarget::MaxError(_) => return Err(SceneError::InvalidState("decimation error must be a non-negative distance")),
+    };
+    let mut state = Collapser::new(mesh);
+    let triangles_before = state.alive;
+    let worst = state.run(limit);
+    let report = DecimationReport { triangles_before, triangles: state.alive, error: worst.max(0.0).sqrt() as f32 };
+    Ok((state.into_mesh(mesh), report))
+}
+
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    cost: f64,
+    from: u32,
+    to: u32,
+    versions: [u32; 2],
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    // Reversed for a min-heap; ties go to lower indices for determinism.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| (other.from, other.to).cmp(&(self.from, self.to)))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+struct Collapser {
+    /// Welded position of each mesh vertex.
+    position_of: Vec<u32>,
+    positions: Vec<DVec3>,
+    quadrics: Vec<DMat4>,
+    versions: Vec<u32>,
+    /// Positions used by polylines, never moved.
+    locked: Vec<bool>,
+    /// Triangles as mesh vertex indices, `None` once collapsed away.
+    triangles: Vec<Option<[u32; 3]>>,
+    faces_of: Vec<Vec<usize>>,
+    alive: usize,
+}
+
+fn plane_quadric(normal: DVec3, point: DVec3) -> DMat4 {
+    let plane = normal.extend(-normal.dot(point));
+    DMat4::from_cols(plane * plane.x, plane * plane.y, plane * plane.z, plane * plane.w)
+}
+
+fn face_normal(p: [DVec3; 3]) -> DVec3 {
+    (p[1] - p[0]).cross(p[2] - p[0])
+}
+
+impl Collapser {
+    fn new(mesh: &MeshData) -> Self {
+        let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut positions = Vec::new();
+        let position_of: Vec<u32> = mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                *welded.entry(v.position.to_array().map(f32::to_bits)).or_insert_with(|| {
+                    positions.push(v.position.as_dvec3());
+                    positions.len() as u32 - 1
+                })
+            })
+            .collect();
+        let mut locked = vec![false; positions.len()];
+        for &i in mesh.polylines.iter().flatten() {
+            if let Some(&p) = position_of.get(i as usize) {
+                locked[p as usize] = true;
+            }
+        }
+        let mut state = Self {
+            quadrics: vec![DMat4::ZERO; positions.len()],
+            versions: vec![0; positions.len()],
+            faces_of: vec![Vec::new(); positions.len()],
+            position_of,
+            positions,
+            locked,
+            triangles: Vec::new(),
+            alive: 0,
+        };
+        for tri in mesh.indices.chunks_exact(3) {
+            let tri = [tri[0], tri[1], tri[2]];
+            if tri.iter().any(|&i| i as usize >= mesh.vertices.len()) {
+                continue;
+            }
+            let p = tri.map(|i| state.position_of[i as usize]);
+            if p[0] == p[1] || p[1] == p[2] || p[2] == p[0] {
+                continue;
+            }
+            let face = state.triangles.len();
+            state.triangles.push(Some(tri));
+            state.alive += 1;
+            for p in p {
+                state.faces_of[p as usize].push(face);
+            }
+            let normal = face_normal(p.map(|p| state.positions[p as usize])).normalize_or_zero();
+            for p in p {
+                state.quadrics[p as usize] += plane_quadric(normal, state.positions[p as usize]);
+            }
+        }
+        state.add_edge_constraints();
+        state
+    }
+
+    fn pos(&self, vertex: u32) -> u32 {
+        self.position_of[vertex as usize]
+    }
+
+    fn faces(&self, p: u32) -> impl Iterator<Item = (usize, [u32; 3])> + '_ {
+        self.faces_of[p as usize].iter().filter_map(|&f| self.triangles[f].map(|tri| (f, tri)))
+    }
+
+    /// Faces around position `a` that also touch position `b`.
+    fn shared(&self, a: u32, b: u32) -> Vec<(usize, [u32; 3])> {
+        self.faces(a).filter(|(_, tri)| tri.iter().any(|&v| self.pos(v) == b)).collect()
+    }
+
+    fn neighbours(&self, p: u32) -> HashSet<u32> {
+        self.faces(p).flat_map(|(_, tri)| tri).map(|v| self.pos(v)).filter(|&q| q != p).collect()
+    }
+
+    /// Boundary edges (one face) and seam edges (two faces disagreeing on
+    /// an endpoint's attributes) get planes through the edge, perpendicular
+    /// to the surface, so that sliding off them costs error.
+    fn add_edge_constraints(&mut self) {
+        let mut edges: HashMap<[u32; 2], Vec<[u32; 3]>> = HashMap::new();
+        for tri in self.triangles.iter().flatten() {
+            for k in 0..3 {
+                let (a, b) = (self.pos(tri[k]), self.pos(tri[(k + 1) % 3]));
+                edges.entry([a.min(b), a.max(b)]).or_default().push(*tri);
+            }
+        }
+        let mut edges: Vec<_> = edges.into_iter().collect();
+        // Fixed summation order keeps the result deterministic.
+        edges.sort_unstable_by_key(|(key, _)| *key);
+        for ([a, b], faces) in edges {
+            let corner = |tri: &[u32; 3], p: u32| tri.iter().copied().find(|&v| self.pos(v) == p);
+            let constrained = match &faces[..] {
+                [_] => true,
+                [f, g] => corner(f, a) != corner(g, a) || corner(f, b) != corner(g, b),
+                _ => false,
+            };
+            if !constrained {
+                continue;
+            }
+            let (pa, pb) = (self.positions[a as usize], self.positions[b as usize]);
+            for tri in &faces {
+                let normal = face_normal(tri.map(|v| self.positions[self.pos(v) as usize]));
+                let across = (pb - pa).cross(normal).normalize_or_zero();
+                let quadric = plane_quadric(across, pa);
+                self.quadrics[a as usize] += quadric;
+                self.quadrics[b as usize] += quadric;
+            }
+        }
+    }
+
+    fn cost(&self, from: u32, to: u32) -> f64 {
+        let x = self.positions[to as usize].extend(1.0);
+        let quadric = self.quadrics[from as usize] + self.quadrics[to as usize];
+        x.dot(quadric * x)
+    }
+
+    fn candidate(&self, from: u32, to: u32) -> Candidate {
+        Candidate { cost: self.cost(from, to), from, to, versions: [self.versions[from as usize], self.versions[to as usize]] }
+    }
+
+    /// Mesh vertex each attribute vertex of `from` becomes, if the collapse is allowed.
+    fn remap(&self, from: u32, to: u32) -> Option<HashMap<u32, u32>> {
+        if self.locked[from as usize] {
+            return None;
+        }
+        let shared = self.shared(from, to);
+        // Non-manifold edges are never collapsed.
+        if shared.is_empty() || shared.len() > 2 {
+            return None;
+        }
+        let mut map = HashMap::new();
+        for (_, tri) in &shared {
+            let a = tri.iter().copied().find(|&v| self.pos(v) == from)?;
+            let b = tri.iter().copied().find(|&v| self.pos(v) == to)?;
+            if *map.entry(a).or_insert(b) != b {
+                return None;
+            }
+        }
+        // Every attribute vertex of `from` needs a partner across the edge;
+        // this keeps seams, and boundary vertices on their boundary.
+        let boundary = |a: u32, b: u32| self.shared(a, b).len() == 1;
+        let on_boundary = self.neighbours(from).into_iter().any(|q| boundary(from, q));
+        if on_boundary && !boundary(from, to) {
+            return None;
+        }
+        if self.faces(from).any(|(_, tri)| tri.iter().any(|&v| self.pos(v) == from && !map.contains_key(&v))) {
+            return None;
+        }
+        // Link condition: only the faces on the edge may pinch together.
+        let common = self.neighbours(from).intersection(&self.neighbours(to)).count();
+        if common != shared.len() {
+            return None;
+        }
+        // Moved faces must not flip or collapse to slivers.
+        let target = self.positions[to as usize];
+        for (_, tri) in self.faces(from).filter(|(f, _)| !shared.iter().any(|(g, _)| g == f)) {
+            let before = tri.map(|v| self.positions[self.pos(v) as usize]);
+            let after = tri.map(|v| if self.pos(v) == from { target } else { self.positions[self.pos(v) as usize] });
+            let (n0, n1) = (face_normal(before), face_normal(after));
+            if n1.dot(n0) <= 0.0 || n1.length_squared() <= 1e-12 * n0.length_squared() {
+                return None;
+            }
+        }
+        Some(map)
+    }
+
+    fn collapse(&mut self, from: u32, to: u32, map: &HashMap<u32, u32>) {
+        for f in std::mem::take(&mut self.faces_of[from as usize]) {
+            let Some(tri) = self.triangles[f] else {
+                continue;
+            };
+            if tri.iter().any(|&v| self.pos(v) == to) {
+                self.triangles[f] = None;
+                self.alive -= 1;
+            } else {
+                self.triangles[f] = Some(tri.map(|v| map.get(&v).copied().unwrap_or(v)));
+                self.faces_of[to as usize].push(f);
+            }
+        }
+        self.quadrics[to as usize] = self.quadrics[to as usize] + self.quadrics[from as usize];
+        self.versions[to as usize] += 1;
+        self.versions[from as usize] += 1;
+    }
+
+    /// Collapses cheapest-first; returns the largest cost accepted.
+    fn run(&mut self, (min_triangles, max_cost): (usize, f64)) -> f64 {
+        let mut heap = BinaryHeap::new();
+        for p in 0..self.positions.len() as u32 {
+            for q in self.neighbours(p) {
+                heap.push(self.candidate(p, q));
+            }
+        }
+        let mut worst: f64 = 0.0;
+        while self.alive > min_triangles {
+            let Some(candidate) = heap.pop() else {
+                break;
+            };
+            if candidate.versions != [self.versions[candidate.from as usize], self.versions[candidate.to as usize]] {
+                continue;
+            }
+            if candidate.cost > max_cost {
+                break;
+            }
+            let Some(map) = self.remap(candidate.from, candidate.to) else {
+                continue;
+            };
+            self.collapse(candidate.from, candidate.to, &map);
+            worst = worst.max(candidate.cost);
+            let to = candidate.to;
+            for q in self.neighbours(to) {
+                heap.push(self.candidate(to, q));
+                heap.push(self.candidate(q, to));
+            }
+        }
+        worst
+    }
+
+    /// Surviving triangles and polylines over the vertices they still use.
+    fn into_mesh(self, mesh: &MeshData) -> MeshData {
+        let mut index = vec![u32::MAX; mesh.vertices.len()];
+        let mut out = MeshData::default();
+        let mut keep = |v: u32, out: &mut MeshData| {
+            if index[v as usize] == u32::MAX {
+                index[v as usize] = out.vertices.len() as u32;
+                out.vertices.push(mesh.vertices[v as usize].clone());
+            }
+            index[v as usize]
+        };
+        for tri in self.triangles.iter().flatten() {
+            for &v in tri {
+                let i = keep(v, &mut out);
+                out.indices.push(i);
+            }
+        }
+        for polyline in &mesh.polylines {
+            let kept = polyline.iter().filter(|&&v| (v as usize) < mesh.vertices.len()).map(|&v| keep(v, &mut out)).collect();
+            out.polylines.push(kept);
+        }
+        out
+    }
+}
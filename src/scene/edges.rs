@@ -0,0 +1,120 @@
+//! Model edges extracted from triangle meshes for CAD-style outlines.
+//!
+//! Tessellators split vertices along seams (one copy per normal), so edges
+//! are found on positions welded by exact value rather than on indices.
+
+use std::collections::HashMap;
+
+use glam::{Vec3, Vec4};
+
+use crate::scene::mesh::MeshData;
+
+/// Dihedral angle (radians) above which a shared edge counts as a crease.
+pub const DEFAULT_CREASE_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
+
+/// Edge between two mesh vertices, as indices into `MeshData::vertices`.
+pub type Edge = [u32; 2];
+
+/// Edge shared by exactly two faces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ManifoldEdge {
+    edge: Edge,
+    faces: [usize; 2],
+    crease: bool,
+}
+
+/// View-independent edges of one mesh plus what silhouettes need.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureEdges {
+    /// Edges used by one triangle, or by more than two (non-manifold).
+    pub boundary: Vec<Edge>,
+    /// Edges whose adjacent faces meet at more than the crease angle.
+    pub crease: Vec<Edge>,
+    manifold: Vec<ManifoldEdge>,
+    /// Per-triangle face normal and a point on the face.
+    faces: Vec<(Vec3, Vec3)>,
+}
+
+impl FeatureEdges {
+    pub fn extract(mesh: &MeshData, crease_angle: f32) -> Self {
+        let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+        let weld: Vec<u32> = mesh
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| *welded.entry(v.position.to_array().map(f32::to_bits)).or_insert(i as u32))
+            .collect();
+
+        let mut faces = Vec::with_capacity(mesh.indices.len() / 3);
+        // Welded edge -> (first original edge, adjacent faces).
+        let mut adjacency: HashMap<Edge, (Edge, Vec<usize>)> = HashMap::new();
+        for tri in mesh.indices.chunks_exact(3) {
+            let Some(p) = tri.iter().map(|&i| mesh.vertices.get(i as usize).map(|v| v.position)).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            let normal = (p[1] - p[0]).cross(p[2] - p[0]).normalize_or_zero();
+            if normal == Vec3::ZERO {
+                continue;
+            }
+            let face = faces.len();
+            faces.push((normal, p[0]));
+            for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+                let (wa, wb) = (weld[a as usize], weld[b as usize]);
+                if wa == wb {
+                    continue;
+                }
+                let entry = adjacency.entry([wa.min(wb), wa.max(wb)]).or_insert(([a, b], Vec::new()));
+                entry.1.push(face);
+            }
+        }
+
+        let cos_crease = crease_angle.cos();
+        let mut edges = Self { faces, ..Self::default() };
+        let mut sorted: Vec<_> = adjacency.into_iter().collect();
+        sorted.sort_unstable_by_key(|(key, _)| *key);
+        for (_, (edge, adjacent)) in sorted {
+            let [a, b] = adjacent[..] else {
+                edges.boundary.push(edge);
+                continue;
+            };
+            let crease = edges.faces[a].0.dot(edges.faces[b].0) < cos_crease;
+            if crease {
+                edges.crease.push(edge);
+            }
+            edges.manifold.push(ManifoldEdge { edge, faces: [a, b], crease });
+        }
+        edges
+    }
+
+    /// Smooth edges between a face turned towards `viewer` and one turned away.
+    ///
+    /// `viewer` is in the mesh's local space: a point (`w = 1`) for
+    /// perspective cameras, a direction towards the viewer (`w = 0`) otherwise.
+    pub fn silhouette(&self, viewer: Vec4) -> Vec<Edge> {
+        self.turning(viewer).filter(|m| !m.crease).map(|m| m.edge).collect()
+    }
+
+    /// Outer contour as seen from `viewer`: boundary edges plus every
+    /// manifold edge, crease or not, where the surface turns away.
+    pub fn contour(&self, viewer: Vec4) -> Vec<Edge> {
+        let mut edges = self.boundary.clone();
+        edges.extend(self.turning(viewer).map(|m| m.edge));
+        edges
+    }
+
+    fn turning(&self, viewer: Vec4) -> impl Iterator<Item = &ManifoldEdge> {
+        let facing = move |face: usize| {
+            let (normal, point) = self.faces[face];
+            normal.dot(viewer.truncate() - point * viewer.w) > 0.0
+        };
+        self.manifold.iter().filter(move |m| facing(m.faces[0]) != facing(m.faces[1]))
+    }
+
+    /// Boundary, crease and silhouette edges as seen from `viewer`.
+    pub fn outline(&self, viewer: Vec4) -> Vec<Edge> {
+        let mut edges = self.boundary.clone();
+        edges.extend_from_slice(&self.crease);
+        edges.extend(self.silhouette(viewer));
+        edges
+    }
+}
@@ -4,10 +4,24 @@ use thiserror::Error;
 pub enum SceneError {
     #[error("unknown entity: {0}")]
     UnknownEntity(u64),
+    #[error("unknown viewport: {0}")]
+    UnknownViewport(u32),
+    #[error("unknown frame: {0}")]
+    UnknownFrame(u64),
+    #[error("unknown overlay item: {0}")]
+    UnknownOverlay(u32),
+    #[error("unknown view: {0}")]
+    UnknownView(String),
     #[error("resource missing: {0}")]
     ResourceMissing(&'static str),
     #[error("invalid state: {0}")]
     InvalidState(&'static str),
+    #[error("invalid transform: {0}")]
+    InvalidTransform(&'static str),
+    #[error("parse error at line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("parse error at byte {offset}: {message}")]
+    ParseBinary { offset: usize, message: String },
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("backend error: {0}")]
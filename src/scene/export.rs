@@ -0,0 +1,236 @@
+//! glTF 2.0 export of the world.
+//!
+//! Every exported entity becomes a node with its model matrix. Geometry is
+//! written once per distinct mesh and materials once per distinct colour and
+//! opacity, so repeated parts stay small. Nodes are placed in scene space
+//! under a root node that carries the rebase origin, since glTF itself is
+//! f32 throughout, and a Z-up scene is turned to glTF's +Y up by an outer
+//! root node.
+
+use std::collections::HashMap;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use glam::{Mat4, Quat, Vec3};
+use serde_json::{json, Value};
+
+use crate::scene::{
+    appearance::Appearance,
+    camera::UpAxis,
+    mesh::MeshData,
+    visual::VisualFlags,
+    world::SceneWorld,
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const MODE_LINES: u32 = 1;
+const MODE_TRIANGLES: u32 = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExportOptions {
+    /// Export only visible entities that are also selected.
+    #[serde(default)]
+    pub selected_only: bool,
+}
+
+/// A glTF document and its single binary buffer.
+#[derive(Debug, Clone)]
+pub struct GltfDocument {
+    /// The glTF JSON, with the buffer's `uri` left out.
+    pub json: Value,
+    pub bin: Vec<u8>,
+}
+
+impl GltfDocument {
+    /// `.gltf` JSON referring to the buffer written separately at `bin_uri`.
+    pub fn to_gltf(&self, bin_uri: &str) -> String {
+        self.with_uri(bin_uri)
+    }
+
+    /// `.gltf` JSON with the buffer embedded as a data URI.
+    pub fn to_gltf_embedded(&self) -> String {
+        self.with_uri(&format!("data:application/octet-stream;base64,{}", BASE64_STANDARD.encode(&self.bin)))
+    }
+
+    fn with_uri(&self, uri: &str) -> String {
+        let mut json = self.json.clone();
+        if let Some(buffer) = json["buffers"].get_mut(0) {
+            buffer["uri"] = json!(uri);
+        }
+        json.to_string()
+    }
+
+    /// Binary `.glb` container: header, JSON chunk, then BIN chunk.
+    pub fn to_glb(&self) -> Vec<u8> {
+        let pad = |mut chunk: Vec<u8>, fill: u8| {
+            chunk.resize(chunk.len().next_multiple_of(4), fill);
+            chunk
+        };
+        let json = pad(self.json.to_string().into_bytes(), b' ');
+        let bin = pad(self.bin.clone(), 0);
+        let mut chunks = vec![(0x4E4F_534A_u32, json)];
+        if !bin.is_empty() {
+            chunks.push((0x004E_4942, bin));
+        }
+        let length = 12 + chunks.iter().map(|(_, data)| 8 + data.len()).sum::<usize>();
+        let mut glb = Vec::with_capacity(length);
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((length as u32).to_le_bytes());
+        for (kind, data) in chunks {
+            glb.extend((data.len() as u32).to_le_bytes());
+            glb.extend(kind.to_le_bytes());
+            glb.extend(data);
+        }
+        glb
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    bin: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Writer {
+    fn accessor(&mut self, bytes: &[u8], target: u32, component: u32, kind: &str, count: usize, bounds: Option<(Vec3, Vec3)>) -> usize {
+        self.buffer_views.push(json!({ "buffer": 0, "byteOffset": self.bin.len(), "byteLength": bytes.len(), "target": target }));
+        self.bin.extend_from_slice(bytes);
+        let mut accessor = json!({ "bufferView": self.buffer_views.len() - 1, "componentType": component, "count": count, "type": kind });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min.to_array());
+            accessor["max"] = json!(max.to_array());
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Attribute and index accessors of `mesh`, as glTF primitives without a material.
+    fn geometry(&mut self, mesh: &MeshData) -> Vec<Value> {
+        let floats = |values: &mut dyn Iterator<Item = f32>| values.flat_map(f32::to_le_bytes).collect::<Vec<u8>>();
+        let count = mesh.vertices.len();
+        let bounds = mesh.bounds().map(|bounds| (bounds.min, bounds.max));
+        let mut attributes = json!({
+            "POSITION": self.accessor(&floats(&mut mesh.vertices.iter().flat_map(|v| v.position.to_array())), ARRAY_BUFFER, FLOAT, "VEC3", count, bounds),
+            "NORMAL": self.accessor(&floats(&mut mesh.vertices.iter().flat_map(|v| v.normal.to_array())), ARRAY_BUFFER, FLOAT, "VEC3", count, None),
+        });
+        // Texture coordinates only when every vertex has them.
+        if let Some(uvs) = mesh.vertices.iter().map(|v| v.uv).collect::<Option<Vec<_>>>() {
+            attributes["TEXCOORD_0"] = json!(self.accessor(&floats(&mut uvs.iter().flat_map(|uv| uv.to_array())), ARRAY_BUFFER, FLOAT, "VEC2", count, None));
+        }
+        let mut indices = |indices: &[u32]| self.accessor(&indices.iter().flat_map(|i| i.to_le_bytes()).collect::<Vec<_>>(), ELEMENT_ARRAY_BUFFER, UNSIGNED_INT, "SCALAR", indices.len(), None);
+        let mut primitives = Vec::new();
+        if !mesh.indices.is_empty() {
+            primitives.push(json!({ "attributes": attributes, "indices": indices(&mesh.indices), "mode": MODE_TRIANGLES }));
+        }
+        let lines: Vec<u32> = mesh.polylines.iter().flat_map(|polyline| polyline.windows(2).flatten().copied()).collect();
+        if !lines.is_empty() {
+            primitives.push(json!({ "attributes": attributes, "indices": indices(&lines), "mode": MODE_LINES }));
+        }
+        primitives
+    }
+}
+
+/// Bit pattern of everything written for a mesh, to find identical ones.
+fn mesh_key(mesh: &MeshData) -> Vec<u32> {
+    let vertices = mesh.vertices.iter().flat_map(|v| {
+        let uv = v.uv.map_or([u32::MAX; 2], |uv| uv.to_array().map(f32::to_bits));
+        v.position.to_array().into_iter().chain(v.normal.to_array()).map(f32::to_bits).chain(uv)
+    });
+    let polylines = mesh.polylines.iter().flat_map(|polyline| std::iter::once(u32::MAX).chain(polyline.iter().copied()));
+    [mesh.vertices.len() as u32, mesh.indices.len() as u32].into_iter().chain(vertices).chain(mesh.indices.iter().copied()).chain(polylines).collect()
+}
+
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn material(appearance: &Appearance) -> Value {
+    let [r, g, b] = appearance.color.map(srgb_to_linear);
+    let mut material = json!({
+        "pbrMetallicRoughness": { "baseColorFactor": [r, g, b, appearance.opacity], "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+        "doubleSided": true,
+    });
+    if !appearance.is_opaque() {
+        material["alphaMode"] = json!("BLEND");
+    }
+    material
+}
+
+/// Writes the visible entities of `world`, in id order, for a scene with `up` up.
+pub(crate) fn export_gltf(world: &SceneWorld, up: UpAxis, options: &ExportOptions) -> GltfDocument {
+    let flags = if options.selected_only { VisualFlags::VISIBLE | VisualFlags::SELECTED } else { VisualFlags::VISIBLE };
+    let mut ids: Vec<_> = world.entities.iter().filter(|(_, record)| record.visual.contains(flags) && record.mesh.is_some()).map(|(id, _)| *id).collect();
+    ids.sort_unstable();
+
+    let mut writer = Writer::default();
+    let mut geometries: HashMap<Vec<u32>, Vec<Value>> = HashMap::new();
+    let mut materials: Vec<Value> = Vec::new();
+    let mut meshes: Vec<Value> = Vec::new();
+    let mut mesh_index: HashMap<(Vec<u32>, usize), usize> = HashMap::new();
+    let mut nodes: Vec<Value> = Vec::new();
+    for id in ids {
+        let record = &world.entities[&id];
+        let Some(mesh) = &record.mesh else {
+            continue;
+        };
+        let key = mesh_key(mesh);
+        let material = material(&record.appearance);
+        let material = materials.iter().position(|existing| *existing == material).unwrap_or_else(|| {
+            materials.push(material);
+            materials.len() - 1
+        });
+        let primitives = geometries.entry(key.clone()).or_insert_with(|| writer.geometry(mesh));
+        let mesh = *mesh_index.entry((key, material)).or_insert_with(|| {
+            let primitives: Vec<Value> = primitives
+                .iter()
+                .map(|primitive| {
+                    let mut primitive = primitive.clone();
+                    primitive["material"] = json!(material);
+                    primitive
+                })
+                .collect();
+            meshes.push(json!({ "primitives": primitives }));
+            meshes.len() - 1
+        });
+        let mut node = json!({ "name": format!("entity {}", id.0), "mesh": mesh, "extras": { "entity_id": id.0 } });
+        if record.model_matrix != Mat4::IDENTITY {
+            node["matrix"] = json!(record.model_matrix.to_cols_array());
+        }
+        nodes.push(node);
+    }
+
+    let mut roots: Vec<usize> = (0..nodes.len()).collect();
+    if world.rebase_origin != glam::DVec3::ZERO {
+        // f64 components, so the origin keeps its precision in the JSON.
+        nodes.push(json!({ "name": "rebase origin", "translation": world.rebase_origin.to_array(), "children": roots }));
+        roots = vec![nodes.len() - 1];
+    }
+    if up == UpAxis::Z {
+        let rotation = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+        nodes.push(json!({ "name": "z up to y up", "rotation": rotation.to_array(), "children": roots }));
+        roots = vec![nodes.len() - 1];
+    }
+    let mut json = json!({
+        "asset": { "version": "2.0", "generator": concat!("rendering-core ", env!("CARGO_PKG_VERSION")) },
+        "scene": 0,
+        "scenes": [{ "nodes": roots }],
+        "nodes": nodes,
+    });
+    if !meshes.is_empty() {
+        json["meshes"] = json!(meshes);
+        json["materials"] = json!(materials);
+        json["accessors"] = json!(writer.accessors);
+        json["bufferViews"] = json!(writer.buffer_views);
+        json["buffers"] = json!([{ "byteLength": writer.bin.len() }]);
+    }
+    GltfDocument { json, bin: writer.bin }
+}
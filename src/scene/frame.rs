@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use crate::scene::{camera::CameraParams, viewport::ViewportId};
+
+/// Number of recent frames kept for `/api/screenshot?frame_id=`.
+pub const FRAME_HISTORY_LEN: usize = 16;
+
+/// Entities uploaded to the backend per dirty category during one sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SyncReport {
+    pub geometry: usize,
+    pub transform: usize,
+    pub visual: usize,
+    /// Vertex and index bytes copied into backend buffers.
+    #[serde(default)]
+    pub uploaded_bytes: usize,
+}
+
+/// What the backend drew for one frame, and how long it took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FrameStats {
+    /// Entities shown in the viewport before culling.
+    pub entities_considered: usize,
+    /// Entities whose world bounds lie outside the frustum or the section.
+    pub entities_culled: usize,
+    pub entities_drawn: usize,
+    /// Triangles and line segments handed to the rasterizer.
+    pub triangles: usize,
+    pub lines: usize,
+    /// Time spent in the sync before drawing, zero when nothing was dirty.
+    pub sync_micros: u64,
+    pub raster_micros: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameRecord {
+    pub frame_id: u64,
+    pub viewport: ViewportId,
+    pub camera: CameraParams,
+    pub sync: SyncReport,
+    pub stats: FrameStats,
+    /// PNG captured by a screenshot of this frame, if any.
+    pub image: Option<Vec<u8>>,
+}
+
+/// Ring buffer of the most recent frames across all viewports.
+#[derive(Debug, Default)]
+pub(crate) struct FrameHistory {
+    frames: VecDeque<FrameRecord>,
+}
+
+impl FrameHistory {
+    pub fn push(&mut self, frame: FrameRecord) {
+        if self.frames.len() == FRAME_HISTORY_LEN {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    pub fn get(&self, frame_id: u64) -> Option<&FrameRecord> {
+        self.frames.iter().find(|frame| frame.frame_id == frame_id)
+    }
+
+    pub fn get_mut(&mut self, frame_id: u64) -> Option<&mut FrameRecord> {
+        self.frames.iter_mut().find(|frame| frame.frame_id == frame_id)
+    }
+
+    pub fn latest(&self) -> Option<&FrameRecord> {
+        self.frames.back()
+    }
+
+    pub fn latest_for(&self, viewport: ViewportId) -> Option<&FrameRecord> {
+        self.frames.iter().rev().find(|frame| frame.viewport == viewport)
+    }
+}
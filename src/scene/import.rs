@@ -0,0 +1,315 @@
+//! OBJ and STL import.
+//!
+//! Each OBJ group (or object) and each STL solid becomes one mesh, welded on
+//! identical position, normal and UV. Normals missing from the file are
+//! generated from the faces, smoothed across edges flatter than the crease
+//! angle.
+
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3};
+
+use crate::scene::{
+    edges::DEFAULT_CREASE_ANGLE,
+    error::{SceneError, SceneResult},
+    mesh::{MeshData, Vertex},
+    shape::KernelShape,
+    tessellation::TessParams,
+};
+
+/// One group of an OBJ file or one solid of an STL file.
+#[derive(Debug, Clone)]
+pub struct ImportedMesh {
+    /// Group, object or solid name; may be empty.
+    pub name: String,
+    pub mesh: MeshData,
+}
+
+impl KernelShape for ImportedMesh {
+    fn tessellate(&self, _params: &TessParams) -> MeshData {
+        self.mesh.clone()
+    }
+}
+
+/// Collects vertices, sharing those that are bit-identical.
+#[derive(Default)]
+struct MeshBuilder {
+    mesh: MeshData,
+    index: HashMap<[u32; 8], u32>,
+}
+
+impl MeshBuilder {
+    fn vertex(&mut self, position: Vec3, normal: Vec3, uv: Option<Vec2>) -> u32 {
+        let uv_bits = uv.map_or([u32::MAX; 2], |uv| uv.to_array().map(f32::to_bits));
+        let [px, py, pz] = position.to_array().map(f32::to_bits);
+        let [nx, ny, nz] = normal.to_array().map(f32::to_bits);
+        let key = [px, py, pz, nx, ny, nz, uv_bits[0], uv_bits[1]];
+        let vertices = &mut self.mesh.vertices;
+        *self.index.entry(key).or_insert_with(|| {
+            vertices.push(Vertex { position, normal, uv });
+            vertices.len() as u32 - 1
+        })
+    }
+
+    fn finish(self) -> Option<MeshData> {
+        (!self.mesh.is_empty()).then_some(self.mesh)
+    }
+}
+
+/// A face corner: indices into the file's position, UV and normal lists.
+#[derive(Debug, Clone, Copy)]
+struct Corner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+#[derive(Default)]
+struct ObjGroup {
+    triangles: Vec<[Corner; 3]>,
+    lines: Vec<Vec<Corner>>,
+}
+
+/// Parses Wavefront OBJ source: `v`, `vt`, `vn`, `f`, `l`, `g` and `o`.
+///
+/// Polygons are triangulated as fans. Materials, smoothing groups and other
+/// statements are ignored.
+pub fn parse_obj(source: &str) -> SceneResult<Vec<ImportedMesh>> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut groups: Vec<(String, ObjGroup)> = vec![(String::new(), ObjGroup::default())];
+    let mut current = 0;
+    for (index, raw) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw.split('#').next().unwrap_or_default().trim();
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let error = |message: String| SceneError::Parse { line: line_no, message };
+        let floats = |words: std::str::SplitWhitespace, count: usize| -> SceneResult<Vec<f32>> {
+            let values = words.map(|word| word.parse::<f32>().ok().filter(|value| value.is_finite())).collect::<Option<Vec<_>>>();
+            match values {
+                Some(values) if values.len() >= count => Ok(values),
+                _ => Err(error(format!("`{keyword}` needs {count} finite numbers"))),
+            }
+        };
+        match keyword {
+            "v" => positions.push(Vec3::from_slice(&floats(words, 3)?)),
+            "vn" => normals.push(Vec3::from_slice(&floats(words, 3)?).normalize_or_zero()),
+            // `vt u [v [w]]`: a missing v is 0, w is dropped.
+            "vt" => {
+                let uv = floats(words, 1)?;
+                uvs.push(Vec2::new(uv[0], uv.get(1).copied().unwrap_or(0.0)));
+            }
+            "g" | "o" => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                current = groups.iter().position(|(existing, _)| *existing == name).unwrap_or_else(|| {
+                    groups.push((name, ObjGroup::default()));
+                    groups.len() - 1
+                });
+            }
+            "f" | "l" => {
+                let lists = (positions.len(), uvs.len(), normals.len());
+                let corners = words.map(|word| obj_corner(word, lists).map_err(&error)).collect::<SceneResult<Vec<_>>>()?;
+                let group = &mut groups[current].1;
+                if keyword == "l" {
+                    if corners.len() < 2 {
+                        return Err(error("a line needs at least 2 vertices".to_owned()));
+                    }
+                    group.lines.push(corners);
+                } else {
+                    if corners.len() < 3 {
+                        return Err(error("a face needs at least 3 vertices".to_owned()));
+                    }
+                    group.triangles.extend((1..corners.len() - 1).map(|i| [corners[0], corners[i], corners[i + 1]]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .filter_map(|(name, group)| {
+            let mut builder = MeshBuilder::default();
+            let generated = corner_normals(&group.triangles, &positions, &normals);
+            for (triangle, normals_at) in group.triangles.iter().zip(generated) {
+                for (corner, normal) in triangle.iter().zip(normals_at) {
+                    let vertex = builder.vertex(positions[corner.position], normal, corner.uv.map(|uv| uvs[uv]));
+                    builder.mesh.indices.push(vertex);
+                }
+            }
+            for line in &group.lines {
+                let polyline = line.iter().map(|corner| builder.vertex(positions[corner.position], Vec3::Z, corner.uv.map(|uv| uvs[uv]))).collect();
+                builder.mesh.polylines.push(polyline);
+            }
+            Some(ImportedMesh { name, mesh: builder.finish()? })
+        })
+        .collect())
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` reference; indices are 1-based
+/// or, when negative, relative to the end of the list so far.
+fn obj_corner(word: &str, (positions, uvs, normals): (usize, usize, usize)) -> Result<Corner, String> {
+    let resolve = |part: Option<&str>, len: usize, what: &str| -> Result<Option<usize>, String> {
+        let Some(part) = part.filter(|part| !part.is_empty()) else {
+            return Ok(None);
+        };
+        let index: i64 = part.parse().map_err(|_| format!("invalid {what} index `{part}`"))?;
+        let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+        if index == 0 || !(0..len as i64).contains(&resolved) {
+            return Err(format!("{what} index {index} out of range"));
+        }
+        Ok(Some(resolved as usize))
+    };
+    let mut parts = word.split('/');
+    let position = resolve(parts.next(), positions, "position")?.ok_or_else(|| format!("missing position in `{word}`"))?;
+    let uv = resolve(parts.next(), uvs, "texture")?;
+    let normal = resolve(parts.next(), normals, "normal")?;
+    Ok(Corner { position, uv, normal })
+}
+
+/// Normal of each triangle corner: the file's own where given, otherwise the
+/// area-weighted face normals around the position that lie within the
+/// crease angle of this face.
+fn corner_normals(triangles: &[[Corner; 3]], positions: &[Vec3], normals: &[Vec3]) -> Vec<[Vec3; 3]> {
+    let faces: Vec<Vec3> = triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|corner| positions[corner.position]);
+            (b - a).cross(c - a)
+        })
+        .collect();
+    let mut around: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (face, triangle) in triangles.iter().enumerate() {
+        for corner in triangle.iter().filter(|corner| corner.normal.is_none()) {
+            around.entry(corner.position).or_default().push(face);
+        }
+    }
+    let cos_crease = DEFAULT_CREASE_ANGLE.cos();
+    triangles
+        .iter()
+        .zip(&faces)
+        .map(|(triangle, face)| {
+            let unit = face.normalize_or_zero();
+            triangle.map(|corner| match corner.normal {
+                Some(normal) => normals[normal],
+                None => {
+                    let smooth: Vec3 = around[&corner.position].iter().map(|&other| faces[other]).filter(|other| other.normalize_or_zero().dot(unit) >= cos_crease).sum();
+                    smooth.try_normalize().or(unit.try_normalize()).unwrap_or(Vec3::Z)
+                }
+            })
+        })
+        .collect()
+}
+
+const STL_HEADER: usize = 80;
+const STL_FACET: usize = 50;
+
+/// Parses binary or ASCII STL.
+///
+/// Binary files are recognised by their exact size, since many begin with
+/// `solid` too. Facets with a zero normal take the one their winding gives.
+pub fn parse_stl(bytes: &[u8]) -> SceneResult<Vec<ImportedMesh>> {
+    let binary_size = bytes.get(STL_HEADER..STL_HEADER + 4).map(|count| STL_HEADER + 4 + STL_FACET * u32::from_le_bytes(count.try_into().unwrap()) as usize);
+    if binary_size == Some(bytes.len()) {
+        return parse_binary_stl(bytes);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(source) if source.trim_start().starts_with("solid") => parse_ascii_stl(source),
+        _ => match binary_size {
+            Some(size) => Err(SceneError::ParseBinary { offset: bytes.len().min(size), message: format!("binary STL should be {size} bytes, got {}", bytes.len()) }),
+            None => Err(SceneError::ParseBinary { offset: bytes.len(), message: "truncated binary STL header".to_owned() }),
+        },
+    }
+}
+
+fn facet_normal(normal: Vec3, corners: [Vec3; 3]) -> Vec3 {
+    let [a, b, c] = corners;
+    normal.try_normalize().or((b - a).cross(c - a).try_normalize()).unwrap_or(Vec3::Z)
+}
+
+fn parse_binary_stl(bytes: &[u8]) -> SceneResult<Vec<ImportedMesh>> {
+    let header = String::from_utf8_lossy(&bytes[..STL_HEADER]);
+    let name = header.trim_end_matches('\0').trim().trim_start_matches("solid").trim().to_owned();
+    let mut builder = MeshBuilder::default();
+    for (facet, record) in bytes[STL_HEADER + 4..].chunks_exact(STL_FACET).enumerate() {
+        let offset = STL_HEADER + 4 + facet * STL_FACET;
+        let vector = |at: usize| -> SceneResult<Vec3> {
+            let value: [f32; 3] = std::array::from_fn(|axis| f32::from_le_bytes(record[at + axis * 4..at + axis * 4 + 4].try_into().unwrap()));
+            if let Some(axis) = value.iter().position(|value| !value.is_finite()) {
+                return Err(SceneError::ParseBinary { offset: offset + at + axis * 4, message: format!("facet {facet} has a non-finite coordinate") });
+            }
+            Ok(Vec3::from_array(value))
+        };
+        let corners = [vector(12)?, vector(24)?, vector(36)?];
+        let normal = facet_normal(vector(0)?, corners);
+        for position in corners {
+            let vertex = builder.vertex(position, normal, None);
+            builder.mesh.indices.push(vertex);
+        }
+    }
+    Ok(builder.finish().map(|mesh| ImportedMesh { name, mesh }).into_iter().collect())
+}
+
+fn parse_ascii_stl(source: &str) -> SceneResult<Vec<ImportedMesh>> {
+    let mut solids = Vec::new();
+    // The open solid, and the open facet with its normal and vertices so far.
+    let mut solid: Option<(String, MeshBuilder)> = None;
+    let mut facet: Option<(Vec3, Vec<Vec3>)> = None;
+    let mut last_line = 0;
+    for (index, raw) in source.lines().enumerate() {
+        let line_no = index + 1;
+        last_line = line_no;
+        let mut words = raw.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let error = |message: &str| SceneError::Parse { line: line_no, message: message.to_owned() };
+        let vector = |words: std::str::SplitWhitespace| -> SceneResult<Vec3> {
+            let values = words.map(|word| word.parse::<f32>().ok().filter(|value| value.is_finite())).collect::<Option<Vec<_>>>();
+            match values {
+                Some(values) if values.len() == 3 => Ok(Vec3::from_slice(&values)),
+                _ => Err(error(&format!("`{keyword}` needs 3 finite numbers"))),
+            }
+        };
+        match (keyword, &mut solid, &mut facet) {
+            ("solid", None, _) => solid = Some((words.collect::<Vec<_>>().join(" "), MeshBuilder::default())),
+            ("endsolid", Some(_), None) => {
+                let (name, builder) = solid.take().unwrap();
+                solids.extend(builder.finish().map(|mesh| ImportedMesh { name, mesh }));
+            }
+            ("facet", Some(_), None) => {
+                if words.next() != Some("normal") {
+                    return Err(error("expected `facet normal`"));
+                }
+                facet = Some((vector(words)?, Vec::with_capacity(3)));
+            }
+            ("outer", Some(_), Some(_)) | ("endloop", Some(_), Some(_)) => {}
+            ("vertex", Some(_), Some((_, corners))) => {
+                if corners.len() == 3 {
+                    return Err(error("a facet has exactly 3 vertices"));
+                }
+                corners.push(vector(words)?);
+            }
+            ("endfacet", Some((_, builder)), Some((normal, corners))) => {
+                let Ok(corners) = <[Vec3; 3]>::try_from(corners.as_slice()) else {
+                    return Err(error("a facet has exactly 3 vertices"));
+                };
+                let normal = facet_normal(*normal, corners);
+                for position in corners {
+                    let vertex = builder.vertex(position, normal, None);
+                    builder.mesh.indices.push(vertex);
+                }
+                facet = None;
+            }
+            _ => return Err(error(&format!("unexpected `{keyword}`"))),
+        }
+    }
+    if solid.is_some() {
+        return Err(SceneError::Parse { line: last_line, message: "solid is missing `endsolid`".to_owned() });
+    }
+    Ok(solids)
+}
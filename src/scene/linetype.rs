@@ -0,0 +1,189 @@
+//! Linetype patterns and the AutoCAD `.lin` format.
+//!
+//! A pattern is a list of elements as in `.lin` files: positive values are
+//! dashes, negative values gaps and zero a dot. Lengths are in world units
+//! (drawing units, like `.lin` files) or in screen pixels.
+
+use std::collections::BTreeMap;
+
+use crate::scene::error::{SceneError, SceneResult};
+
+/// Definitions available before any `.lin` file is loaded.
+pub const STANDARD_LIN: &str = "\
+*CONTINUOUS,Solid line
+A,
+*DASHED,Dashed __ __ __ __
+A,.5,-.25
+*HIDDEN,Hidden __ __ __ __
+A,.25,-.125
+*CENTER,Center ____ _ ____ _
+A,1.25,-.25,.25,-.25
+*DOT,Dot . . . . . . .
+A,0,-.25
+*DASHDOT,Dash dot __ . __ . __
+A,.5,-.25,0,-.25
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum PatternUnits {
+    /// Lengths scale with the model, as in `.lin` files.
+    #[default]
+    World,
+    /// Lengths in pixels, independent of zoom.
+    Screen,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Linetype {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Dash (> 0), gap (< 0) and dot (0) lengths. Empty means continuous.
+    pub pattern: Vec<f32>,
+    #[serde(default)]
+    pub units: PatternUnits,
+}
+
+impl Linetype {
+    pub fn continuous() -> Self {
+        Self {
+            name: "Continuous".to_owned(),
+            description: "Solid line".to_owned(),
+            pattern: Vec::new(),
+            units: PatternUnits::World,
+        }
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.pattern.iter().all(|&element| element > 0.0)
+    }
+
+    /// Length of one repetition of the pattern.
+    pub fn period(&self) -> f32 {
+        self.pattern.iter().map(|element| element.abs()).sum()
+    }
+
+    pub fn validate(&self) -> SceneResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(SceneError::InvalidState("linetype name must not be empty"));
+        }
+        if self.pattern.iter().any(|element| !element.is_finite()) {
+            return Err(SceneError::InvalidState("linetype pattern must be finite"));
+        }
+        if !self.is_continuous() && self.period() <= 0.0 {
+            return Err(SceneError::InvalidState("linetype pattern has zero length"));
+        }
+        Ok(())
+    }
+
+    /// Whether the line is drawn at `distance` from the start of the polyline.
+    ///
+    /// Dots are widened to `dot` (the length of one pixel in pattern units)
+    /// so that they stay visible.
+    pub fn inked(&self, distance: f32, dot: f32) -> bool {
+        if self.is_continuous() {
+            return true;
+        }
+        let period = self.period();
+        let phase = distance.rem_euclid(period);
+        let mut start = 0.0;
+        for &element in &self.pattern {
+            let end = start + element.abs();
+            let hit = if element == 0.0 {
+                // Measured both ways round so a dot at the period start wraps.
+                let offset = (phase - start).abs();
+                offset.min(period - offset) <= dot * 0.5
+            } else {
+                element > 0.0 && (start..end).contains(&phase)
+            };
+            if hit {
+                return true;
+            }
+            start = end;
+        }
+        false
+    }
+}
+
+/// Parses AutoCAD-style `.lin` source into simple linetypes.
+///
+/// Complex elements (embedded shapes or text in `[...]`) are rejected.
+pub fn parse_lin(source: &str) -> SceneResult<Vec<Linetype>> {
+    let mut linetypes = Vec::new();
+    let mut header: Option<(usize, String, String)> = None;
+    for (index, raw) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw.split(";;").next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| SceneError::Parse { line: line_no, message: message.to_owned() };
+        if let Some(rest) = line.strip_prefix('*') {
+            if header.is_some() {
+                return Err(error("linetype header without a pattern line"));
+            }
+            let (name, description) = rest.split_once(',').unwrap_or((rest, ""));
+            if name.trim().is_empty() {
+                return Err(error("linetype name is empty"));
+            }
+            header = Some((line_no, name.trim().to_owned(), description.trim().to_owned()));
+            continue;
+        }
+        let Some((_, name, description)) = header.take() else {
+            return Err(error("pattern line without a `*NAME` header"));
+        };
+        let mut fields = line.split(',').map(str::trim);
+        if !fields.next().is_some_and(|alignment| alignment.eq_ignore_ascii_case("A")) {
+            return Err(error("pattern must start with the `A` alignment field"));
+        }
+        let mut pattern = Vec::new();
+        for field in fields.filter(|field| !field.is_empty()) {
+            if field.starts_with('[') {
+                return Err(error("complex linetype elements are not supported"));
+            }
+            let value: f32 = field.parse().map_err(|_| error(&format!("invalid pattern length `{field}`")))?;
+            if !value.is_finite() {
+                return Err(error(&format!("invalid pattern length `{field}`")));
+            }
+            pattern.push(value);
+        }
+        let linetype = Linetype { name, description, pattern, units: PatternUnits::World };
+        linetype.validate().map_err(|_| error("pattern has zero length"))?;
+        linetypes.push(linetype);
+    }
+    if let Some((line, name, _)) = header {
+        return Err(SceneError::Parse { line, message: format!("linetype `{name}` has no pattern line") });
+    }
+    Ok(linetypes)
+}
+
+/// Linetypes by case-insensitive name.
+#[derive(Debug, Clone)]
+pub struct LinetypeLibrary {
+    linetypes: BTreeMap<String, Linetype>,
+}
+
+impl Default for LinetypeLibrary {
+    fn default() -> Self {
+        let mut library = Self { linetypes: BTreeMap::new() };
+        for linetype in parse_lin(STANDARD_LIN).expect("standard linetypes parse") {
+            library.insert(linetype);
+        }
+        library
+    }
+}
+
+impl LinetypeLibrary {
+    /// Adds or replaces a linetype by name.
+    pub fn insert(&mut self, linetype: Linetype) {
+        self.linetypes.insert(linetype.name.to_ascii_uppercase(), linetype);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Linetype> {
+        self.linetypes.get(&name.to_ascii_uppercase())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.linetypes.values().map(|linetype| linetype.name.as_str())
+    }
+}
@@ -0,0 +1,97 @@
+//! Screen-space-error level of detail.
+//!
+//! An entity's submitted mesh is its finest level. Coarser levels carry the
+//! geometric error they were made with, and a viewport draws the coarsest
+//! level whose error projects under a pixel threshold.
+
+use glam::{Mat4, Vec4};
+
+use crate::scene::{
+    bounds::Aabb,
+    camera::CameraParams,
+    error::{SceneError, SceneResult},
+    mesh::MeshData,
+};
+
+/// Smallest error a level may carry. Every level must deviate by a positive
+/// error, so a decimated level that lost only flat detail is given this.
+pub const MIN_LOD_ERROR: f32 = 1e-6;
+
+/// A coarser tessellation of an entity.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LodLevel {
+    pub mesh: MeshData,
+    /// Largest deviation from the true surface, in local units.
+    pub error: f32,
+}
+
+impl LodLevel {
+    pub fn validate(&self) -> SceneResult<()> {
+        if self.mesh.is_empty() {
+            return Err(SceneError::ResourceMissing("level of detail has an empty mesh"));
+        }
+        self.mesh.validate()?;
+        if !self.error.is_finite() || self.error <= 0.0 {
+            return Err(SceneError::InvalidState("level of detail error must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LodPolicy {
+    /// Largest projected error, in pixels, a level may show.
+    pub pixel_threshold: f32,
+    /// Fraction of the threshold a level must clear before switching to it
+    /// or drift past before leaving it, so levels do not flicker.
+    pub hysteresis: f32,
+}
+
+impl Default for LodPolicy {
+    fn default() -> Self {
+        Self { pixel_threshold: 1.0, hysteresis: 0.25 }
+    }
+}
+
+impl LodPolicy {
+    pub fn validate(&self) -> SceneResult<()> {
+        if !self.pixel_threshold.is_finite() || self.pixel_threshold <= 0.0 {
+            return Err(SceneError::InvalidState("LOD pixel threshold must be positive"));
+        }
+        if !(0.0..1.0).contains(&self.hysteresis) {
+            return Err(SceneError::InvalidState("LOD hysteresis must be within 0..1"));
+        }
+        Ok(())
+    }
+
+    /// Level to draw given the current one and the projected error of each
+    /// coarser level; `0` is the finest mesh.
+    pub fn select(&self, current: usize, errors_px: &[f32]) -> usize {
+        let fits = |level: usize, threshold: f32| level == 0 || errors_px[level - 1] <= threshold;
+        let current = current.min(errors_px.len());
+        let coarsest = |threshold: f32| (0..=errors_px.len()).rev().find(|&level| fits(level, threshold)).unwrap_or(0);
+        if !fits(current, self.pixel_threshold * (1.0 + self.hysteresis)) {
+            return coarsest(self.pixel_threshold);
+        }
+        coarsest(self.pixel_threshold * (1.0 - self.hysteresis)).max(current)
+    }
+}
+
+/// Pixels covered by a local-space `error` on an entity with world `bounds`,
+/// measured at the point of the bounds nearest the camera.
+pub fn projected_error(camera: &CameraParams, model: &Mat4, bounds: &Aabb, error: f32) -> f32 {
+    let scale = [model.x_axis, model.y_axis, model.z_axis].iter().map(|axis| axis.truncate().length()).fold(0.0, f32::max);
+    // Pixels per world unit at unit depth, from the vertical projection scale.
+    let pixels = camera.proj.y_axis.y.abs() * camera.viewport.y as f32 * 0.5;
+    let orthographic = camera.proj.row(3) == Vec4::W;
+    let depth = if orthographic {
+        1.0
+    } else {
+        let eye = camera.view.inverse().w_axis.truncate();
+        eye.clamp(bounds.min, bounds.max).distance(eye)
+    };
+    if depth <= 0.0 {
+        return f32::INFINITY;
+    }
+    error * scale * pixels / depth
+}
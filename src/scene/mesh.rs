@@ -1,5 +1,10 @@
 use glam::{Vec2, Vec3};
 
+use crate::scene::{
+    bounds::Aabb,
+    error::{SceneError, SceneResult},
+};
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Vertex {
     pub position: Vec3,
@@ -7,14 +12,48 @@ pub struct Vertex {
     pub uv: Option<Vec2>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct MeshData {
     pub vertices: Vec<Vertex>,
+    /// Triangle list.
     pub indices: Vec<u32>,
+    /// Line topology: each polyline is a chain of vertex indices.
+    #[serde(default)]
+    pub polylines: Vec<Vec<u32>>,
+}
+
+impl Vertex {
+    pub fn is_finite(&self) -> bool {
+        self.position.is_finite() && self.normal.is_finite() && self.uv.is_none_or(|uv| uv.is_finite())
+    }
 }
 
 impl MeshData {
     pub fn is_empty(&self) -> bool {
-        self.vertices.is_empty() || self.indices.is_empty()
+        self.vertices.is_empty() || (self.indices.is_empty() && self.polylines.iter().all(|polyline| polyline.len() < 2))
+    }
+
+    /// Checks what every submission path relies on: finite attributes,
+    /// whole triangles and indices within the vertex list.
+    pub fn validate(&self) -> SceneResult<()> {
+        if self.is_empty() {
+            return Err(SceneError::ResourceMissing("mesh has no triangles or polylines"));
+        }
+        if !self.vertices.iter().all(Vertex::is_finite) {
+            return Err(SceneError::InvalidState("mesh vertex attributes must be finite"));
+        }
+        if !self.indices.len().is_multiple_of(3) {
+            return Err(SceneError::InvalidState("triangle index count must be a multiple of 3"));
+        }
+        let count = self.vertices.len();
+        if self.indices.iter().chain(self.polylines.iter().flatten()).any(|&i| i as usize >= count) {
+            return Err(SceneError::InvalidState("mesh index out of range"));
+        }
+        Ok(())
+    }
+
+    /// Local-space bounds of all vertices.
+    pub fn bounds(&self) -> Option<Aabb> {
+        Aabb::from_points(self.vertices.iter().map(|v| v.position))
     }
 }
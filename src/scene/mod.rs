@@ -1,10 +1,35 @@
+pub mod appearance;
+pub(crate) mod backend;
+pub mod bounds;
 pub mod camera;
+pub mod color;
+pub mod constraint;
 pub mod context;
+pub mod decimate;
+pub mod edges;
 pub mod error;
+pub mod export;
+pub mod frame;
 pub mod id;
+pub mod import;
+pub mod linetype;
+pub mod lod;
 pub mod mesh;
+pub mod overlay;
+pub mod packed;
+pub mod pick;
+pub mod raster;
+pub mod section;
 pub mod shape;
+pub mod slice;
+pub mod snap;
 pub mod tessellation;
+pub mod tracking;
 pub mod transform;
+pub mod view;
+pub mod viewport;
 pub mod visual;
 pub mod world;
+
+#[cfg(test)]
+mod tests;
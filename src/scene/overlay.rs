@@ -0,0 +1,281 @@
+//! Transient overlay drawn over every viewport.
+//!
+//! Command previews, rubber-band windows, snap markers, tracking guides and grips live here
+//! rather than in the world, so they never reach state queries, exports,
+//! entity ids or ordinary picks. The overlay ignores depth and sections and
+//! is drawn after the scene, in id order.
+
+use std::collections::BTreeMap;
+
+use glam::{Vec2, Vec3};
+
+use crate::scene::{
+    camera::CameraParams,
+    color::Rgba,
+    error::{SceneError, SceneResult},
+    mesh::MeshData,
+    pick::ray_triangle,
+    raster::{self, ClipVertex, RenderTarget, MAX_TARGET_EDGE},
+};
+
+/// Edge length of snap markers, in pixels.
+pub const MARKER_SIZE_PX: f32 = 12.0;
+/// Edge length of grip squares, in pixels.
+pub const GRIP_SIZE_PX: f32 = 8.0;
+/// Furthest a rubber-band corner may lie from the viewport origin, in pixels.
+pub const MAX_BAND_PX: f32 = 4.0 * MAX_TARGET_EDGE as f32;
+
+pub const WINDOW_COLOR: Rgba = [0, 120, 215, 255];
+pub const CROSSING_COLOR: Rgba = [0, 160, 60, 255];
+pub const GRIP_COLOR: Rgba = [0, 90, 255, 255];
+pub const HOT_GRIP_COLOR: Rgba = [230, 30, 30, 255];
+
+/// Coverage of the rubber-band fill.
+const BAND_FILL_ALPHA: f32 = 0.15;
+/// Dash and gap length of crossing windows and guides, in pixels.
+const DASH_PX: f32 = 4.0;
+
+/// Whether a point `distance` pixels along a dashed line is drawn.
+fn dash(distance: f32) -> bool {
+    ((distance / DASH_PX) as u32).is_multiple_of(2)
+}
+
+/// Identifier of an overlay item; never recycled, even across `clear`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct OverlayId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerGlyph {
+    /// Endpoint.
+    Square,
+    /// Midpoint.
+    Triangle,
+    /// Center.
+    Circle,
+    /// Intersection.
+    Cross,
+    /// Quadrant.
+    Diamond,
+    /// Perpendicular foot.
+    Perpendicular,
+    /// Tangent point: a circle under a line.
+    Tangent,
+    /// Nearest point.
+    Hourglass,
+}
+
+impl MarkerGlyph {
+    /// Outline as segments in a unit box, y down like the screen.
+    fn segments(self) -> Vec<[Vec2; 2]> {
+        let closed = |points: &[Vec2]| (0..points.len()).map(|i| [points[i], points[(i + 1) % points.len()]]).collect::<Vec<_>>();
+        let circle = |radius: f32, centre: Vec2| closed(&(0..16).map(|i| centre + Vec2::from_angle(i as f32 * std::f32::consts::TAU / 16.0) * radius).collect::<Vec<_>>());
+        match self {
+            MarkerGlyph::Square => closed(&[Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::new(1.0, 1.0), Vec2::new(-1.0, 1.0)]),
+            MarkerGlyph::Triangle => closed(&[Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(0.0, -1.0)]),
+            MarkerGlyph::Circle => circle(1.0, Vec2::ZERO),
+            MarkerGlyph::Cross => vec![[Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)], [Vec2::new(-1.0, 1.0), Vec2::new(1.0, -1.0)]],
+            MarkerGlyph::Diamond => closed(&[Vec2::new(0.0, -1.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0), Vec2::new(-1.0, 0.0)]),
+            MarkerGlyph::Perpendicular => vec![
+                [Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0)],
+                [Vec2::new(-1.0, 1.0), Vec2::new(-1.0, -1.0)],
+                [Vec2::new(-1.0, 0.0), Vec2::new(0.0, 0.0)],
+                [Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0)],
+            ],
+            MarkerGlyph::Tangent => {
+                let mut segments = circle(0.75, Vec2::new(0.0, 0.25));
+                segments.push([Vec2::new(-1.0, -0.5), Vec2::new(1.0, -0.5)]);
+                segments
+            }
+            MarkerGlyph::Hourglass => closed(&[Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0)]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OverlayItem {
+    /// Scene-space geometry such as the entity a command is about to create.
+    /// Triangles blend with the colour's alpha; polylines draw one pixel wide.
+    Preview { mesh: MeshData, color: Rgba },
+    /// Selection window between two pixel corners. Crossing windows, dragged
+    /// right to left, are dashed and green.
+    RubberBand { from: Vec2, to: Vec2, crossing: bool },
+    /// Object snap glyph at a scene point, a fixed size on screen.
+    SnapMarker { position: Vec3, glyph: MarkerGlyph, color: Rgba },
+    /// Grip square at a scene point; hot grips are filled red.
+    Grip { position: Vec3, hot: bool },
+    /// Dashed tracking ray from a scene point through another to the edge of the viewport.
+    GuideLine { from: Vec3, to: Vec3, color: Rgba },
+}
+
+impl OverlayItem {
+    pub fn validate(&self) -> SceneResult<()> {
+        let finite = match self {
+            OverlayItem::Preview { mesh, .. } => return mesh.validate(),
+            OverlayItem::RubberBand { from, to, .. } => from.is_finite() && to.is_finite(),
+            OverlayItem::SnapMarker { position, .. } | OverlayItem::Grip { position, .. } => position.is_finite(),
+            OverlayItem::GuideLine { from, to, .. } => from.is_finite() && to.is_finite(),
+        };
+        if !finite {
+            return Err(SceneError::InvalidState("overlay positions must be finite"));
+        }
+        if let OverlayItem::RubberBand { from, to, .. } = self {
+            if from.abs().max(to.abs()).max_element() > MAX_BAND_PX {
+                return Err(SceneError::InvalidState("rubber-band corners must lie near the viewport"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Overlay item under a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct OverlayHit {
+    pub overlay_id: OverlayId,
+    /// The grip or marker position, or the point on a preview surface.
+    pub point: Vec3,
+}
+
+#[derive(Debug, Default)]
+pub struct Overlay {
+    items: BTreeMap<OverlayId, OverlayItem>,
+    next_id: u32,
+}
+
+impl Overlay {
+    pub fn add(&mut self, item: OverlayItem) -> SceneResult<OverlayId> {
+        item.validate()?;
+        let id = OverlayId(self.next_id);
+        self.next_id += 1;
+        self.items.insert(id, item);
+        Ok(id)
+    }
+
+    pub fn replace(&mut self, id: OverlayId, item: OverlayItem) -> SceneResult<()> {
+        item.validate()?;
+        let slot = self.items.get_mut(&id).ok_or(SceneError::UnknownOverlay(id.0))?;
+        *slot = item;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: OverlayId) -> SceneResult<()> {
+        self.items.remove(&id).map(|_| ()).ok_or(SceneError::UnknownOverlay(id.0))
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn get(&self, id: OverlayId) -> Option<&OverlayItem> {
+        self.items.get(&id)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (OverlayId, &OverlayItem)> {
+        self.items.iter().map(|(id, item)| (*id, item))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Draws every item over `target`, ignoring depth.
+    pub(crate) fn draw(&self, target: &mut RenderTarget, camera: &CameraParams) {
+        let size = target.size();
+        let pixel = |pos: Vec2| raster::pixel_vertex(size, pos);
+        for item in self.items.values() {
+            match item {
+                OverlayItem::Preview { mesh, color } => {
+                    let view_proj = camera.view_proj();
+                    let vertices: Vec<ClipVertex> = mesh.vertices.iter().map(|v| ClipVertex { clip: view_proj * v.position.extend(1.0), world: v.position }).collect();
+                    let alpha = color[3] as f32 / 255.0;
+                    for tri in mesh.indices.chunks_exact(3) {
+                        let corners = [0, 1, 2].map(|k| vertices[tri[k] as usize]);
+                        raster::triangle(size, corners, &[], |f| target.blend(f.index, *color, alpha));
+                    }
+                    for [a, b] in mesh.polylines.iter().flat_map(|polyline| polyline.windows(2)).map(|pair| [pair[0], pair[1]]) {
+                        raster::line(size, vertices[a as usize], vertices[b as usize], 1.0, &[], |f| target.set_color(f.index, *color));
+                    }
+                }
+                OverlayItem::RubberBand { from, to, crossing } => {
+                    let color = if *crossing { CROSSING_COLOR } else { WINDOW_COLOR };
+                    let corners = [*from, Vec2::new(to.x, from.y), *to, Vec2::new(from.x, to.y)];
+                    for tri in [[0, 1, 2], [0, 2, 3]] {
+                        raster::triangle(size, tri.map(|k| pixel(corners[k])), &[], |f| target.blend(f.index, color, BAND_FILL_ALPHA));
+                    }
+                    for k in 0..4 {
+                        let (a, b) = (corners[k], corners[(k + 1) % 4]);
+                        let length = a.distance(b);
+                        raster::line(size, pixel(a), pixel(b), 1.0, &[], |f| {
+                            if !*crossing || dash(f.along * length) {
+                                target.set_color(f.index, color);
+                            }
+                        });
+                    }
+                }
+                OverlayItem::SnapMarker { position, glyph, color } => {
+                    let Some(centre) = camera.to_screen(*position) else {
+                        continue;
+                    };
+                    for [a, b] in glyph.segments() {
+                        let (a, b) = (centre + a * MARKER_SIZE_PX * 0.5, centre + b * MARKER_SIZE_PX * 0.5);
+                        raster::line(size, pixel(a), pixel(b), 2.0, &[], |f| target.set_color(f.index, *color));
+                    }
+                }
+                OverlayItem::GuideLine { from, to, color } => {
+                    let (Some(a), Some(b)) = (camera.to_screen(*from), camera.to_screen(*to)) else {
+                        continue;
+                    };
+                    let Some(direction) = (b - a).try_normalize() else {
+                        continue;
+                    };
+                    // Far enough to leave the viewport from anywhere on it.
+                    let end = b + direction * (size.x + size.y) as f32;
+                    let length = a.distance(end);
+                    raster::line(size, pixel(a), pixel(end), 1.0, &[], |f| {
+                        if dash(f.along * length) {
+                            target.set_color(f.index, *color);
+                        }
+                    });
+                }
+                OverlayItem::Grip { position, hot } => {
+                    let Some(centre) = camera.to_screen(*position) else {
+                        continue;
+                    };
+                    let half = Vec2::splat(GRIP_SIZE_PX * 0.5);
+                    let (min, max) = (centre - half, centre + half);
+                    let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+                    let color = if *hot { HOT_GRIP_COLOR } else { GRIP_COLOR };
+                    for tri in [[0, 1, 2], [0, 2, 3]] {
+                        raster::triangle(size, tri.map(|k| pixel(corners[k])), &[], |f| target.set_color(f.index, color));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Topmost item under `cursor`: grips and markers within their square,
+    /// previews along the pick ray. Rubber bands and guides are never hit.
+    pub(crate) fn pick(&self, camera: &CameraParams, cursor: Vec2) -> Option<OverlayHit> {
+        let (origin, dir) = camera.ray(cursor);
+        self.items.iter().rev().find_map(|(&overlay_id, item)| {
+            let point = match item {
+                OverlayItem::Preview { mesh, .. } => {
+                    let positions = |tri: &[u32]| [0, 1, 2].map(|k| mesh.vertices[tri[k] as usize].position);
+                    let t = mesh.indices.chunks_exact(3).filter_map(|tri| ray_triangle(origin, dir, positions(tri))).min_by(f32::total_cmp)?;
+                    origin + dir * t
+                }
+                OverlayItem::RubberBand { .. } | OverlayItem::GuideLine { .. } => return None,
+                OverlayItem::SnapMarker { position, .. } | OverlayItem::Grip { position, .. } => {
+                    let half = if matches!(item, OverlayItem::Grip { .. }) { GRIP_SIZE_PX } else { MARKER_SIZE_PX } * 0.5;
+                    let offset = camera.to_screen(*position)? - cursor;
+                    if offset.abs().max_element() > half {
+                        return None;
+                    }
+                    *position
+                }
+            };
+            Some(OverlayHit { overlay_id, point })
+        })
+    }
+}
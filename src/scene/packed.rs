@@ -0,0 +1,225 @@
+//! Compact binary mesh encoding for uploads.
+//!
+//! Little-endian throughout. A 20-byte header
+//!
+//! | offset | type     | field                       |
+//! |--------|----------|-----------------------------|
+//! | 0      | `[u8;4]` | magic `RCMB`                |
+//! | 4      | u16      | version, `1`                |
+//! | 6      | u16      | flags, see [`PackedFlags`]  |
+//! | 8      | u32      | vertex count                |
+//! | 12     | u32      | triangle index count        |
+//! | 16     | u32      | polyline count              |
+//!
+//! is followed by these sections, each padded to a multiple of 4 bytes:
+//! the position bounds as 6 f32 (quantized positions only), positions
+//! (3 f32, or 3 u16 spread over the bounds), normals (3 f32, or 2 i16
+//! octahedral), UVs (2 f32, when flagged), triangle indices (u32 or u16),
+//! polyline lengths (u32) and polyline indices (same width as triangles).
+
+use glam::{Vec2, Vec3};
+
+use crate::scene::{
+    error::{SceneError, SceneResult},
+    mesh::{MeshData, Vertex},
+};
+
+pub const PACKED_MAGIC: [u8; 4] = *b"RCMB";
+pub const PACKED_VERSION: u16 = 1;
+const HEADER_LEN: usize = 20;
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PackedFlags: u16 {
+        const UVS = 1 << 0;
+        const INDEX_U16 = 1 << 1;
+        const QUANTIZED_POSITIONS = 1 << 2;
+        const OCTAHEDRAL_NORMALS = 1 << 3;
+    }
+}
+
+/// Lossy encodings to shrink an upload further.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PackOptions {
+    /// 16 bits per coordinate across the mesh bounds.
+    #[serde(default)]
+    pub quantize_positions: bool,
+    /// Two 16-bit octahedral components per normal.
+    #[serde(default)]
+    pub octahedral_normals: bool,
+}
+
+/// Encodes `mesh`; indices are 16-bit whenever the vertex count allows.
+/// UVs are written only when every vertex has them.
+pub fn encode_mesh(mesh: &MeshData, options: &PackOptions) -> Vec<u8> {
+    let uvs: Option<Vec<Vec2>> = mesh.vertices.iter().map(|v| v.uv).collect();
+    let bounds = mesh.bounds().filter(|_| options.quantize_positions);
+    let mut flags = PackedFlags::empty();
+    flags.set(PackedFlags::UVS, uvs.is_some() && !mesh.vertices.is_empty());
+    flags.set(PackedFlags::INDEX_U16, mesh.vertices.len() <= 1 << 16);
+    flags.set(PackedFlags::QUANTIZED_POSITIONS, bounds.is_some());
+    flags.set(PackedFlags::OCTAHEDRAL_NORMALS, options.octahedral_normals);
+
+    let mut out = Vec::new();
+    out.extend(PACKED_MAGIC);
+    out.extend(PACKED_VERSION.to_le_bytes());
+    out.extend(flags.bits().to_le_bytes());
+    for count in [mesh.vertices.len(), mesh.indices.len(), mesh.polylines.len()] {
+        out.extend((count as u32).to_le_bytes());
+    }
+    let pad = |out: &mut Vec<u8>| out.resize(out.len().next_multiple_of(4), 0);
+    match bounds {
+        Some(bounds) => {
+            out.extend(bounds.min.to_array().into_iter().chain(bounds.max.to_array()).flat_map(f32::to_le_bytes));
+            let extent = (bounds.max - bounds.min).to_array();
+            for v in &mesh.vertices {
+                let offset = (v.position - bounds.min).to_array();
+                // A flat axis has nothing to spread over and packs to 0.
+                let unit = std::array::from_fn::<f32, 3, _>(|axis| if extent[axis] > 0.0 { (offset[axis] / extent[axis]).clamp(0.0, 1.0) } else { 0.0 });
+                out.extend(unit.into_iter().flat_map(|c| ((c * 65535.0).round() as u16).to_le_bytes()));
+            }
+            pad(&mut out);
+        }
+        None => out.extend(mesh.vertices.iter().flat_map(|v| v.position.to_array()).flat_map(f32::to_le_bytes)),
+    }
+    if options.octahedral_normals {
+        for v in &mesh.vertices {
+            out.extend(octahedral_encode(v.normal).to_array().into_iter().flat_map(|c| ((c.clamp(-1.0, 1.0) * 32767.0).round() as i16).to_le_bytes()));
+        }
+        pad(&mut out);
+    } else {
+        out.extend(mesh.vertices.iter().flat_map(|v| v.normal.to_array()).flat_map(f32::to_le_bytes));
+    }
+    if let Some(uvs) = uvs.filter(|_| flags.contains(PackedFlags::UVS)) {
+        out.extend(uvs.iter().flat_map(|uv| uv.to_array()).flat_map(f32::to_le_bytes));
+    }
+    let write_indices = |out: &mut Vec<u8>, indices: &mut dyn Iterator<Item = u32>| {
+        for i in indices {
+            if flags.contains(PackedFlags::INDEX_U16) {
+                out.extend((i as u16).to_le_bytes());
+            } else {
+                out.extend(i.to_le_bytes());
+            }
+        }
+        pad(out);
+    };
+    write_indices(&mut out, &mut mesh.indices.iter().copied());
+    out.extend(mesh.polylines.iter().flat_map(|polyline| (polyline.len() as u32).to_le_bytes()));
+    write_indices(&mut out, &mut mesh.polylines.iter().flatten().copied());
+    out
+}
+
+/// Decodes and validates a packed mesh; structural errors carry the byte
+/// offset of the section at fault.
+pub fn decode_mesh(bytes: &[u8]) -> SceneResult<MeshData> {
+    let mut reader = Reader { bytes, offset: 0 };
+    let header = reader.take(HEADER_LEN, "header")?;
+    if header[..4] != PACKED_MAGIC {
+        return Err(SceneError::ParseBinary { offset: 0, message: "not a packed mesh".to_owned() });
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != PACKED_VERSION {
+        return Err(SceneError::ParseBinary { offset: 4, message: format!("unsupported packed mesh version {version}") });
+    }
+    let flags = PackedFlags::from_bits(u16::from_le_bytes([header[6], header[7]])).ok_or_else(|| SceneError::ParseBinary { offset: 6, message: "unknown packed mesh flags".to_owned() })?;
+    let count = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap()) as usize;
+    let (vertex_count, index_count, polyline_count) = (count(8), count(12), count(16));
+
+    let positions: Vec<Vec3> = if flags.contains(PackedFlags::QUANTIZED_POSITIONS) {
+        let bounds = reader.f32s(6, "position bounds")?;
+        let (min, max) = (Vec3::from_slice(&bounds[..3]), Vec3::from_slice(&bounds[3..]));
+        let quantized = reader.padded(vertex_count * 6, "quantized positions")?;
+        quantized
+            .chunks_exact(6)
+            .map(|q| {
+                let unit = Vec3::from_array(std::array::from_fn(|axis| u16::from_le_bytes([q[axis * 2], q[axis * 2 + 1]]) as f32 / 65535.0));
+                min + unit * (max - min)
+            })
+            .collect()
+    } else {
+        reader.f32s(vertex_count * 3, "positions")?.chunks_exact(3).map(Vec3::from_slice).collect()
+    };
+    let normals: Vec<Vec3> = if flags.contains(PackedFlags::OCTAHEDRAL_NORMALS) {
+        let packed = reader.padded(vertex_count * 4, "octahedral normals")?;
+        packed
+            .chunks_exact(4)
+            .map(|q| {
+                let component = |at: usize| (i16::from_le_bytes([q[at], q[at + 1]]) as f32 / 32767.0).max(-1.0);
+                octahedral_decode(Vec2::new(component(0), component(2)))
+            })
+            .collect()
+    } else {
+        reader.f32s(vertex_count * 3, "normals")?.chunks_exact(3).map(Vec3::from_slice).collect()
+    };
+    let uvs: Option<Vec<Vec2>> = if flags.contains(PackedFlags::UVS) {
+        Some(reader.f32s(vertex_count * 2, "UVs")?.chunks_exact(2).map(Vec2::from_slice).collect())
+    } else {
+        None
+    };
+    let wide = !flags.contains(PackedFlags::INDEX_U16);
+    let indices = reader.indices(index_count, wide, "triangle indices")?;
+    let lengths: Vec<usize> = reader.take(polyline_count * 4, "polyline lengths")?.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap()) as usize).collect();
+    let mut flat = reader.indices(lengths.iter().sum(), wide, "polyline indices")?.into_iter();
+    let polylines = lengths.iter().map(|&len| flat.by_ref().take(len).collect()).collect();
+    if reader.offset != bytes.len() {
+        return Err(SceneError::ParseBinary { offset: reader.offset, message: format!("{} unexpected trailing bytes", bytes.len() - reader.offset) });
+    }
+
+    let vertices = (0..vertex_count).map(|i| Vertex { position: positions[i], normal: normals[i], uv: uvs.as_ref().map(|uvs| uvs[i]) }).collect();
+    let mesh = MeshData { vertices, indices, polylines };
+    mesh.validate()?;
+    Ok(mesh)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, what: &str) -> SceneResult<&'a [u8]> {
+        let end = self.offset.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let Some(end) = end else {
+            return Err(SceneError::ParseBinary { offset: self.offset, message: format!("truncated {what}: needs {len} bytes, {} left", self.bytes.len() - self.offset) });
+        };
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    /// `len` bytes followed by padding to the next multiple of 4.
+    fn padded(&mut self, len: usize, what: &str) -> SceneResult<&'a [u8]> {
+        let slice = self.take(len.next_multiple_of(4), what)?;
+        Ok(&slice[..len])
+    }
+
+    fn f32s(&mut self, count: usize, what: &str) -> SceneResult<Vec<f32>> {
+        Ok(self.take(count * 4, what)?.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect())
+    }
+
+    fn indices(&mut self, count: usize, wide: bool, what: &str) -> SceneResult<Vec<u32>> {
+        if wide {
+            return Ok(self.take(count * 4, what)?.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect());
+        }
+        Ok(self.padded(count * 2, what)?.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]]) as u32).collect())
+    }
+}
+
+/// Unit vector folded onto the octahedron and flattened to `[-1, 1]²`.
+fn octahedral_encode(normal: Vec3) -> Vec2 {
+    let a = normal.abs();
+    let n = normal / (a.x + a.y + a.z).max(f32::MIN_POSITIVE);
+    if n.z >= 0.0 {
+        n.truncate()
+    } else {
+        (Vec2::ONE - Vec2::new(n.y, n.x).abs()) * Vec2::new(n.x, n.y).signum()
+    }
+}
+
+fn octahedral_decode(packed: Vec2) -> Vec3 {
+    let mut n = Vec3::new(packed.x, packed.y, 1.0 - packed.x.abs() - packed.y.abs());
+    let fold = (-n.z).max(0.0);
+    n.x -= fold.copysign(n.x);
+    n.y -= fold.copysign(n.y);
+    n.normalize_or_zero()
+}
@@ -0,0 +1,58 @@
+//! CPU picking against world geometry.
+
+use glam::Vec3;
+
+use crate::scene::{id::EntityId, section::Section, world::EntityRecord};
+
+/// Nearest surface under a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PickHit {
+    pub entity_id: EntityId,
+    /// World-space hit point.
+    pub point: Vec3,
+    /// Distance from the ray origin on the near plane.
+    pub distance: f32,
+}
+
+/// Möller–Trumbore intersection, both faces; returns the ray parameter.
+pub(crate) fn ray_triangle(origin: Vec3, dir: Vec3, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+    let (e1, e2) = (b - a, c - a);
+    let p = dir.cross(e2);
+    let det = e1.dot(p);
+    if det.abs() < f32::EPSILON * e1.length() * e2.length() {
+        return None;
+    }
+    let s = origin - a;
+    let u = s.dot(p) / det;
+    let q = s.cross(e1);
+    let v = dir.dot(q) / det;
+    let t = e2.dot(q) / det;
+    (u >= 0.0 && v >= 0.0 && u + v <= 1.0 && t >= 0.0).then_some(t)
+}
+
+/// Nearest hit on the triangles of `entities` that `section` keeps.
+pub(crate) fn pick<'a>(entities: impl Iterator<Item = (EntityId, &'a EntityRecord)>, section: &Section, origin: Vec3, dir: Vec3) -> Option<PickHit> {
+    let keep = section.keep_planes();
+    let kept = |point: Vec3| keep.iter().all(|k| k.dot(point.extend(1.0)) >= -1e-5);
+    let mut best: Option<PickHit> = None;
+    for (entity_id, record) in entities {
+        let Some(mesh) = &record.mesh else {
+            continue;
+        };
+        let world: Vec<Vec3> = mesh.vertices.iter().map(|v| record.model_matrix.transform_point3(v.position)).collect();
+        for tri in mesh.indices.chunks_exact(3) {
+            let Some(corners) = tri.iter().map(|&i| world.get(i as usize).copied()).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            let Some(t) = ray_triangle(origin, dir, [corners[0], corners[1], corners[2]]) else {
+                continue;
+            };
+            let point = origin + dir * t;
+            if best.is_some_and(|hit| hit.distance <= t) || !kept(point) {
+                continue;
+            }
+            best = Some(PickHit { entity_id, point, distance: t });
+        }
+    }
+    best
+}
@@ -0,0 +1,319 @@
+//! CPU rasterizer behind the reference backend.
+//!
+//! Conventions follow wgpu: clip-space depth in `[0, 1]`, pixel (0,0) at the
+//! top left, samples at pixel centres and the top-left fill rule so that
+//! triangles sharing an edge never cover a pixel twice.
+
+use glam::{UVec2, Vec2, Vec3, Vec4};
+
+use crate::scene::{
+    color::Rgba,
+    error::{SceneError, SceneResult},
+};
+
+/// Largest render target edge, in pixels; larger requests are clamped.
+pub const MAX_TARGET_EDGE: u32 = 8192;
+
+/// Colour and depth buffers of one viewport.
+#[derive(Debug, Clone)]
+pub struct RenderTarget {
+    size: UVec2,
+    color: Vec<Rgba>,
+    depth: Vec<f32>,
+}
+
+impl RenderTarget {
+    pub fn new(size: UVec2) -> Self {
+        let size = Self::clamp_size(size);
+        let len = size.x as usize * size.y as usize;
+        Self {
+            size,
+            color: vec![[0, 0, 0, 255]; len],
+            depth: vec![0.0; len],
+        }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgba {
+        self.color[(y * self.size.x + x) as usize]
+    }
+
+    pub fn pixels(&self) -> &[Rgba] {
+        &self.color
+    }
+
+    /// PNG (RGBA8) encoding of the colour buffer.
+    pub fn encode_png(&self) -> SceneResult<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.size.x, self.size.y);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|_| SceneError::Backend("png header encoding failed"))?;
+        writer
+            .write_image_data(self.color.as_flattened())
+            .map_err(|_| SceneError::Backend("png data encoding failed"))?;
+        writer.finish().map_err(|_| SceneError::Backend("png encoding failed"))?;
+        Ok(out)
+    }
+
+    fn clamp_size(size: UVec2) -> UVec2 {
+        size.clamp(UVec2::ONE, UVec2::splat(MAX_TARGET_EDGE))
+    }
+
+    pub(crate) fn resize(&mut self, size: UVec2) {
+        if Self::clamp_size(size) != self.size {
+            *self = Self::new(size);
+        }
+    }
+
+    pub(crate) fn clear(&mut self, color: Rgba, depth: f32) {
+        self.color.fill(color);
+        self.depth.fill(depth);
+    }
+
+    pub(crate) fn depth(&self, index: usize) -> f32 {
+        self.depth[index]
+    }
+
+    pub(crate) fn set_depth(&mut self, index: usize, depth: f32) {
+        self.depth[index] = depth;
+    }
+
+    pub(crate) fn set_color(&mut self, index: usize, color: Rgba) {
+        self.color[index] = color;
+    }
+
+    /// Blends `color` over the stored colour with the given coverage.
+    pub(crate) fn blend(&mut self, index: usize, color: Rgba, alpha: f32) {
+        let dst = &mut self.color[index];
+        for channel in 0..3 {
+            let mixed = color[channel] as f32 * alpha + dst[channel] as f32 * (1.0 - alpha);
+            dst[channel] = mixed.round() as u8;
+        }
+    }
+}
+
+/// Vertex after the model-view-projection transform.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ClipVertex {
+    pub clip: Vec4,
+    pub world: Vec3,
+}
+
+impl ClipVertex {
+    fn lerp(self, other: ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            clip: self.clip.lerp(other.clip, t),
+            world: self.world.lerp(other.world, t),
+        }
+    }
+}
+
+/// A point at a pixel position on the near plane, for screen-space drawing.
+pub(crate) fn pixel_vertex(size: UVec2, pos: Vec2) -> ClipVertex {
+    let ndc = Vec2::new(pos.x / size.x as f32 * 2.0 - 1.0, 1.0 - pos.y / size.y as f32 * 2.0);
+    ClipVertex { clip: ndc.extend(0.0).extend(1.0), world: Vec3::ZERO }
+}
+
+/// One covered pixel sample.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Fragment {
+    pub index: usize,
+    /// Depth in `[0, 1]` as produced by the projection.
+    pub depth: f32,
+    /// Triangles: counter-clockwise in NDC. Lines: always true.
+    pub ccw: bool,
+    /// Lines: position along the unclipped segment, `0` at `a` and `1` at `b`,
+    /// proportional to world distance. Triangles: `0`.
+    pub along: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScreenVertex {
+    pos: Vec2,
+    depth: f32,
+}
+
+impl ScreenVertex {
+    fn from_clip(v: &ClipVertex, size: UVec2) -> Self {
+        let ndc = v.clip.truncate() / v.clip.w;
+        Self {
+            pos: Vec2::new((ndc.x * 0.5 + 0.5) * size.x as f32, (0.5 - ndc.y * 0.5) * size.y as f32),
+            depth: ndc.z,
+        }
+    }
+}
+
+/// Clip planes bounding depth, followed by the world-space section planes.
+const DEPTH_PLANES: usize = 3;
+
+/// Distance of `v` to the inside of clip plane `plane`. Section planes are
+/// keep-functions `k · (world, 1) >= 0`.
+fn plane_distance(v: &ClipVertex, plane: usize, section: &[Vec4]) -> f32 {
+    match plane {
+        0 => v.clip.z,
+        1 => v.clip.w - v.clip.z,
+        2 => v.clip.w - 1e-6,
+        _ => section[plane - DEPTH_PLANES].dot(v.world.extend(1.0)),
+    }
+}
+
+/// Sutherland–Hodgman clip of a convex polygon against the depth range and section planes.
+fn clip_polygon(mut poly: Vec<ClipVertex>, section: &[Vec4]) -> Vec<ClipVertex> {
+    for plane in 0..DEPTH_PLANES + section.len() {
+        if poly.is_empty() {
+            break;
+        }
+        let mut out = Vec::with_capacity(poly.len() + 2);
+        for i in 0..poly.len() {
+            let (a, b) = (poly[i], poly[(i + 1) % poly.len()]);
+            let (da, db) = (plane_distance(&a, plane, section), plane_distance(&b, plane, section));
+            if da >= 0.0 {
+                out.push(a);
+            }
+            if (da >= 0.0) != (db >= 0.0) {
+                out.push(a.lerp(b, da / (da - db)));
+            }
+        }
+        poly = out;
+    }
+    poly
+}
+
+fn edge(a: Vec2, b: Vec2, p: Vec2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Top-left rule for screen-clockwise triangles (positive `edge` area).
+fn is_top_left(a: Vec2, b: Vec2) -> bool {
+    let d = b - a;
+    d.y < 0.0 || (d.y == 0.0 && d.x > 0.0)
+}
+
+/// Rasterizes the part of a triangle kept by `section`, calling `emit` for each covered pixel centre.
+pub(crate) fn triangle(size: UVec2, vertices: [ClipVertex; 3], section: &[Vec4], mut emit: impl FnMut(Fragment)) {
+    let poly = clip_polygon(vertices.to_vec(), section);
+    let screen: Vec<ScreenVertex> = poly.iter().map(|v| ScreenVertex::from_clip(v, size)).collect();
+    for i in 1..screen.len().saturating_sub(1) {
+        fill(size, [screen[0], screen[i], screen[i + 1]], &mut emit);
+    }
+}
+
+/// Fills a screen-space triangle; `ccw` is derived from its orientation.
+fn fill(size: UVec2, mut v: [ScreenVertex; 3], emit: &mut impl FnMut(Fragment)) {
+    let mut area = edge(v[0].pos, v[1].pos, v[2].pos);
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    // Screen space is y-down, so a negative area is counter-clockwise in NDC.
+    let ccw = area < 0.0;
+    if ccw {
+        v.swap(1, 2);
+        area = -area;
+    }
+    let min = v[0].pos.min(v[1].pos).min(v[2].pos).floor().max(Vec2::ZERO);
+    let max = v[0].pos.max(v[1].pos).max(v[2].pos).ceil().min(Vec2::new(size.x as f32, size.y as f32));
+    let edges = [(1, 2), (2, 0), (0, 1)];
+    let top_left = edges.map(|(a, b)| is_top_left(v[a].pos, v[b].pos));
+    for y in min.y as u32..max.y as u32 {
+        for x in min.x as u32..max.x as u32 {
+            let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let w = edges.map(|(a, b)| edge(v[a].pos, v[b].pos, p));
+            if (0..3).any(|i| w[i] < 0.0 || (w[i] == 0.0 && !top_left[i])) {
+                continue;
+            }
+            let b = Vec3::new(w[0], w[1], w[2]) / area;
+            emit(Fragment {
+                index: (y * size.x + x) as usize,
+                depth: b.x * v[0].depth + b.y * v[1].depth + b.z * v[2].depth,
+                ccw,
+                along: 0.0,
+            });
+        }
+    }
+}
+
+/// Length in pixels of a segment with both ends in front of the camera.
+pub(crate) fn screen_length(size: UVec2, a: &ClipVertex, b: &ClipVertex) -> Option<f32> {
+    if a.clip.w <= 1e-6 || b.clip.w <= 1e-6 {
+        return None;
+    }
+    Some(ScreenVertex::from_clip(a, size).pos.distance(ScreenVertex::from_clip(b, size).pos))
+}
+
+/// Rasterizes the part of a line segment kept by `section`, `width` pixels wide.
+pub(crate) fn line(size: UVec2, a: ClipVertex, b: ClipVertex, width: f32, section: &[Vec4], mut emit: impl FnMut(Fragment)) {
+    // Parametric clip against the same planes as triangles, then against the
+    // viewport widened by the line's half width and a pixel, so that only
+    // pixels on the target are stepped however far the ends project.
+    let guard = (width.max(1.0) + 2.0) / size.as_vec2();
+    let rect = |v: &ClipVertex| {
+        let (x, y, w) = (v.clip.x, v.clip.y, v.clip.w);
+        [w * (1.0 + guard.x) + x, w * (1.0 + guard.x) - x, w * (1.0 + guard.y) + y, w * (1.0 + guard.y) - y]
+    };
+    let depth = (0..DEPTH_PLANES + section.len()).map(|plane| (plane_distance(&a, plane, section), plane_distance(&b, plane, section)));
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (pa, pb) in depth.chain(rect(&a).into_iter().zip(rect(&b))) {
+        if !(pa >= 0.0 || pb >= 0.0) {
+            return;
+        }
+        if pa < 0.0 {
+            t0 = t0.max(pa / (pa - pb));
+        } else if pb < 0.0 {
+            t1 = t1.min(pa / (pa - pb));
+        }
+    }
+    if t0 >= t1 {
+        return;
+    }
+    let (ca, cb) = (a.lerp(b, t0), a.lerp(b, t1));
+    let (sa, sb) = (ScreenVertex::from_clip(&ca, size), ScreenVertex::from_clip(&cb, size));
+    // Perspective-correct map from screen parameter to segment parameter.
+    let along = |s: f32| {
+        let s = s.clamp(0.0, 1.0);
+        let u = s * ca.clip.w / ((1.0 - s) * cb.clip.w + s * ca.clip.w);
+        t0 + (t1 - t0) * u
+    };
+    let delta = sb.pos - sa.pos;
+    let length = delta.length();
+    if width <= 1.0 {
+        let steps = delta.x.abs().max(delta.y.abs()).ceil().max(1.0) as u32;
+        let mut last = None;
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            let pos = sa.pos + delta * t;
+            if pos.x < 0.0 || pos.y < 0.0 || pos.x >= size.x as f32 || pos.y >= size.y as f32 {
+                continue;
+            }
+            let (x, y) = (pos.x as u32, pos.y as u32);
+            if last == Some((x, y)) {
+                continue;
+            }
+            last = Some((x, y));
+            emit(Fragment {
+                index: (y * size.x + x) as usize,
+                depth: sa.depth + (sb.depth - sa.depth) * t,
+                ccw: true,
+                along: along(t),
+            });
+        }
+        return;
+    }
+    if length == 0.0 {
+        return;
+    }
+    // Wide lines are expanded into a screen-space quad.
+    let normal = Vec2::new(-delta.y, delta.x) / length * (width * 0.5);
+    let offset = |v: ScreenVertex, n: Vec2| ScreenVertex { pos: v.pos + n, ..v };
+    let quad = [offset(sa, normal), offset(sb, normal), offset(sb, -normal), offset(sa, -normal)];
+    let mut emit_line = |f: Fragment| {
+        let pixel = Vec2::new((f.index as u32 % size.x) as f32 + 0.5, (f.index as u32 / size.x) as f32 + 0.5);
+        let s = (pixel - sa.pos).dot(delta) / (length * length);
+        emit(Fragment { ccw: true, along: along(s), ..f })
+    };
+    fill(size, [quad[0], quad[1], quad[2]], &mut emit_line);
+    fill(size, [quad[0], quad[2], quad[3]], &mut emit_line);
+}
@@ -0,0 +1,161 @@
+//! Section planes and the section box used for cutaways.
+//!
+//! Geometry is clipped exactly, before rasterization, so that picking and
+//! rendering agree on what has been cut away.
+
+use glam::{Quat, Vec3, Vec4};
+
+use crate::scene::{
+    color::Rgba,
+    error::{SceneError, SceneResult},
+};
+
+/// Most clip planes a `Section` may hold, not counting the section box.
+pub const MAX_CLIP_PLANES: usize = 6;
+
+/// Removes the half-space the normal points into: points with `normal · p > distance`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ClipPlane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl ClipPlane {
+    /// Plane through `point` cutting away the side `normal` points to.
+    pub fn through(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize_or_zero();
+        Self { normal, distance: normal.dot(point) }
+    }
+
+    /// Horizontal cut keeping everything below `elevation` (Z-up).
+    pub fn horizontal(elevation: f32) -> Self {
+        Self { normal: Vec3::Z, distance: elevation }
+    }
+
+    /// `k · (p, 1) >= 0` for kept points.
+    fn keep(&self) -> Vec4 {
+        (-self.normal).extend(self.distance)
+    }
+}
+
+/// Oriented box; everything outside it is cut away.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SectionBox {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+    #[serde(default)]
+    pub rotation: Quat,
+}
+
+impl SectionBox {
+    fn planes(&self) -> [ClipPlane; 6] {
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| self.rotation * axis);
+        let mut planes = [ClipPlane { normal: Vec3::X, distance: 0.0 }; 6];
+        for (i, axis) in axes.into_iter().enumerate() {
+            let extent = self.half_extents[i];
+            planes[i * 2] = ClipPlane::through(self.center + axis * extent, axis);
+            planes[i * 2 + 1] = ClipPlane::through(self.center - axis * extent, -axis);
+        }
+        planes
+    }
+}
+
+/// How cut solids are closed off where a plane passes through them.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub enum CapStyle {
+    /// Interiors show through the cut.
+    None,
+    #[default]
+    Solid,
+    /// Parallel screen-space lines over the cap fill.
+    Hatch {
+        spacing_px: f32,
+        /// Line direction in degrees, counter-clockwise from screen right.
+        angle_deg: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Section {
+    #[serde(default)]
+    pub planes: Vec<ClipPlane>,
+    #[serde(default)]
+    pub section_box: Option<SectionBox>,
+    #[serde(default)]
+    pub cap: CapStyle,
+    /// Cap fill, or the hatch background.
+    pub cap_color: Rgba,
+    pub hatch_color: Rgba,
+}
+
+impl Default for Section {
+    fn default() -> Self {
+        Self {
+            planes: Vec::new(),
+            section_box: None,
+            cap: CapStyle::default(),
+            cap_color: [96, 100, 108, 255],
+            hatch_color: [24, 26, 30, 255],
+        }
+    }
+}
+
+impl Section {
+    pub fn validate(&self) -> SceneResult<()> {
+        if self.planes.len() > MAX_CLIP_PLANES {
+            return Err(SceneError::InvalidState("too many clip planes"));
+        }
+        let finite = |v: Vec3| v.is_finite();
+        for plane in &self.planes {
+            if !finite(plane.normal) || !plane.distance.is_finite() || (plane.normal.length() - 1.0).abs() > 1e-3 {
+                return Err(SceneError::InvalidState("clip plane normal must be a finite unit vector"));
+            }
+        }
+        if let Some(section_box) = &self.section_box {
+            if !finite(section_box.center) || !finite(section_box.half_extents) || section_box.half_extents.min_element() <= 0.0 {
+                return Err(SceneError::InvalidState("section box needs finite, positive extents"));
+            }
+            if !section_box.rotation.is_finite() || !section_box.rotation.is_normalized() {
+                return Err(SceneError::InvalidState("section box rotation must be a unit quaternion"));
+            }
+        }
+        if let CapStyle::Hatch { spacing_px, angle_deg } = self.cap {
+            if !spacing_px.is_finite() || spacing_px < 2.0 || !angle_deg.is_finite() {
+                return Err(SceneError::InvalidState("hatch spacing must be at least 2 pixels"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.planes.is_empty() || self.section_box.is_some()
+    }
+
+    /// Keep-functions of every plane, the box's included: `k · (p, 1) >= 0` is kept.
+    pub(crate) fn keep_planes(&self) -> Vec<Vec4> {
+        let box_planes = self.section_box.iter().flat_map(SectionBox::planes);
+        self.planes.iter().copied().chain(box_planes).map(|plane| plane.keep()).collect()
+    }
+
+    pub fn keeps(&self, point: Vec3) -> bool {
+        self.keep_planes().iter().all(|k| k.dot(point.extend(1.0)) >= 0.0)
+    }
+
+    /// Cap colour at a pixel.
+    pub(crate) fn cap_pixel(&self, x: u32, y: u32) -> Rgba {
+        match self.cap {
+            CapStyle::Hatch { spacing_px, angle_deg } => {
+                let (sin, cos) = angle_deg.to_radians().sin_cos();
+                // Distance across the hatch lines; screen y points down.
+                let across = (x as f32 + 0.5) * -sin + -(y as f32 + 0.5) * cos;
+                if across.rem_euclid(spacing_px) < 1.0 {
+                    self.hatch_color
+                } else {
+                    self.cap_color
+                }
+            }
+            _ => self.cap_color,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Floor plans cut from the 3D model.
+//!
+//! Triangles are intersected with a horizontal plane (Z-up) in world space.
+//! Intersection points are keyed on the welded mesh edge they lie on, so the
+//! segments of neighbouring triangles chain into polylines exactly.
+
+use std::collections::{HashMap, HashSet};
+
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::scene::{
+    edges::{FeatureEdges, DEFAULT_CREASE_ANGLE},
+    error::{SceneError, SceneResult},
+    id::EntityId,
+    transform::FrontFace,
+    visual::VisualFlags,
+    world::{EntityRecord, SceneWorld},
+};
+
+/// Where a plan is cut: the level's elevation plus the cut height above it.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlanCut {
+    pub elevation: f32,
+    pub cut_height: f32,
+    /// How far below `elevation` elements are still drawn in projection;
+    /// unlimited when `None`.
+    #[serde(default)]
+    pub view_depth: Option<f32>,
+}
+
+impl PlanCut {
+    pub fn new(elevation: f32, cut_height: f32) -> Self {
+        Self { elevation, cut_height, view_depth: None }
+    }
+
+    pub fn cut_z(&self) -> f32 {
+        self.elevation + self.cut_height
+    }
+
+    pub fn validate(&self) -> SceneResult<()> {
+        if !self.elevation.is_finite() || !self.cut_height.is_finite() {
+            return Err(SceneError::InvalidState("plan cut elevation must be finite"));
+        }
+        if self.view_depth.is_some_and(|depth| !depth.is_finite() || depth < 0.0) {
+            return Err(SceneError::InvalidState("view depth must be a non-negative distance"));
+        }
+        Ok(())
+    }
+}
+
+/// Plan-space polyline (world X/Y).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Polyline2 {
+    pub points: Vec<Vec2>,
+    /// The last point connects back to the first.
+    pub closed: bool,
+}
+
+/// What one entity contributes to a plan.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EntitySlice {
+    pub entity_id: EntityId,
+    /// Section outline at the cut. Closed solids give closed loops, outer
+    /// boundaries counter-clockwise and holes clockwise; open meshes may
+    /// leave open polylines.
+    pub cut: Vec<Polyline2>,
+    /// Projected outline of an entity entirely below the cut.
+    pub below: Vec<[Vec2; 2]>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlanSlice {
+    pub cut_z: f32,
+    /// Entities that are cut or drawn below the cut, in id order.
+    pub entities: Vec<EntitySlice>,
+}
+
+/// Slices every visible entity of `world`.
+pub(crate) fn slice_world(world: &SceneWorld, cut: &PlanCut) -> PlanSlice {
+    let cut_z = cut.cut_z();
+    let floor = cut.view_depth.map_or(f32::NEG_INFINITY, |depth| cut.elevation - depth);
+    let mut ids: Vec<_> = world.entities.keys().copied().collect();
+    ids.sort_unstable();
+    let mut entities = Vec::new();
+    for (entity_id, record) in ids.into_iter().map(|id| (id, &world.entities[&id])) {
+        let Some(bounds) = record.world_bounds() else {
+            continue;
+        };
+        if !record.visual.contains(VisualFlags::VISIBLE) || bounds.max.z < floor {
+            continue;
+        }
+        // Like vertices on the plane, elements touching it from below are cut.
+        let slice = if bounds.max.z < cut_z {
+            EntitySlice { entity_id, cut: Vec::new(), below: projected_outline(record) }
+        } else if bounds.min.z < cut_z {
+            EntitySlice { entity_id, cut: section_loops(record, cut_z), below: Vec::new() }
+        } else {
+            continue;
+        };
+        if !slice.cut.is_empty() || !slice.below.is_empty() {
+            entities.push(slice);
+        }
+    }
+    PlanSlice { cut_z, entities }
+}
+
+type PointKey = [u32; 3];
+
+/// Where a triangle edge crosses the plane: the welded edge and the plan point.
+type Crossing = ([PointKey; 2], Vec2);
+
+fn key(point: Vec3) -> PointKey {
+    point.to_array().map(f32::to_bits)
+}
+
+/// Chained intersection of the entity's triangles with `z = cut_z`.
+fn section_loops(record: &EntityRecord, cut_z: f32) -> Vec<Polyline2> {
+    let Some(mesh) = &record.mesh else {
+        return Vec::new();
+    };
+    let world: Vec<Vec3> = mesh.vertices.iter().map(|v| record.model_matrix.transform_point3(v.position)).collect();
+    let mirrored = record.front_face == FrontFace::Cw;
+    // Vertices on the plane count as above it, so no segment degenerates.
+    let above = |p: Vec3| p.z >= cut_z;
+    let crossing = |a: Vec3, b: Vec3| {
+        // A vertex on the plane is shared by all its edges, so key it alone.
+        if let Some(on) = [a, b].into_iter().find(|p| p.z == cut_z) {
+            return ([key(on); 2], on.truncate());
+        }
+        let (a, b) = if key(a) <= key(b) { (a, b) } else { (b, a) };
+        let t = (cut_z - a.z) / (b.z - a.z);
+        ([key(a), key(b)], a.lerp(b, t).truncate())
+    };
+
+    // Directed segments, keyed by the welded edges they start and end on.
+    let mut segments: Vec<(Crossing, Crossing)> = Vec::new();
+    for tri in mesh.indices.chunks_exact(3) {
+        let Some(p) = tri.iter().map(|&i| world.get(i as usize).copied()).collect::<Option<Vec<_>>>() else {
+            continue;
+        };
+        let mut hits = [(0, 1), (1, 2), (2, 0)].into_iter().filter(|&(a, b)| above(p[a]) != above(p[b])).map(|(a, b)| crossing(p[a], p[b]));
+        let (Some(start), Some(end)) = (hits.next(), hits.next()) else {
+            continue;
+        };
+        if start.0 == end.0 {
+            continue;
+        }
+        let normal = (p[1] - p[0]).cross(p[2] - p[0]);
+        let normal = if mirrored { -normal } else { normal };
+        // Keep the solid's interior on the left: along Z × normal.
+        let forward = Vec3::Z.cross(normal).truncate();
+        if (end.1 - start.1).dot(forward) >= 0.0 {
+            segments.push((start, end));
+        } else {
+            segments.push((end, start));
+        }
+    }
+    chain(segments)
+}
+
+fn chain(segments: Vec<(Crossing, Crossing)>) -> Vec<Polyline2> {
+    let mut outgoing: HashMap<[PointKey; 2], Vec<usize>> = HashMap::new();
+    let mut incoming: HashSet<[PointKey; 2]> = HashSet::new();
+    for (i, (start, end)) in segments.iter().enumerate() {
+        outgoing.entry(start.0).or_default().push(i);
+        incoming.insert(end.0);
+    }
+    let mut used = vec![false; segments.len()];
+    let mut polylines = Vec::new();
+    // Open chains first, from starts nothing leads into, then the loops.
+    let starts = (0..segments.len()).filter(|&i| !incoming.contains(&segments[i].0 .0)).chain(0..segments.len());
+    for first in starts.collect::<Vec<_>>() {
+        if used[first] {
+            continue;
+        }
+        let origin = segments[first].0 .0;
+        let mut points = vec![segments[first].0 .1];
+        let mut current = first;
+        let closed = loop {
+            used[current] = true;
+            let end = segments[current].1;
+            if end.0 == origin {
+                break true;
+            }
+            points.push(end.1);
+            match outgoing.get(&end.0).and_then(|next| next.iter().copied().find(|&n| !used[n])) {
+                Some(next) => current = next,
+                None => break false,
+            }
+        };
+        // Vertices lying on the plane repeat where two crossings meet.
+        points.dedup();
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() >= 2 {
+            polylines.push(Polyline2 { points, closed });
+        }
+    }
+    polylines
+}
+
+/// Outline seen from straight above, flattened onto the plan.
+fn projected_outline(record: &EntityRecord) -> Vec<[Vec2; 2]> {
+    let Some(mesh) = &record.mesh else {
+        return Vec::new();
+    };
+    let edges = FeatureEdges::extract(mesh, DEFAULT_CREASE_ANGLE);
+    let viewer = record.model_matrix.inverse() * Vec4::Z;
+    let flat = |i: u32| mesh.vertices.get(i as usize).map(|v| record.model_matrix.transform_point3(v.position).truncate());
+    let mut seen = HashSet::new();
+    let mut segments = Vec::new();
+    for [a, b] in edges.outline(viewer) {
+        let (Some(a), Some(b)) = (flat(a), flat(b)) else {
+            continue;
+        };
+        let (a, b) = if a.to_array().map(f32::to_bits) <= b.to_array().map(f32::to_bits) { (a, b) } else { (b, a) };
+        // Vertical edges collapse to points; stacked ones coincide.
+        if a != b && seen.insert([a, b].map(|p| p.to_array().map(f32::to_bits))) {
+            segments.push([a, b]);
+        }
+    }
+    segments
+}
@@ -0,0 +1,434 @@
+//! Object snaps: point input preprocessing.
+//!
+//! The cursor is turned into a `PointInputResult` before any command sees it.
+//! Candidates come from the edges of visible geometry near the cursor —
+//! polylines and the boundary and crease edges of triangle meshes — and must
+//! project within the aperture square around the cursor, except centres,
+//! which are offered while their curve is under the cursor. The enabled kind
+//! with the highest priority wins, the nearest on screen among equals.
+//! Polylines of at least `MIN_CURVE_SEGMENTS` segments whose points lie on a
+//! circle are treated as circles or arcs.
+
+use std::f32::consts::TAU;
+
+use glam::{Vec2, Vec3};
+
+use crate::scene::{
+    camera::CameraParams,
+    constraint::{ConstraintKind, Constrained},
+    edges::{FeatureEdges, DEFAULT_CREASE_ANGLE},
+    error::{SceneError, SceneResult},
+    id::EntityId,
+    overlay::MarkerGlyph,
+    section::Section,
+    world::EntityRecord,
+};
+
+/// Fewest segments a polyline needs before it is taken for a circle or arc.
+pub const MIN_CURVE_SEGMENTS: usize = 8;
+
+/// Snap kinds in priority order, highest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapKind {
+    Endpoint,
+    Intersection,
+    Midpoint,
+    Center,
+    /// Foot of the perpendicular from the previous point.
+    Perpendicular,
+    /// Tangent point on a circle from the previous point.
+    Tangent,
+    /// Closest point on an edge to the cursor.
+    Nearest,
+}
+
+impl SnapKind {
+    pub const ALL: [SnapKind; 7] = [
+        SnapKind::Endpoint,
+        SnapKind::Intersection,
+        SnapKind::Midpoint,
+        SnapKind::Center,
+        SnapKind::Perpendicular,
+        SnapKind::Tangent,
+        SnapKind::Nearest,
+    ];
+
+    /// Overlay marker shown at a point snapped this way.
+    pub fn glyph(self) -> MarkerGlyph {
+        match self {
+            SnapKind::Endpoint => MarkerGlyph::Square,
+            SnapKind::Intersection => MarkerGlyph::Cross,
+            SnapKind::Midpoint => MarkerGlyph::Triangle,
+            SnapKind::Center => MarkerGlyph::Circle,
+            SnapKind::Perpendicular => MarkerGlyph::Perpendicular,
+            SnapKind::Tangent => MarkerGlyph::Tangent,
+            SnapKind::Nearest => MarkerGlyph::Hourglass,
+        }
+    }
+}
+
+/// Which snaps are on, and how far from the cursor they reach.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SnapSettings {
+    pub endpoint: bool,
+    pub intersection: bool,
+    pub midpoint: bool,
+    pub center: bool,
+    pub perpendicular: bool,
+    pub tangent: bool,
+    pub nearest: bool,
+    /// Half the side of the square around the cursor that candidates must
+    /// project into, in pixels.
+    pub aperture_px: f32,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self { endpoint: true, intersection: true, midpoint: true, center: true, perpendicular: true, tangent: true, nearest: true, aperture_px: 10.0 }
+    }
+}
+
+impl SnapSettings {
+    pub fn validate(&self) -> SceneResult<()> {
+        if !self.aperture_px.is_finite() || self.aperture_px <= 0.0 {
+            return Err(SceneError::InvalidState("snap aperture must be positive"));
+        }
+        Ok(())
+    }
+
+    pub fn enabled(&self, kind: SnapKind) -> bool {
+        match kind {
+            SnapKind::Endpoint => self.endpoint,
+            SnapKind::Intersection => self.intersection,
+            SnapKind::Midpoint => self.midpoint,
+            SnapKind::Center => self.center,
+            SnapKind::Perpendicular => self.perpendicular,
+            SnapKind::Tangent => self.tangent,
+            SnapKind::Nearest => self.nearest,
+        }
+    }
+}
+
+/// A cursor position after snapping, in scene space.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PointInputResult {
+    /// The point before snapping: the surface under the cursor, else the
+    /// cursor on the work plane.
+    pub raw: Vec3,
+    /// `raw`, held to the constraint line if any, or the snap point that won.
+    pub snapped: Vec3,
+    pub snap_type: Option<SnapKind>,
+    /// Direction constraint `snapped` lies on.
+    #[serde(default)]
+    pub constraint: Option<ConstraintKind>,
+}
+
+/// Cursor state the snap engine works from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SnapQuery {
+    pub cursor: Vec2,
+    /// Previous point of the command, and the base of any constraint.
+    pub from: Option<Vec3>,
+    pub constrained: Option<Constrained>,
+}
+
+impl SnapQuery {
+    /// The constraint with its base point.
+    fn line(&self) -> Option<(Vec3, Constrained)> {
+        self.from.zip(self.constrained)
+    }
+}
+
+/// Edge of snappable geometry in scene space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Segment {
+    pub a: Vec3,
+    pub b: Vec3,
+    /// Index into `SnapGeometry::arcs` of the circle or arc this segment
+    /// tessellates; its vertices are then not endpoints.
+    pub curve: Option<usize>,
+}
+
+impl Segment {
+    pub fn direction(&self) -> Vec3 {
+        self.b - self.a
+    }
+
+    /// Parameter of the point on the segment's line closest to `point`.
+    pub fn project(&self, point: Vec3) -> f32 {
+        let d = self.direction();
+        (point - self.a).dot(d) / d.length_squared().max(f32::MIN_POSITIVE)
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.a + self.direction() * t
+    }
+}
+
+/// Circle or arc through the points of a polyline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Arc {
+    pub center: Vec3,
+    /// Unit normal; the arc runs counter-clockwise about it.
+    pub normal: Vec3,
+    pub radius: f32,
+    /// Unit direction from the centre to the start.
+    start: Vec3,
+    /// Swept angle, `TAU` for a full circle.
+    sweep: f32,
+}
+
+impl Arc {
+    /// Fits a circle to `points`, or `None` when they are not concyclic and
+    /// coplanar within a thousandth of the radius.
+    pub fn fit(points: &[Vec3]) -> Option<Arc> {
+        if points.len() < MIN_CURVE_SEGMENTS + 1 {
+            return None;
+        }
+        let closed = points[0].distance(points[points.len() - 1]) <= f32::EPSILON * points[0].length().max(1.0);
+        let open = if closed { &points[..points.len() - 1] } else { points };
+        let (a, b, c) = if closed { (open[0], open[open.len() / 3], open[2 * open.len() / 3]) } else { (open[0], open[open.len() / 2], open[open.len() - 1]) };
+        let (ab, ac) = (b - a, c - a);
+        let n = ab.cross(ac);
+        if n.length_squared() <= f32::EPSILON * ab.length_squared() * ac.length_squared() {
+            return None;
+        }
+        let center = a + (n.cross(ab) * ac.length_squared() + ac.cross(n) * ab.length_squared()) / (2.0 * n.length_squared());
+        let radius = a.distance(center);
+        let mut normal = n.normalize();
+        let tolerance = radius * 1e-3;
+        if open.iter().any(|p| (p.distance(center) - radius).abs() > tolerance || (*p - center).dot(normal).abs() > tolerance) {
+            return None;
+        }
+        // Orient the normal along the direction the points run.
+        if (open[0] - center).cross(open[1] - center).dot(normal) < 0.0 {
+            normal = -normal;
+        }
+        let mut arc = Arc { center, normal, radius, start: (open[0] - center) / radius, sweep: TAU };
+        if !closed {
+            arc.sweep = arc.angle(open[open.len() - 1]);
+        }
+        Some(arc)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sweep >= TAU
+    }
+
+    /// Counter-clockwise angle of `point` from the start, in `[0, TAU)`.
+    pub fn angle(&self, point: Vec3) -> f32 {
+        let offset = point - self.center;
+        let across = self.normal.cross(self.start);
+        offset.dot(across).atan2(offset.dot(self.start)).rem_euclid(TAU)
+    }
+
+    pub fn at(&self, angle: f32) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        self.center + (self.start * cos + self.normal.cross(self.start) * sin) * self.radius
+    }
+
+    /// Whether a point on the circle lies on the arc.
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.is_closed() || self.angle(point) <= self.sweep + 1e-4
+    }
+
+    /// `point` dropped onto the arc's plane.
+    fn flatten(&self, point: Vec3) -> Vec3 {
+        point - self.normal * (point - self.center).dot(self.normal)
+    }
+}
+
+/// Snappable edges and curves near the cursor.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnapGeometry {
+    pub segments: Vec<Segment>,
+    pub arcs: Vec<Arc>,
+}
+
+impl SnapGeometry {
+    /// Edges of `entities` whose bounds come within `aperture` of the cursor.
+    pub fn gather<'a>(entities: impl Iterator<Item = (EntityId, &'a EntityRecord)>, camera: &CameraParams, cursor: Vec2, aperture: f32) -> SnapGeometry {
+        let mut geometry = SnapGeometry::default();
+        for (_, record) in entities {
+            let (Some(mesh), Some(bounds)) = (&record.mesh, record.world_bounds()) else {
+                continue;
+            };
+            let corners: Option<Vec<Vec2>> = bounds.corners().iter().map(|c| camera.to_screen(*c)).collect();
+            // Bounds reaching behind the eye cannot be ruled out on screen.
+            if let Some(corners) = corners {
+                let min = corners.iter().fold(Vec2::INFINITY, |m, c| m.min(*c)) - aperture;
+                let max = corners.iter().fold(Vec2::NEG_INFINITY, |m, c| m.max(*c)) + aperture;
+                if cursor.cmplt(min).any() || cursor.cmpgt(max).any() {
+                    continue;
+                }
+            }
+            let scene = |i: u32| record.model_matrix.transform_point3(mesh.vertices[i as usize].position);
+            for polyline in &mesh.polylines {
+                let points: Vec<Vec3> = polyline.iter().map(|&i| scene(i)).collect();
+                let curve = Arc::fit(&points).map(|arc| {
+                    geometry.arcs.push(arc);
+                    geometry.arcs.len() - 1
+                });
+                geometry.segments.extend(points.windows(2).map(|pair| Segment { a: pair[0], b: pair[1], curve }));
+            }
+            if !mesh.indices.is_empty() {
+                let edges = FeatureEdges::extract(mesh, DEFAULT_CREASE_ANGLE);
+                geometry.segments.extend(edges.boundary.iter().chain(&edges.crease).map(|&[a, b]| Segment { a: scene(a), b: scene(b), curve: None }));
+            }
+        }
+        geometry
+    }
+}
+
+/// Point of the cursor ray on the work plane: horizontal through `from`, or
+/// through the scene origin, unless the view looks along it, in which case
+/// the plane faces the viewer.
+pub(crate) fn work_plane_point(camera: &CameraParams, cursor: Vec2, from: Option<Vec3>) -> Vec3 {
+    let (origin, dir) = camera.ray(cursor);
+    let anchor = from.unwrap_or(Vec3::ZERO);
+    let normal = if dir.z.abs() > 1e-3 { Vec3::Z } else { dir };
+    let t = (anchor - origin).dot(normal) / dir.dot(normal);
+    origin + dir * t
+}
+
+/// Parameters of the closest points of the lines `p + s·u` and `q + t·v`,
+/// or `None` when they are parallel.
+pub(crate) fn closest_params(p: Vec3, u: Vec3, q: Vec3, v: Vec3) -> Option<(f32, f32)> {
+    let w = p - q;
+    let (a, b, c, d, e) = (u.dot(u), u.dot(v), v.dot(v), u.dot(w), v.dot(w));
+    let denominator = a * c - b * b;
+    if denominator <= f32::EPSILON * a * c {
+        return None;
+    }
+    Some(((b * e - c * d) / denominator, (a * e - b * d) / denominator))
+}
+
+fn screen_distance(camera: &CameraParams, point: Vec3, cursor: Vec2) -> Option<f32> {
+    Some((camera.to_screen(point)? - cursor).abs().max_element())
+}
+
+/// Square distance from `cursor` to the projected segment, if both ends are in front of the eye.
+fn segment_screen_distance(camera: &CameraParams, segment: &Segment, cursor: Vec2) -> Option<f32> {
+    let (a, b) = (camera.to_screen(segment.a)?, camera.to_screen(segment.b)?);
+    let d = b - a;
+    let t = ((cursor - a).dot(d) / d.length_squared().max(f32::MIN_POSITIVE)).clamp(0.0, 1.0);
+    Some((a + d * t - cursor).abs().max_element())
+}
+
+/// Every candidate of the enabled kinds within the aperture, unordered.
+/// Under a constraint the aperture centres on the constrained point and only
+/// candidates on the constraint line are kept, plus the points where the
+/// line crosses nearby edges.
+pub(crate) fn candidates(geometry: &SnapGeometry, camera: &CameraParams, settings: &SnapSettings, query: &SnapQuery) -> Vec<(SnapKind, Vec3)> {
+    let aperture = settings.aperture_px;
+    let from = query.from;
+    let cursor = query.line().and_then(|(_, line)| camera.to_screen(line.point)).unwrap_or(query.cursor);
+    let near: Vec<&Segment> = geometry.segments.iter().filter(|s| segment_screen_distance(camera, s, cursor).is_some_and(|d| d <= aperture)).collect();
+    // Curves count as near when part of them or their centre is.
+    let near_arcs: Vec<&Arc> = geometry
+        .arcs
+        .iter()
+        .enumerate()
+        .filter(|(i, arc)| near.iter().any(|s| s.curve == Some(*i)) || screen_distance(camera, arc.center, cursor).is_some_and(|d| d <= aperture))
+        .map(|(_, arc)| arc)
+        .collect();
+    let mut out = Vec::new();
+    for segment in near.iter().filter(|s| s.curve.is_none()) {
+        out.push((SnapKind::Endpoint, segment.a));
+        out.push((SnapKind::Endpoint, segment.b));
+        out.push((SnapKind::Midpoint, segment.at(0.5)));
+    }
+    for arc in &near_arcs {
+        out.push((SnapKind::Center, arc.center));
+        if !arc.is_closed() {
+            out.push((SnapKind::Endpoint, arc.at(0.0)));
+            out.push((SnapKind::Endpoint, arc.at(arc.sweep)));
+            out.push((SnapKind::Midpoint, arc.at(arc.sweep * 0.5)));
+        }
+    }
+    for (i, s) in near.iter().enumerate() {
+        for t in &near[i + 1..] {
+            let Some((p, q)) = closest_params(s.a, s.direction(), t.a, t.direction()) else {
+                continue;
+            };
+            let within = |x: f32| (-1e-4..=1.0 + 1e-4).contains(&x);
+            let end = |x: f32| x.abs() <= 1e-4 || (x - 1.0).abs() <= 1e-4;
+            let (a, b) = (s.at(p), t.at(q));
+            let tolerance = 1e-4 * (s.direction().length() + t.direction().length());
+            // Segments joined end to end meet at an endpoint, not an intersection.
+            if within(p) && within(q) && !(end(p) && end(q)) && a.distance(b) <= tolerance {
+                out.push((SnapKind::Intersection, a));
+            }
+        }
+    }
+    if let Some((base, line)) = query.line() {
+        for segment in &near {
+            let Some((p, q)) = closest_params(base, line.direction, segment.a, segment.direction()) else {
+                continue;
+            };
+            let point = segment.at(q);
+            if p >= 0.0 && (0.0..=1.0).contains(&q) && line.distance_to_line(base, point) <= 1e-4 * segment.direction().length() {
+                out.push((SnapKind::Intersection, point));
+            }
+        }
+    }
+    if let Some(from) = from {
+        for segment in near.iter().filter(|s| s.curve.is_none()) {
+            let t = segment.project(from);
+            if (0.0..=1.0).contains(&t) {
+                out.push((SnapKind::Perpendicular, segment.at(t)));
+            }
+        }
+        for arc in &near_arcs {
+            let offset = arc.flatten(from) - arc.center;
+            let distance = offset.length();
+            if distance <= arc.radius * 1e-4 {
+                continue;
+            }
+            let toward = offset / distance;
+            let feet = [arc.center + toward * arc.radius, arc.center - toward * arc.radius];
+            out.extend(feet.into_iter().filter(|p| arc.contains(*p)).map(|p| (SnapKind::Perpendicular, p)));
+            // Tangent points exist only from outside the circle.
+            if distance > arc.radius {
+                let (sin, cos) = (arc.radius / distance).acos().sin_cos();
+                let across = arc.normal.cross(toward);
+                let tangents = [1.0, -1.0].map(|side| arc.center + (toward * cos + across * sin * side) * arc.radius);
+                out.extend(tangents.into_iter().filter(|p| arc.contains(*p)).map(|p| (SnapKind::Tangent, p)));
+            }
+        }
+    }
+    let (origin, dir) = camera.ray(cursor);
+    for segment in &near {
+        let Some((t, _)) = closest_params(segment.a, segment.direction(), origin, dir) else {
+            continue;
+        };
+        out.push((SnapKind::Nearest, segment.at(t.clamp(0.0, 1.0))));
+    }
+    // Centres are the exception: they are offered for any curve near the cursor.
+    out.retain(|(kind, point)| settings.enabled(*kind) && (*kind == SnapKind::Center || screen_distance(camera, *point, cursor).is_some_and(|d| d <= aperture)));
+    if let Some((base, line)) = query.line() {
+        out.retain(|(_, point)| line.distance_to_line(base, *point) <= 1e-4 * point.distance(base).max(1.0));
+    }
+    out
+}
+
+/// Snaps `raw`, or its constrained point, to the best candidate near the
+/// cursor; cut-away candidates are ignored.
+pub(crate) fn snap(geometry: &SnapGeometry, camera: &CameraParams, settings: &SnapSettings, section: &Section, query: &SnapQuery, raw: Vec3) -> PointInputResult {
+    let constrained = query.line().map(|(_, line)| line);
+    let target = constrained.map_or(raw, |line| line.point);
+    let cursor = constrained.and_then(|line| camera.to_screen(line.point)).unwrap_or(query.cursor);
+    let best = candidates(geometry, camera, settings, query)
+        .into_iter()
+        .filter(|(_, point)| section.keeps(*point))
+        .filter_map(|(kind, point)| Some((kind, point, (camera.to_screen(point)? - cursor).length())))
+        .min_by(|a, b| a.0.cmp(&b.0).then(a.2.total_cmp(&b.2)));
+    PointInputResult {
+        raw,
+        snapped: best.map_or(target, |(_, point, _)| point),
+        snap_type: best.map(|(kind, _, _)| kind),
+        constraint: constrained.map(|line| line.kind),
+    }
+}
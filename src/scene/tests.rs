@@ -1,5 +1,16 @@
-use super::{context::SceneContext, error::SceneError, id::EntityId, shape::KernelShape, tessellation::TessParams, visual::DirtyFlags};
-use crate::scene::mesh::{MeshData, Vertex};
+use std::collections::HashMap;
+
+use glam::{DVec3, Mat4, Quat, UVec2, Vec2, Vec3, Vec4};
+
+use super::{appearance::Appearance, backend::{BACKGROUND, EDGE, INDEX_BYTES, VERTEX_BYTES, WIRE}, bounds::{Aabb, Frustum}, camera::{Camera, CameraParams, Projection, UpAxis}, constraint::{self, ConstraintKind, ConstraintSettings}, context::SceneContext, decimate::{decimate, DecimateTarget}, edges::{FeatureEdges, DEFAULT_CREASE_ANGLE}, error::SceneError, export::ExportOptions, frame::{FrameStats, SyncReport, FRAME_HISTORY_LEN}, id::EntityId, import::{parse_obj, parse_stl}, linetype::{parse_lin, Linetype, PatternUnits}, lod::{self, LodPolicy}, section::{CapStyle, ClipPlane, Section, SectionBox, MAX_CLIP_PLANES}, shape::KernelShape, slice::{PlanCut, Polyline2}, tessellation::TessParams, transform::{FrontFace, Transform}, view::View, viewport::{DisplayMode, ViewportId}, visual::{DirtyFlags, HighlightStyle}};
+use crate::scene::{
+    mesh::{MeshData, Vertex},
+    overlay::{MarkerGlyph, OverlayItem, CROSSING_COLOR, HOT_GRIP_COLOR, MARKER_SIZE_PX, MAX_BAND_PX, WINDOW_COLOR},
+    packed::{decode_mesh, encode_mesh, PackOptions},
+    raster::RenderTarget,
+    snap::{PointInputResult, SnapKind, SnapSettings},
+    tracking::{GuideLine, TrackingKind, TrackingSettings, GUIDE_COLOR, MAX_TRACKING_POINTS, MIN_POLAR_INCREMENT_DEG},
+};
 
 struct DummyShape {
     mesh: MeshData,
@@ -19,6 +30,7 @@ fn simple_triangle() -> MeshData {
             Vertex { position: (0.0, 1.0, 0.0).into(), normal: (0.0, 0.0, 1.0).into(), uv: None },
         ],
         indices: vec![0, 1, 2],
+        ..MeshData::default()
     }
 }
 
@@ -41,7 +53,7 @@ fn remove_unknown_entity_errors() {
 #[test]
 fn empty_mesh_rejected() {
     let mut ctx = SceneContext::new();
-    let empty_shape = DummyShape { mesh: MeshData { vertices: vec![], indices: vec![] } };
+    let empty_shape = DummyShape { mesh: MeshData::default() };
     let err = ctx.submit_shape(None, &empty_shape, &TessParams::default()).unwrap_err();
     matches!(err, SceneError::ResourceMissing(_));
 }
@@ -55,3 +67,2009 @@ fn sync_clears_dirty_before_render() {
     ctx.render(&camera).unwrap();
     assert!(ctx.dirty_flags().is_empty());
 }
+
+#[test]
+fn singular_transform_rejected_and_record_kept() {
+    let mut ctx = SceneContext::new();
+    let shape = DummyShape { mesh: simple_triangle() };
+    let id = ctx.submit_shape(None, &shape, &TessParams::default()).unwrap();
+    let zero_scale = Transform::from_trs(Vec3::ZERO, Quat::IDENTITY, Vec3::new(1.0, 0.0, 1.0));
+    let err = ctx.set_transform(id, zero_scale).unwrap_err();
+    assert!(matches!(err, SceneError::InvalidTransform(_)));
+    assert_eq!(ctx.get_state(id).unwrap().transform.matrix, Mat4::IDENTITY);
+}
+
+#[test]
+fn non_finite_transform_rejected() {
+    let mut nan = Mat4::IDENTITY;
+    nan.w_axis.x = f32::NAN;
+    let err = Transform { matrix: nan }.validate(false).unwrap_err();
+    assert!(matches!(err, SceneError::InvalidTransform(_)));
+}
+
+#[test]
+fn projective_transform_rejected_only_when_affine_required() {
+    let mut ctx = SceneContext::new();
+    let shape = DummyShape { mesh: simple_triangle() };
+    let id = ctx.submit_shape(None, &shape, &TessParams::default()).unwrap();
+    let mut projective = Mat4::IDENTITY;
+    projective.x_axis.w = 0.5;
+    ctx.set_transform(id, Transform { matrix: projective }).unwrap();
+    ctx.set_affine_only(true);
+    let err = ctx.set_transform(id, Transform { matrix: projective }).unwrap_err();
+    assert!(matches!(err, SceneError::InvalidTransform(_)));
+}
+
+#[test]
+fn millimetre_scale_is_not_singular() {
+    let tiny = Transform::from_trs(Vec3::ZERO, Quat::IDENTITY, Vec3::splat(1e-3));
+    assert!(tiny.validate(true).is_ok());
+    assert!(tiny.normal_matrix().unwrap().is_finite());
+}
+
+#[test]
+fn mirrored_transform_flips_front_face() {
+    let mut ctx = SceneContext::new();
+    let shape = DummyShape { mesh: simple_triangle() };
+    let id = ctx.submit_shape(None, &shape, &TessParams::default()).unwrap();
+    let mirror = Transform::from_trs(Vec3::ZERO, Quat::IDENTITY, Vec3::new(-1.0, 1.0, 1.0));
+    assert_eq!(mirror.front_face(), FrontFace::Cw);
+    ctx.set_transform(id, mirror).unwrap();
+    assert!(ctx.get_state(id).unwrap().mirrored);
+    // Normals of a mirrored mesh must still point out of the surface.
+    let normal = mirror.normal_matrix().unwrap() * Vec3::X;
+    assert_eq!(normal, Vec3::NEG_X);
+}
+
+fn project(params: &CameraParams, point: Vec3) -> Vec3 {
+    params.view_proj().project_point3(point)
+}
+
+#[test]
+fn reversed_z_maps_near_to_one() {
+    let camera = Camera::look_at(Vec3::new(0.0, -10.0, 0.0), Vec3::ZERO, UpAxis::Z);
+    let params = camera.params(UVec2::new(800, 600));
+    let near = project(&params, Vec3::new(0.0, -10.0 + camera.near, 0.0));
+    let far = project(&params, Vec3::new(0.0, -10.0 + camera.far, 0.0));
+    assert!((near.z - 1.0).abs() < 1e-4);
+    assert!(far.z.abs() < 1e-4);
+    // Z-up: world +Z points up on screen (NDC +y), world +X to the right.
+    assert!(project(&params, Vec3::Z).y > 0.0);
+    assert!(project(&params, Vec3::X).x > 0.0);
+}
+
+#[test]
+fn top_view_along_up_axis_is_well_defined() {
+    let camera = Camera::look_at(Vec3::new(0.0, 0.0, 20.0), Vec3::ZERO, UpAxis::Z).orthographic(10.0);
+    let params = camera.params(UVec2::new(100, 100));
+    assert!(params.view.is_finite());
+    // Plan view: +Y is north (screen up), +X is east (screen right).
+    assert!(project(&params, Vec3::Y).y > 0.0);
+    assert!(project(&params, Vec3::X).x > 0.0);
+}
+
+#[test]
+fn orbit_keeps_distance_and_clamps_at_pole() {
+    let mut camera = Camera::default();
+    let distance = camera.distance();
+    camera.orbit(0.3, 0.2);
+    assert!((camera.distance() - distance).abs() < 1e-3);
+    camera.orbit(0.0, 10.0);
+    assert!(camera.forward().dot(Vec3::NEG_Z) < 1.0);
+    assert!(camera.view_matrix().is_finite());
+}
+
+#[test]
+fn zoom_at_keeps_point_under_cursor() {
+    let viewport = UVec2::new(800, 600);
+    let mut camera = Camera::look_at(Vec3::new(0.0, -10.0, 0.0), Vec3::ZERO, UpAxis::Z);
+    let cursor = Vec2::new(600.0, 150.0);
+    let anchor = camera.target_plane_point(cursor, viewport);
+    camera.zoom_at(cursor, viewport, 0.5).unwrap();
+    assert!((camera.distance() - 5.0).abs() < 1e-4);
+    assert!(camera.target_plane_point(cursor, viewport).distance(anchor) < 1e-4);
+
+    for factor in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(camera.zoom_at(cursor, viewport, factor).is_err());
+        assert!(camera.dolly(factor).is_err());
+    }
+    assert!((camera.distance() - 5.0).abs() < 1e-4);
+}
+
+#[test]
+fn degenerate_cameras_are_rejected() {
+    let camera = Camera::default();
+    assert!(camera.validate().is_ok());
+    let broken = [
+        Camera { target: camera.eye, ..camera },
+        Camera { eye: Vec3::NAN, ..camera },
+        Camera { near: 0.0, ..camera },
+        Camera { far: camera.near, ..camera },
+        camera.perspective(0.0),
+        camera.perspective(std::f32::consts::PI),
+        camera.orthographic(-1.0),
+    ];
+    for camera in broken {
+        assert!(matches!(camera.validate(), Err(SceneError::InvalidState(_))), "{camera:?}");
+    }
+    // Looking straight down the up axis falls back to another screen-up.
+    assert!(Camera::look_at(Vec3::Z, Vec3::ZERO, UpAxis::Z).validate().is_ok());
+}
+
+#[test]
+fn pan_moves_eye_and_target_together() {
+    let viewport = UVec2::new(100, 100);
+    let mut camera = Camera::look_at(Vec3::new(0.0, 0.0, 20.0), Vec3::ZERO, UpAxis::Z).orthographic(10.0);
+    camera.pan(Vec2::new(10.0, 0.0), viewport);
+    // Dragging right by 10% of a 10m wide view moves the camera 1m west.
+    assert!(camera.target.distance(Vec3::new(-1.0, 0.0, 0.0)) < 1e-5);
+    assert!((camera.distance() - 20.0).abs() < 1e-5);
+}
+
+#[test]
+fn zoom_extents_fits_visible_entities() {
+    let mut ctx = SceneContext::new();
+    let shape = DummyShape { mesh: simple_triangle() };
+    let id = ctx.submit_shape(None, &shape, &TessParams::default()).unwrap();
+    assert!(ctx.scene_bounds().is_none());
+    ctx.set_visibility(id, true).unwrap();
+    ctx.set_transform(id, Transform::from_trs(Vec3::new(100.0, 0.0, 0.0), Quat::IDENTITY, Vec3::ONE)).unwrap();
+    let bounds = ctx.scene_bounds().unwrap();
+    assert_eq!(bounds.min, Vec3::new(100.0, 0.0, 0.0));
+
+    let viewport = UVec2::new(640, 480);
+    let mut camera = Camera::default();
+    camera.zoom_extents(&bounds, viewport);
+    let params = camera.params(viewport);
+    for corner in bounds.corners() {
+        let ndc = project(&params, corner);
+        assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && (0.0..=1.0).contains(&ndc.z), "{corner} -> {ndc}");
+    }
+}
+
+#[test]
+fn zoom_window_centres_rectangle() {
+    let viewport = UVec2::new(100, 100);
+    let mut camera = Camera::look_at(Vec3::new(0.0, 0.0, 20.0), Vec3::ZERO, UpAxis::Z).orthographic(10.0);
+    camera.zoom_window(Vec2::new(50.0, 0.0), Vec2::new(100.0, 50.0), viewport);
+    assert!(camera.target.distance(Vec3::new(2.5, 2.5, 0.0)) < 1e-5);
+    assert_eq!(camera.projection, Projection::Orthographic { height: 5.0 });
+}
+
+#[test]
+fn named_views_save_restore_and_list() {
+    let mut ctx = SceneContext::new();
+    let plan = Camera::look_at(Vec3::new(3.0, 4.0, 50.0), Vec3::new(3.0, 4.0, 0.0), UpAxis::Z).orthographic(20.0);
+    ctx.set_current_view(View::new(plan, UVec2::new(320, 240)));
+    ctx.save_view("Plan 1F").unwrap();
+    assert_eq!(ctx.view_names(), vec!["Top", "Front", "Right", "Iso", "Plan 1F"]);
+
+    ctx.set_current_view(View::new(Camera::default(), UVec2::new(320, 240)));
+    assert_eq!(ctx.restore_view("Plan 1F").unwrap().camera, plan);
+    assert!(matches!(ctx.restore_view("missing"), Err(SceneError::UnknownView(_))));
+    assert!(matches!(ctx.save_view("top"), Err(SceneError::InvalidState(_))));
+    ctx.delete_view("Plan 1F").unwrap();
+    assert_eq!(ctx.view_names().len(), 4);
+}
+
+#[test]
+fn standard_top_view_fits_scene_from_above() {
+    let mut ctx = SceneContext::new();
+    let shape = DummyShape { mesh: simple_triangle() };
+    let id = ctx.submit_shape(None, &shape, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    let view = ctx.restore_view("Top").unwrap();
+    assert_eq!(view.camera.target, Vec3::new(0.5, 0.5, 0.0));
+    assert!(view.camera.forward().distance(Vec3::NEG_Z) < 1e-6);
+    assert_eq!(ctx.current_view(), view);
+    ctx.render_current().unwrap();
+    assert!(ctx.dirty_flags().is_empty());
+}
+
+fn plan_view(size: u32) -> View {
+    let camera = Camera::look_at(Vec3::new(0.25, 0.25, 5.0), Vec3::new(0.25, 0.25, 0.0), UpAxis::Z).orthographic(1.0);
+    View::new(camera, UVec2::splat(size))
+}
+
+fn visible_triangle(ctx: &mut SceneContext) -> EntityId {
+    let shape = DummyShape { mesh: simple_triangle() };
+    let id = ctx.submit_shape(None, &shape, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    id
+}
+
+#[test]
+fn viewports_render_independently_with_visibility_overrides() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    let side = ctx.create_viewport(plan_view(8));
+    ctx.set_viewport_visibility(side, id, Some(false)).unwrap();
+
+    ctx.render_current().unwrap();
+    ctx.render_viewport(side).unwrap();
+    let main = ctx.render_target(ViewportId::MAIN).unwrap();
+    assert_eq!(main.size(), UVec2::splat(16));
+    assert_ne!(main.pixel(8, 8), BACKGROUND);
+    assert_eq!(main.pixel(15, 0), BACKGROUND);
+    let side_target = ctx.render_target(side).unwrap();
+    assert_eq!(side_target.size(), UVec2::splat(8));
+    assert!(side_target.pixels().iter().all(|p| *p == BACKGROUND));
+
+    ctx.set_viewport_visibility(side, id, None).unwrap();
+    ctx.render_viewport(side).unwrap();
+    assert_ne!(ctx.render_target(side).unwrap().pixel(4, 4), BACKGROUND);
+}
+
+#[test]
+fn oversized_viewports_are_capped_and_long_lines_clipped() {
+    let mut ctx = SceneContext::new();
+    let wide = ctx.create_viewport(View::new(plan_view(16).camera, UVec2::new(70_000, 4)));
+    assert_eq!(ctx.viewport_view(wide).unwrap().viewport, UVec2::new(8192, 4));
+    assert!(View::new(Camera::default(), UVec2::new(70_000, 4)).validate().is_err());
+
+    // Hundreds of millions of pixels long on screen; only the visible part is stepped.
+    let id = ctx.submit_shape(None, &DummyShape { mesh: polyline_mesh(&[Vec2::new(-1e7, 0.25), Vec2::new(1e7, 0.25)]) }, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    ctx.set_current_view(plan_view(16));
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    assert!((0..16).all(|x| target.pixel(x, 8) != BACKGROUND));
+}
+
+#[test]
+fn wireframe_draws_only_edges() {
+    let mut ctx = SceneContext::new();
+    visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    ctx.set_display_mode(ViewportId::MAIN, DisplayMode::Wireframe).unwrap();
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    // Interior is empty, the x = 0 edge is drawn.
+    assert_eq!(target.pixel(8, 8), BACKGROUND);
+    assert_ne!(target.pixel(4, 8), BACKGROUND);
+}
+
+#[test]
+fn back_face_culling_respects_mirroring() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    // Seen from below the triangle is a back face...
+    let below = Camera::look_at(Vec3::new(0.25, 0.25, -5.0), Vec3::new(0.25, 0.25, 0.0), UpAxis::Z).orthographic(1.0);
+    ctx.set_current_view(View::new(below, UVec2::splat(16)));
+    ctx.set_back_face_culling(ViewportId::MAIN, true).unwrap();
+    ctx.render_current().unwrap();
+    assert_eq!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8), BACKGROUND);
+    // ...until a mirror through z = 0 flips both the mesh and its winding.
+    let mirror = Transform::from_trs(Vec3::ZERO, Quat::IDENTITY, Vec3::new(1.0, 1.0, -1.0));
+    ctx.set_transform(id, mirror).unwrap();
+    ctx.set_current_view(plan_view(16));
+    ctx.render_current().unwrap();
+    assert_eq!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8), BACKGROUND);
+    ctx.set_current_view(View::new(below, UVec2::splat(16)));
+    ctx.render_current().unwrap();
+    assert_ne!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8), BACKGROUND);
+}
+
+#[test]
+fn screenshot_rerenders_stale_viewport() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(plan_view(16));
+    let before = ctx.screenshot(ViewportId::MAIN).unwrap();
+    visible_triangle(&mut ctx);
+    let after = ctx.screenshot(ViewportId::MAIN).unwrap();
+    assert_ne!(before, after);
+    assert!(matches!(ctx.screenshot(ViewportId(42)), Err(SceneError::UnknownViewport(42))));
+    assert!(matches!(ctx.remove_viewport(ViewportId::MAIN), Err(SceneError::InvalidState(_))));
+}
+
+#[test]
+fn frames_count_up_and_report_synced_entities() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(plan_view(16));
+    let id = visible_triangle(&mut ctx);
+    let other = visible_triangle(&mut ctx);
+    ctx.render_current().unwrap();
+    let first = ctx.last_frame().unwrap().clone();
+    assert_eq!(first.frame_id, 1);
+    assert_eq!(first.sync, SyncReport { geometry: 2, transform: 2, visual: 2, uploaded_bytes: 216 });
+    let untimed = FrameStats { sync_micros: 0, raster_micros: 0, ..first.stats };
+    assert_eq!(untimed, FrameStats { entities_considered: 2, entities_drawn: 2, triangles: 2, ..FrameStats::default() });
+
+    ctx.set_highlight(id, true).unwrap();
+    ctx.set_transform(other, Transform::from_trs(Vec3::X, Quat::IDENTITY, Vec3::ONE)).unwrap();
+    ctx.render_current().unwrap();
+    let second = ctx.last_frame().unwrap();
+    assert_eq!(second.frame_id, 2);
+    assert_eq!(second.sync, SyncReport { geometry: 0, transform: 1, visual: 1, uploaded_bytes: 0 });
+
+    ctx.render_current().unwrap();
+    assert_eq!(ctx.last_frame().unwrap().sync, SyncReport::default());
+}
+
+#[test]
+fn frame_screenshots_come_from_history() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(plan_view(16));
+    let empty = ctx.screenshot(ViewportId::MAIN).unwrap();
+    let empty_frame = ctx.latest_frame(ViewportId::MAIN).unwrap().frame_id;
+    visible_triangle(&mut ctx);
+    ctx.render_current().unwrap();
+    let uncaptured = ctx.last_frame().unwrap().frame_id;
+    ctx.render_current().unwrap();
+
+    // Captured frames keep their image; older uncaptured ones are gone.
+    assert_eq!(ctx.frame_screenshot(empty_frame).unwrap(), empty);
+    assert!(matches!(ctx.frame_screenshot(uncaptured), Err(SceneError::ResourceMissing(_))));
+    let latest = ctx.last_frame().unwrap().frame_id;
+    assert_ne!(ctx.frame_screenshot(latest).unwrap(), empty);
+    assert!(ctx.frame(latest).unwrap().image.is_some());
+
+    for _ in 0..FRAME_HISTORY_LEN {
+        ctx.render_current().unwrap();
+    }
+    assert!(matches!(ctx.frame_screenshot(empty_frame), Err(SceneError::UnknownFrame(_))));
+}
+
+fn quad(min: Vec2, max: Vec2, z: f32) -> MeshData {
+    let vertex = |x: f32, y: f32| Vertex { position: Vec3::new(x, y, z), normal: Vec3::Z, uv: None };
+    MeshData {
+        vertices: vec![vertex(min.x, min.y), vertex(max.x, min.y), vertex(max.x, max.y), vertex(min.x, max.y)],
+        indices: vec![0, 1, 2, 0, 2, 3],
+        ..MeshData::default()
+    }
+}
+
+/// Triangle at z = 0 partly covered by a square at z = 0.5, seen from above.
+fn display_mode_fixture(mode: DisplayMode) -> (Vec<String>, RenderTarget) {
+    let mut ctx = SceneContext::new();
+    for mesh in [simple_triangle(), quad(Vec2::splat(0.25), Vec2::splat(0.75), 0.5)] {
+        let id = ctx.submit_shape(None, &DummyShape { mesh }, &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+    }
+    let camera = Camera::look_at(Vec3::new(0.5, 0.5, 5.0), Vec3::new(0.5, 0.5, 0.0), UpAxis::Z).orthographic(1.5);
+    ctx.set_current_view(View::new(camera, UVec2::splat(16)));
+    ctx.set_display_mode(ViewportId::MAIN, mode).unwrap();
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap().clone();
+    (ascii(&target), target)
+}
+
+/// One character per pixel: background `.`, wire `+`, edge `-`, surface `#`.
+fn ascii(target: &RenderTarget) -> Vec<String> {
+    let size = target.size();
+    (0..size.y)
+        .map(|y| {
+            (0..size.x)
+                .map(|x| match target.pixel(x, y) {
+                    p if p == BACKGROUND => '.',
+                    p if p == WIRE => '+',
+                    p if p == EDGE => '-',
+                    _ => '#',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+const SHADED: [&str; 16] = [
+    "................",
+    "................",
+    "................",
+    "................",
+    "...#............",
+    "...########.....",
+    "...########.....",
+    "...########.....",
+    "...########.....",
+    "...########.....",
+    "...########.....",
+    "...########.....",
+    "...#########....",
+    "................",
+    "................",
+    "................",
+];
+
+const WIREFRAME: [&str; 16] = [
+    "................",
+    "................",
+    "..+.............",
+    "..++............",
+    "..+.+...........",
+    "..+..++++++.....",
+    "..+..++..++.....",
+    "..+..+.++.+.....",
+    "..+..+.++.+.....",
+    "..+..++..++.....",
+    "..+..++++++.....",
+    "..+........+....",
+    "..+.........+...",
+    "..++++++++++++..",
+    "................",
+    "................",
+];
+
+const SHADED_WITH_EDGES: [&str; 16] = [
+    "................",
+    "................",
+    "..-.............",
+    "..--............",
+    "..-#-...........",
+    "..-##------.....",
+    "..-##-###--.....",
+    "..-##-##-#-.....",
+    "..-##-#--#-.....",
+    "..-##--###-.....",
+    "..-##------.....",
+    "..-########-....",
+    "..-#########-...",
+    "..------------..",
+    "................",
+    "................",
+];
+
+/// The triangle's hypotenuse disappears behind the square.
+const HIDDEN_LINE: [&str; 16] = [
+    "................",
+    "................",
+    "..+.............",
+    "..++............",
+    "..+.+...........",
+    "..+..++++++.....",
+    "..+..+...++.....",
+    "..+..+..+.+.....",
+    "..+..+.++.+.....",
+    "..+..++...+.....",
+    "..+..++++++.....",
+    "..+........+....",
+    "..+.........+...",
+    "..++++++++++++..",
+    "................",
+    "................",
+];
+
+#[test]
+fn display_mode_screenshots_match_fixtures() {
+    assert_eq!(display_mode_fixture(DisplayMode::Shaded).0, SHADED);
+    assert_eq!(display_mode_fixture(DisplayMode::Wireframe).0, WIREFRAME);
+    assert_eq!(display_mode_fixture(DisplayMode::ShadedWithEdges).0, SHADED_WITH_EDGES);
+    assert_eq!(display_mode_fixture(DisplayMode::HiddenLine).0, HIDDEN_LINE);
+}
+
+#[test]
+fn xray_blends_overlapping_surfaces() {
+    let (mask, target) = display_mode_fixture(DisplayMode::XRay);
+    assert_eq!(mask, SHADED);
+    let (single, overlap) = (target.pixel(4, 8), target.pixel(6, 8));
+    assert_ne!(single, BACKGROUND);
+    assert_ne!(overlap, single);
+    // Translucent layers only lighten the dark background, never reach the opaque colour.
+    let (_, shaded) = display_mode_fixture(DisplayMode::Shaded);
+    assert!(single[0] < overlap[0] && overlap[0] < shaded.pixel(6, 8)[0]);
+}
+
+/// Unit cube with one vertex copy per face, as a B-rep tessellator emits it.
+fn split_cube() -> MeshData {
+    let mut mesh = MeshData::default();
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z, Vec3::NEG_X, Vec3::NEG_Y, Vec3::NEG_Z] {
+        let (u, v) = axis.any_orthonormal_pair();
+        let base = mesh.vertices.len() as u32;
+        for (a, b) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let position = (axis + u * a + v * b) * 0.5;
+            mesh.vertices.push(Vertex { position, normal: axis, uv: None });
+        }
+        // Wind counter-clockwise around the outward normal.
+        let order = if u.cross(v).dot(axis) > 0.0 { [0, 1, 2, 0, 2, 3] } else { [0, 2, 1, 0, 3, 2] };
+        mesh.indices.extend(order.map(|i| base + i));
+    }
+    mesh
+}
+
+/// Two faces meeting at a shallow ridge along the x axis.
+fn shallow_roof() -> MeshData {
+    let vertex = |x: f32, y: f32, z: f32| Vertex { position: Vec3::new(x, y, z), normal: Vec3::Z, uv: None };
+    MeshData {
+        vertices: vec![vertex(0.0, -1.0, 0.0), vertex(1.0, -1.0, 0.0), vertex(1.0, 0.0, 0.2), vertex(0.0, 0.0, 0.2), vertex(1.0, 1.0, 0.0), vertex(0.0, 1.0, 0.0)],
+        indices: vec![0, 1, 2, 0, 2, 3, 3, 2, 4, 3, 4, 5],
+        ..MeshData::default()
+    }
+}
+
+#[test]
+fn split_cube_has_only_crease_edges() {
+    let edges = FeatureEdges::extract(&split_cube(), DEFAULT_CREASE_ANGLE);
+    assert!(edges.boundary.is_empty());
+    assert_eq!(edges.crease.len(), 12);
+    // Face diagonals are flat and never on a cube's silhouette.
+    assert!(edges.silhouette(Vec4::new(3.0, 2.0, 1.0, 1.0)).is_empty());
+}
+
+#[test]
+fn shallow_ridge_is_silhouette_only_when_seen_edge_on() {
+    let edges = FeatureEdges::extract(&shallow_roof(), DEFAULT_CREASE_ANGLE);
+    assert_eq!(edges.boundary.len(), 6);
+    assert!(edges.crease.is_empty());
+    assert!(edges.silhouette(Vec4::Z).is_empty());
+    // Looking along +y just above the slope, one face turns away.
+    let ridge = edges.silhouette(Vec4::new(0.0, 1.0, 0.05, 0.0));
+    assert_eq!(ridge.len(), 1);
+    assert_eq!(ridge[0].map(|i| shallow_roof().vertices[i as usize].position.z), [0.2, 0.2]);
+    // A perspective eye far out on +y sees the same.
+    assert_eq!(edges.silhouette(Vec4::new(0.5, 50.0, 2.0, 1.0)), ridge);
+    assert_eq!(FeatureEdges::extract(&shallow_roof(), 0.1).crease.len(), 1);
+}
+
+#[test]
+fn feature_edges_follow_geometry_changes() {
+    let mut ctx = SceneContext::new();
+    let id = ctx.submit_shape(None, &DummyShape { mesh: split_cube() }, &TessParams::default()).unwrap();
+    assert_eq!(ctx.feature_edges(id).unwrap().crease.len(), 12);
+    // Reading edges leaves the dirty set to the next render.
+    assert!(ctx.dirty_flags().contains(DirtyFlags::GEOMETRY));
+    ctx.remove(id).unwrap();
+    ctx.submit_shape(Some(id), &DummyShape { mesh: simple_triangle() }, &TessParams::default()).unwrap();
+    let edges = ctx.feature_edges(id).unwrap();
+    assert_eq!((edges.boundary.len(), edges.crease.len()), (3, 0));
+    assert!(matches!(ctx.feature_edges(EntityId(99)), Err(SceneError::UnknownEntity(99))));
+}
+
+#[test]
+fn feature_wireframe_skips_flat_diagonals() {
+    let mut ctx = SceneContext::new();
+    let id = ctx.submit_shape(None, &DummyShape { mesh: quad(Vec2::ZERO, Vec2::ONE, 0.0) }, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    let camera = Camera::look_at(Vec3::new(0.5, 0.5, 5.0), Vec3::new(0.5, 0.5, 0.0), UpAxis::Z).orthographic(1.5);
+    ctx.set_current_view(View::new(camera, UVec2::splat(16)));
+    ctx.set_display_mode(ViewportId::MAIN, DisplayMode::Wireframe).unwrap();
+    let interior_wire = |ctx: &mut SceneContext| {
+        ctx.render_current().unwrap();
+        let target = ctx.render_target(ViewportId::MAIN).unwrap();
+        (5..11).any(|x| target.pixel(x, 8) == WIRE)
+    };
+    assert!(interior_wire(&mut ctx));
+    ctx.set_feature_wireframe(ViewportId::MAIN, true).unwrap();
+    assert!(!interior_wire(&mut ctx));
+}
+
+#[test]
+fn highlight_restyles_pixels_without_geometry_sync() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    let plain = ctx.screenshot(ViewportId::MAIN).unwrap();
+    let (inside, outside) = (ctx.render_target(ViewportId::MAIN).unwrap().pixel(6, 10), (3, 10));
+    ctx.set_highlight(id, true).unwrap();
+    let hovered = ctx.screenshot(ViewportId::MAIN).unwrap();
+    let frame = ctx.last_frame().unwrap();
+    assert_eq!(frame.sync, SyncReport { geometry: 0, transform: 0, visual: 1, uploaded_bytes: 0 });
+    assert_ne!(plain, hovered);
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    assert_ne!(target.pixel(6, 10), inside);
+    // The outline reaches past the triangle's left edge.
+    assert_eq!(target.pixel(outside.0, outside.1), HighlightStyle::default().highlight_color);
+
+    ctx.set_highlight(id, false).unwrap();
+    assert_eq!(ctx.screenshot(ViewportId::MAIN).unwrap(), plain);
+}
+
+#[test]
+fn geometry_rebuild_keeps_visual_style() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    ctx.set_highlight(id, true).unwrap();
+    let hovered = ctx.screenshot(ViewportId::MAIN).unwrap();
+    // Rebuilds the instance without touching its visual state.
+    ctx.clear_lods(id).unwrap();
+    assert_eq!(ctx.screenshot(ViewportId::MAIN).unwrap(), hovered);
+    let sync = ctx.last_frame().unwrap().sync;
+    assert_eq!((sync.geometry, sync.visual), (1, 0));
+}
+
+#[test]
+fn selection_style_is_configurable() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    ctx.set_selected(id, true).unwrap();
+    let style = HighlightStyle { selection_color: [255, 0, 0, 255], outline_px: 0.0, ..HighlightStyle::default() };
+    ctx.set_highlight_style(style).unwrap();
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    assert!(!target.pixels().contains(&[255, 0, 0, 255]));
+    let tinted = target.pixel(6, 10);
+    assert!(tinted[0] > tinted[2]);
+    let invalid = HighlightStyle { hover_tint: 2.0, ..style };
+    assert!(matches!(ctx.set_highlight_style(invalid), Err(SceneError::InvalidState(_))));
+}
+
+#[test]
+fn appearance_recolours_and_blends_without_geometry_sync() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    ctx.render_current().unwrap();
+    let red = Appearance { color: [255, 0, 0], ..Appearance::default() };
+    ctx.set_appearance(id, red.clone()).unwrap();
+    assert_eq!(ctx.dirty_flags(), DirtyFlags::VISUAL);
+    ctx.render_current().unwrap();
+    assert_eq!(ctx.last_frame().unwrap().sync, SyncReport { geometry: 0, transform: 0, visual: 1, uploaded_bytes: 0 });
+    let opaque = ctx.render_target(ViewportId::MAIN).unwrap().pixel(6, 10);
+    assert!(opaque[0] > 200 && opaque[1] == 0);
+
+    ctx.set_appearance(id, Appearance { opacity: 0.5, ..red }).unwrap();
+    ctx.render_current().unwrap();
+    let blended = ctx.render_target(ViewportId::MAIN).unwrap().pixel(6, 10);
+    assert!(blended[0] < opaque[0] && blended[1] > 0);
+    assert_eq!(ctx.get_state(id).unwrap().appearance.opacity, 0.5);
+    let empty_linetype = Appearance { linetype: " ".to_owned(), ..Appearance::default() };
+    assert!(matches!(ctx.set_appearance(id, empty_linetype), Err(SceneError::InvalidState(_))));
+}
+
+const SAMPLE_LIN: &str = "\
+;; Custom linetypes
+*BORDER,Border __ __ . __ __ .
+A,.5,-.25,.5,-.25,0,-.25
+*FENCE,Fenceline ----0----
+A,.25,-.1,[CIRC1,ltypeshp.shx,x=-.1,s=.1],-.1,1
+";
+
+#[test]
+fn lin_parser_reads_patterns_and_reports_lines() {
+    let parsed = parse_lin(SAMPLE_LIN.lines().take(3).collect::<Vec<_>>().join("\n").as_str()).unwrap();
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].name, "BORDER");
+    assert_eq!(parsed[0].pattern, [0.5, -0.25, 0.5, -0.25, 0.0, -0.25]);
+    assert!(matches!(parse_lin(SAMPLE_LIN), Err(SceneError::Parse { line: 5, .. })));
+    assert!(matches!(parse_lin("A,.5,-.25"), Err(SceneError::Parse { line: 1, .. })));
+    assert!(matches!(parse_lin("*A,\nA,.5\n*B,no pattern"), Err(SceneError::Parse { line: 3, .. })));
+    assert!(matches!(parse_lin("*ZERO\nA,0,0"), Err(SceneError::Parse { line: 2, .. })));
+}
+
+#[test]
+fn linetype_pattern_phase() {
+    let dashdot = Linetype { name: "DASHDOT".into(), description: String::new(), pattern: vec![0.5, -0.25, 0.0, -0.25], units: PatternUnits::World };
+    assert!(dashdot.inked(0.1, 0.01));
+    assert!(!dashdot.inked(0.6, 0.01));
+    assert!(dashdot.inked(0.75, 0.01));
+    assert!(!dashdot.inked(0.9, 0.01));
+    // Phase wraps at the period and is continuous for negative distances.
+    assert!(dashdot.inked(1.1, 0.01));
+    assert!(!dashdot.inked(-0.1, 0.01));
+    assert!(Linetype::continuous().inked(123.0, 0.0));
+}
+
+fn polyline_mesh(points: &[Vec2]) -> MeshData {
+    MeshData {
+        vertices: points.iter().map(|p| Vertex { position: p.extend(0.0), normal: Vec3::Z, uv: None }).collect(),
+        polylines: vec![(0..points.len() as u32).collect()],
+        ..MeshData::default()
+    }
+}
+
+/// Renders a polyline along y = 0, at 40 px per unit for `height` 0.5, and
+/// returns the inked columns of the row it lands on plus the number of inked rows.
+fn render_polyline(points: &[Vec2], appearance: Appearance, pixels_per_mm: f32, height: f32, linetype: Option<Linetype>) -> (String, usize) {
+    let mut ctx = SceneContext::new();
+    if let Some(linetype) = linetype {
+        ctx.define_linetype(linetype).unwrap();
+    }
+    let id = ctx.submit_shape(None, &DummyShape { mesh: polyline_mesh(points) }, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    ctx.set_appearance(id, appearance).unwrap();
+    let camera = Camera::look_at(Vec3::new(1.0, 0.0, 5.0), Vec3::new(1.0, 0.0, 0.0), UpAxis::Z).orthographic(height);
+    ctx.set_current_view(View::new(camera, UVec2::new(80, 20)));
+    ctx.set_plot_scale(ViewportId::MAIN, pixels_per_mm).unwrap();
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    let inked: Vec<u32> = (0..20).filter(|&y| (0..80).any(|x| target.pixel(x, y) != BACKGROUND)).collect();
+    let row = inked.first().copied().unwrap_or_default();
+    let columns = (0..80).map(|x| if target.pixel(x, row) == BACKGROUND { '.' } else { '#' }).collect();
+    (columns, inked.len())
+}
+
+#[test]
+fn dashes_continue_across_polyline_vertices() {
+    let dashed = Appearance { linetype: "dashed".into(), lineweight: 0.0, ..Appearance::default() };
+    let (straight, _) = render_polyline(&[Vec2::ZERO, Vec2::new(2.0, 0.0)], dashed.clone(), 1.0, 0.5, None);
+    let (split, _) = render_polyline(&[Vec2::ZERO, Vec2::new(0.3, 0.0), Vec2::new(2.0, 0.0)], dashed, 1.0, 0.5, None);
+    // 0.5 dash, 0.25 gap at 40 px per unit.
+    let expected = format!("{0}{1}{0}{1}{0}", "#".repeat(20), ".".repeat(10));
+    assert_eq!(straight, expected);
+    assert_eq!(split, straight);
+}
+
+#[test]
+fn screen_unit_patterns_ignore_zoom() {
+    let pixels = Linetype { name: "PIXELS".into(), description: String::new(), pattern: vec![4.0, -4.0], units: PatternUnits::Screen };
+    let appearance = Appearance { linetype: "PIXELS".into(), lineweight: 0.0, ..Appearance::default() };
+    // Both lines start at the left edge of the view.
+    let (near, _) = render_polyline(&[Vec2::ZERO, Vec2::new(2.0, 0.0)], appearance.clone(), 1.0, 0.5, Some(pixels.clone()));
+    let (far, _) = render_polyline(&[Vec2::new(-3.0, 0.0), Vec2::new(5.0, 0.0)], appearance, 1.0, 2.0, Some(pixels));
+    assert_eq!(near, "####....".repeat(10));
+    assert_eq!(far, near);
+}
+
+#[test]
+fn lineweights_follow_plot_scale() {
+    let line = [Vec2::ZERO, Vec2::new(2.0, 0.0)];
+    let heavy = Appearance { lineweight: 1.0, ..Appearance::default() };
+    assert_eq!(render_polyline(&line, heavy.clone(), 3.0, 0.5, None).1, 3);
+    assert_eq!(render_polyline(&line, heavy, 1.0, 0.5, None).1, 1);
+    // Thin weights never vanish.
+    assert_eq!(render_polyline(&line, Appearance { lineweight: 0.0, ..Appearance::default() }, 1.0, 0.5, None).1, 1);
+
+    let mut ctx = SceneContext::new();
+    assert!(matches!(ctx.set_plot_scale(ViewportId::MAIN, 0.0), Err(SceneError::InvalidState(_))));
+    let names = ctx.load_linetypes(SAMPLE_LIN.lines().take(3).collect::<Vec<_>>().join("\n").as_str()).unwrap();
+    assert_eq!(names, ["BORDER"]);
+    assert!(ctx.linetype_names().contains(&"BORDER".to_owned()));
+}
+
+/// Submits visible quads covering the plan view's centre, in the given order.
+fn quads_at(ctx: &mut SceneContext, layers: &[(f32, Appearance)]) -> Vec<EntityId> {
+    layers
+        .iter()
+        .map(|(z, appearance)| {
+            let id = ctx.submit_shape(None, &DummyShape { mesh: quad(Vec2::splat(0.0), Vec2::splat(0.5), *z) }, &TessParams::default()).unwrap();
+            ctx.set_visibility(id, true).unwrap();
+            ctx.set_appearance(id, appearance.clone()).unwrap();
+            id
+        })
+        .collect()
+}
+
+#[test]
+fn translucent_entities_blend_back_to_front() {
+    let red = Appearance { color: [255, 0, 0], opacity: 0.5, ..Appearance::default() };
+    let blue = Appearance { color: [0, 0, 255], opacity: 0.5, ..Appearance::default() };
+    let render = |layers: &[(f32, Appearance)]| {
+        let mut ctx = SceneContext::new();
+        quads_at(&mut ctx, layers);
+        ctx.set_current_view(plan_view(16));
+        ctx.render_current().unwrap();
+        ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8)
+    };
+    // Blue is nearer the camera in both cases, whatever the submission order.
+    let near_first = render(&[(1.0, blue.clone()), (0.0, red.clone())]);
+    let far_first = render(&[(0.0, red), (1.0, blue)]);
+    assert_eq!(near_first, far_first);
+    assert!(near_first[2] > near_first[0], "nearest layer dominates: {near_first:?}");
+}
+
+#[test]
+fn opaque_geometry_shows_through_translucent_entities() {
+    let mut ctx = SceneContext::new();
+    let glass = Appearance { color: [0, 0, 255], opacity: 0.4, ..Appearance::default() };
+    let ids = quads_at(&mut ctx, &[(1.0, glass.clone()), (0.0, Appearance { color: [255, 0, 0], ..Appearance::default() })]);
+    ctx.set_current_view(plan_view(16));
+    ctx.render_current().unwrap();
+    let behind = ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8);
+    assert!(behind[0] > 0 && behind[2] > 0, "{behind:?}");
+
+    // The selection outline of the hidden entity stays visible through the glass.
+    let style = HighlightStyle { draw_on_top: false, ..HighlightStyle::default() };
+    ctx.set_highlight_style(style).unwrap();
+    ctx.set_selected(ids[1], true).unwrap();
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    assert_eq!(target.pixel(4, 8), style.selection_color);
+    // An opaque occluder still hides it.
+    ctx.set_appearance(ids[0], Appearance { opacity: 1.0, ..glass }).unwrap();
+    ctx.render_current().unwrap();
+    assert_ne!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(4, 8), style.selection_color);
+}
+
+/// Unit cube at the origin seen from above with a 2 m orthographic view.
+fn cube_from_above(ctx: &mut SceneContext) -> EntityId {
+    let id = ctx.submit_shape(None, &DummyShape { mesh: split_cube() }, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    let camera = Camera::look_at(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, UpAxis::Z).orthographic(2.0);
+    ctx.set_current_view(View::new(camera, UVec2::splat(16)));
+    id
+}
+
+#[test]
+fn clip_planes_cut_away_geometry_and_cap_solids() {
+    let mut ctx = SceneContext::new();
+    quads_at(&mut ctx, &[(0.0, Appearance::default()), (1.0, Appearance { color: [255, 0, 0], ..Appearance::default() })]);
+    ctx.set_current_view(plan_view(16));
+    ctx.add_clip_plane(ClipPlane::horizontal(0.5)).unwrap();
+    ctx.render_current().unwrap();
+    // The red quad above the cut is gone; the grey one below shows.
+    let below = ctx.render_target(ViewportId::MAIN).unwrap().pixel(6, 6);
+    assert!(below != BACKGROUND && below[1] > 0, "{below:?}");
+
+    let mut ctx = SceneContext::new();
+    cube_from_above(&mut ctx);
+    let section = Section { planes: vec![ClipPlane::horizontal(0.0)], ..Section::default() };
+    ctx.set_section(section.clone()).unwrap();
+    ctx.render_current().unwrap();
+    // Looking into the cut cube shows the cap, not its inner faces.
+    assert_eq!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8), section.cap_color);
+    assert_eq!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(1, 1), BACKGROUND);
+
+    ctx.set_section(Section { cap: CapStyle::None, ..section.clone() }).unwrap();
+    ctx.render_current().unwrap();
+    assert_ne!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(8, 8), section.cap_color);
+
+    ctx.set_section(Section { cap: CapStyle::Hatch { spacing_px: 4.0, angle_deg: 45.0 }, ..section.clone() }).unwrap();
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    let inside: Vec<_> = (5..11).flat_map(|y| (5..11).map(move |x| (x, y))).map(|(x, y)| target.pixel(x, y)).collect();
+    assert!(inside.contains(&section.hatch_color) && inside.contains(&section.cap_color), "{inside:?}");
+}
+
+#[test]
+fn section_box_keeps_only_its_interior() {
+    let mut ctx = SceneContext::new();
+    let ids = quads_at(&mut ctx, &[(0.0, Appearance::default()), (1.0, Appearance { color: [255, 0, 0], ..Appearance::default() })]);
+    ctx.set_current_view(plan_view(16));
+    let section_box = SectionBox { center: Vec3::new(0.25, 0.25, 1.0), half_extents: Vec3::new(0.1, 0.1, 0.2), rotation: Quat::from_rotation_z(0.3) };
+    ctx.set_section(Section { section_box: Some(section_box), ..Section::default() }).unwrap();
+    assert!(ctx.section().keeps(Vec3::new(0.25, 0.25, 1.0)));
+    assert!(!ctx.section().keeps(Vec3::new(0.25, 0.25, 0.0)));
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    // Only a small rotated patch of the red quad is left around the box centre.
+    let patch = target.pixel(8, 8);
+    assert!(patch[0] > 0 && patch[1] == 0, "{patch:?}");
+    assert_eq!(target.pixel(5, 5), BACKGROUND);
+    assert_eq!(target.pixel(11, 11), BACKGROUND);
+
+    let hit = ctx.pick(ViewportId::MAIN, Vec2::new(8.5, 8.5)).unwrap().unwrap();
+    assert_eq!(hit.entity_id, ids[1]);
+    assert!(ctx.pick(ViewportId::MAIN, Vec2::new(5.5, 5.5)).unwrap().is_none());
+}
+
+#[test]
+fn picking_ignores_hidden_and_cut_away_geometry() {
+    let mut ctx = SceneContext::new();
+    let ids = quads_at(&mut ctx, &[(0.0, Appearance::default()), (1.0, Appearance::default())]);
+    ctx.set_current_view(plan_view(16));
+    let cursor = Vec2::new(6.5, 6.5);
+    let hit = ctx.pick(ViewportId::MAIN, cursor).unwrap().unwrap();
+    assert_eq!(hit.entity_id, ids[1]);
+    assert!((hit.point.z - 1.0).abs() < 1e-4, "{hit:?}");
+
+    ctx.add_clip_plane(ClipPlane::horizontal(0.5)).unwrap();
+    assert_eq!(ctx.pick(ViewportId::MAIN, cursor).unwrap().unwrap().entity_id, ids[0]);
+    ctx.set_visibility(ids[0], false).unwrap();
+    assert!(ctx.pick(ViewportId::MAIN, cursor).unwrap().is_none());
+    ctx.clear_section();
+    assert_eq!(ctx.pick(ViewportId::MAIN, cursor).unwrap().unwrap().entity_id, ids[1]);
+    assert!(ctx.pick(ViewportId::MAIN, Vec2::new(14.5, 14.5)).unwrap().is_none());
+}
+
+#[test]
+fn section_validation_limits_planes() {
+    let mut ctx = SceneContext::new();
+    for _ in 0..MAX_CLIP_PLANES {
+        ctx.add_clip_plane(ClipPlane::horizontal(1.0)).unwrap();
+    }
+    assert!(matches!(ctx.add_clip_plane(ClipPlane::horizontal(1.0)), Err(SceneError::InvalidState(_))));
+    assert_eq!(ctx.section().planes.len(), MAX_CLIP_PLANES);
+    let skewed = ClipPlane { normal: Vec3::new(0.0, 0.0, 2.0), distance: 0.0 };
+    assert!(ctx.set_section(Section { planes: vec![skewed], ..Section::default() }).is_err());
+    let flat = SectionBox { center: Vec3::ZERO, half_extents: Vec3::new(1.0, 1.0, 0.0), rotation: Quat::IDENTITY };
+    assert!(ctx.set_section(Section { section_box: Some(flat), ..Section::default() }).is_err());
+}
+
+#[test]
+fn section_payloads_may_omit_colors() {
+    let section: Section = serde_json::from_value(serde_json::json!({ "planes": [{ "normal": [0.0, 0.0, 1.0], "distance": 0.5 }] })).unwrap();
+    assert_eq!(section, Section { planes: vec![ClipPlane { normal: Vec3::Z, distance: 0.5 }], ..Section::default() });
+}
+
+fn signed_area(polyline: &Polyline2) -> f32 {
+    let points = &polyline.points;
+    (0..points.len()).map(|i| points[i].perp_dot(points[(i + 1) % points.len()])).sum::<f32>() * 0.5
+}
+
+fn submit_visible(ctx: &mut SceneContext, mesh: MeshData, transform: Transform) -> EntityId {
+    let id = ctx.submit_shape(None, &DummyShape { mesh }, &TessParams::default()).unwrap();
+    ctx.set_transform(id, transform).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    id
+}
+
+#[test]
+fn plan_cut_gives_closed_counter_clockwise_loops() {
+    let mut ctx = SceneContext::new();
+    // A 2 x 1 x 3 m block standing on level 0, and a mirrored copy beside it.
+    let block = Transform::from_trs(Vec3::new(0.0, 0.0, 1.5), Quat::IDENTITY, Vec3::new(2.0, 1.0, 3.0));
+    let mirrored = Transform::from_trs(Vec3::new(5.0, 0.0, 1.5), Quat::from_rotation_z(0.4), Vec3::new(-2.0, 1.0, 3.0));
+    let ids = [submit_visible(&mut ctx, split_cube(), block), submit_visible(&mut ctx, split_cube(), mirrored)];
+
+    let plan = ctx.slice_plan(&PlanCut::new(0.0, 1.2)).unwrap();
+    assert_eq!(plan.cut_z, 1.2);
+    assert_eq!(plan.entities.iter().map(|e| e.entity_id).collect::<Vec<_>>(), ids);
+    for entity in &plan.entities {
+        assert!(entity.below.is_empty());
+        let [outline] = &entity.cut[..] else { panic!("one loop expected: {:?}", entity.cut) };
+        assert!(outline.closed);
+        assert!((signed_area(outline) - 2.0).abs() < 1e-4, "{outline:?}");
+    }
+    let corners = &plan.entities[0].cut[0].points;
+    assert!(corners.iter().all(|p| (p.x.abs() - 1.0).abs() < 1e-5 || (p.y.abs() - 0.5).abs() < 1e-5), "{corners:?}");
+
+    // Cutting exactly through the top vertices still closes the loop.
+    let plan = ctx.slice_plan(&PlanCut::new(0.0, 3.0)).unwrap();
+    assert!((signed_area(&plan.entities[0].cut[0]) - 2.0).abs() < 1e-4, "{plan:?}");
+    assert!(ctx.slice_plan(&PlanCut::new(0.0, 3.5)).unwrap().entities.iter().all(|e| e.cut.is_empty()));
+}
+
+#[test]
+fn plan_cut_leaves_open_surfaces_open() {
+    let mut ctx = SceneContext::new();
+    let upright = Transform::from_trs(Vec3::ZERO, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2), Vec3::ONE);
+    submit_visible(&mut ctx, shallow_roof(), upright);
+    let plan = ctx.slice_plan(&PlanCut::new(0.0, 0.5)).unwrap();
+    let [polyline] = &plan.entities[0].cut[..] else { panic!("{plan:?}") };
+    assert!(!polyline.closed);
+    assert_eq!(polyline.points.len(), 3);
+}
+
+#[test]
+fn plan_projects_elements_below_the_cut() {
+    let mut ctx = SceneContext::new();
+    let table = submit_visible(&mut ctx, split_cube(), Transform::from_trs(Vec3::new(0.0, 0.0, 0.4), Quat::IDENTITY, Vec3::new(1.0, 1.0, 0.8)));
+    let lower_floor = submit_visible(&mut ctx, quad(Vec2::ZERO, Vec2::ONE, -3.0), Transform::identity());
+    let hidden = submit_visible(&mut ctx, quad(Vec2::ZERO, Vec2::ONE, 0.0), Transform::identity());
+    ctx.set_visibility(hidden, false).unwrap();
+    let above = submit_visible(&mut ctx, quad(Vec2::ZERO, Vec2::ONE, 2.5), Transform::identity());
+
+    let plan = ctx.slice_plan(&PlanCut::new(0.0, 1.2)).unwrap();
+    assert_eq!(plan.entities.iter().map(|e| e.entity_id).collect::<Vec<_>>(), [table, lower_floor]);
+    // The table's top and bottom squares coincide in plan; its legs collapse.
+    assert_eq!(plan.entities[0].below.len(), 4);
+    assert!(plan.entities[0].cut.is_empty());
+    assert_eq!(plan.entities[1].below.len(), 4);
+    assert!(!plan.entities.iter().any(|e| e.entity_id == above));
+
+    let shallow = PlanCut { view_depth: Some(1.0), ..PlanCut::new(0.0, 1.2) };
+    assert_eq!(ctx.slice_plan(&shallow).unwrap().entities.len(), 1);
+    assert!(ctx.slice_plan(&PlanCut { view_depth: Some(-1.0), ..shallow }).is_err());
+}
+
+#[test]
+fn frustum_rejects_boxes_outside_the_view_volume() {
+    let camera = Camera::look_at(Vec3::new(0.0, -10.0, 0.0), Vec3::ZERO, UpAxis::Z);
+    for reversed_z in [true, false] {
+        let frustum = Frustum::from_view_proj(&Camera { reversed_z, ..camera }.params(UVec2::new(200, 100)).view_proj());
+        let unit = |center: Vec3| Aabb::new(center - 0.5, center + 0.5);
+        assert!(frustum.intersects(&unit(Vec3::ZERO)));
+        // Behind the eye, beyond the far plane, and well off to the side.
+        assert!(!frustum.intersects(&unit(Vec3::new(0.0, -20.0, 0.0))));
+        assert!(!frustum.intersects(&unit(Vec3::new(0.0, 20_000.0, 0.0))));
+        assert!(!frustum.intersects(&unit(Vec3::new(30.0, 0.0, 0.0))));
+        // Wider than tall: the same offset is visible sideways but not upwards.
+        assert!(frustum.intersects(&unit(Vec3::new(7.0, 0.0, 0.0))));
+        assert!(!frustum.intersects(&unit(Vec3::new(0.0, 0.0, 7.0))));
+        // A box straddling the eye is kept.
+        assert!(frustum.intersects(&Aabb::new(Vec3::splat(-20.0), Vec3::splat(20.0))));
+    }
+}
+
+#[test]
+fn off_screen_and_cut_away_entities_are_culled() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(plan_view(16));
+    let _on_screen = visible_triangle(&mut ctx);
+    let far_away = visible_triangle(&mut ctx);
+    ctx.set_transform(far_away, Transform::from_trs(Vec3::new(50.0, 0.0, 0.0), Quat::IDENTITY, Vec3::ONE)).unwrap();
+    let hidden = visible_triangle(&mut ctx);
+    ctx.set_visibility(hidden, false).unwrap();
+    ctx.render_current().unwrap();
+    let stats = ctx.last_frame().unwrap().stats;
+    assert_eq!((stats.entities_considered, stats.entities_culled, stats.entities_drawn, stats.triangles), (2, 1, 1, 1));
+
+    // Moving it back into view brings it back.
+    ctx.set_transform(far_away, Transform::from_trs(Vec3::new(0.0, 0.0, 0.5), Quat::IDENTITY, Vec3::ONE)).unwrap();
+    ctx.render_current().unwrap();
+    assert_eq!(ctx.last_frame().unwrap().stats.entities_culled, 0);
+
+    // Wireframe draws lines instead of triangles; a cut below both removes them.
+    ctx.set_display_mode(ViewportId::MAIN, DisplayMode::Wireframe).unwrap();
+    ctx.render_current().unwrap();
+    let stats = ctx.last_frame().unwrap().stats;
+    assert_eq!((stats.triangles, stats.lines), (0, 6));
+    ctx.add_clip_plane(ClipPlane::horizontal(-0.1)).unwrap();
+    ctx.render_current().unwrap();
+    let stats = ctx.last_frame().unwrap().stats;
+    assert_eq!((stats.entities_culled, stats.entities_drawn, stats.lines), (2, 0, 0));
+}
+
+/// Unit square split into more cells the smaller the allowed error.
+struct GridShape;
+
+impl KernelShape for GridShape {
+    fn tessellate(&self, params: &TessParams) -> MeshData {
+        let cells = (0.1 / params.max_error).ceil().clamp(1.0, 16.0) as u32;
+        let mut mesh = MeshData::default();
+        for j in 0..=cells {
+            for i in 0..=cells {
+                let position = Vec3::new(i as f32, j as f32, 0.0) / cells as f32;
+                mesh.vertices.push(Vertex { position, normal: Vec3::Z, uv: None });
+            }
+        }
+        for j in 0..cells {
+            for i in 0..cells {
+                let corner = j * (cells + 1) + i;
+                mesh.indices.extend([corner, corner + 1, corner + cells + 2, corner, corner + cells + 2, corner + cells + 1]);
+            }
+        }
+        mesh
+    }
+}
+
+#[test]
+fn lod_policy_switches_with_hysteresis() {
+    let policy = LodPolicy { pixel_threshold: 1.0, hysteresis: 0.25 };
+    assert_eq!(policy.select(0, &[]), 0);
+    assert_eq!(policy.select(0, &[0.5, 2.0]), 1);
+    assert_eq!(policy.select(0, &[0.1, 0.7]), 2);
+    // Coarsening needs a margin below the threshold...
+    assert_eq!(policy.select(0, &[0.9, 4.0]), 0);
+    // ...and a level is only left once it drifts past the threshold plus the margin.
+    assert_eq!(policy.select(1, &[1.2, 4.0]), 1);
+    assert_eq!(policy.select(1, &[1.3, 4.0]), 0);
+    assert_eq!(policy.select(2, &[0.5, 1.3]), 1);
+    // Stale selections beyond the available levels are clamped.
+    assert_eq!(policy.select(5, &[0.1]), 1);
+    assert!(LodPolicy { hysteresis: 1.0, ..policy }.validate().is_err());
+}
+
+#[test]
+fn projected_error_shrinks_with_distance_and_grows_with_scale() {
+    let camera = Camera::look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, UpAxis::Z).perspective(std::f32::consts::FRAC_PI_2);
+    let params = camera.params(UVec2::splat(100));
+    let bounds = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+    // 50 px per unit at unit depth; the box top is 9.5 units away.
+    let near = lod::projected_error(&params, &Mat4::IDENTITY, &bounds, 1.0);
+    assert!((near - 50.0 / 9.5).abs() < 1e-3, "{near}");
+    assert!((lod::projected_error(&params, &Mat4::from_scale(Vec3::splat(2.0)), &bounds, 1.0) - 2.0 * near).abs() < 1e-3);
+    let inside = Aabb::new(Vec3::splat(-20.0), Vec3::splat(20.0));
+    assert_eq!(lod::projected_error(&params, &Mat4::IDENTITY, &inside, 1e-6), f32::INFINITY);
+}
+
+#[test]
+fn viewports_draw_the_coarsest_level_under_a_pixel() {
+    let mut ctx = SceneContext::new();
+    let id = ctx.submit_shape(None, &GridShape, &TessParams { max_error: 0.01, ..TessParams::default() }).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    let coarse = [0.2, 0.05].map(|max_error| TessParams { max_error, ..TessParams::default() });
+    ctx.add_lods(id, &GridShape, &coarse).unwrap();
+    assert_eq!(ctx.lod_errors(id).unwrap(), [0.05, 0.2]);
+    // A bad later level leaves the earlier ones out too.
+    for bad in [0.0, f32::NAN] {
+        let levels = [0.4, bad].map(|max_error| TessParams { max_error, ..TessParams::default() });
+        assert!(ctx.add_lods(id, &GridShape, &levels).is_err());
+        assert_eq!(ctx.lod_errors(id).unwrap(), [0.05, 0.2]);
+    }
+
+    // Orthographic height h over 100 px: level errors of 5/h and 20/h pixels.
+    let render = |ctx: &mut SceneContext, height: f32| {
+        let camera = Camera::look_at(Vec3::new(0.5, 0.5, 5.0), Vec3::new(0.5, 0.5, 0.0), UpAxis::Z).orthographic(height);
+        ctx.set_current_view(View::new(camera, UVec2::splat(100)));
+        ctx.render_current().unwrap();
+        (ctx.drawn_lod(ViewportId::MAIN, id).unwrap().unwrap(), ctx.last_frame().unwrap().stats.triangles)
+    };
+    assert_eq!(render(&mut ctx, 2.0), (0, 200));
+    assert_eq!(render(&mut ctx, 10.0), (1, 8));
+    assert_eq!(render(&mut ctx, 100.0), (2, 2));
+    assert_eq!(render(&mut ctx, 10.0), (1, 8));
+    // Within the hysteresis band the level holds, though a fresh view would not pick it.
+    assert_eq!(render(&mut ctx, 6.0), (1, 8));
+    assert_eq!(render(&mut ctx, 3.5), (0, 200));
+    assert_eq!(render(&mut ctx, 6.0), (0, 200));
+
+    // Removing the levels falls back to the submitted mesh.
+    ctx.clear_lods(id).unwrap();
+    assert_eq!(render(&mut ctx, 100.0), (0, 200));
+    assert!(ctx.add_lod_mesh(id, quad(Vec2::ZERO, Vec2::ONE, 0.0), 0.0).is_err());
+}
+
+#[test]
+fn level_changes_keep_the_highlight_outline() {
+    let mut ctx = SceneContext::new();
+    let id = ctx.submit_shape(None, &DummyShape { mesh: height_field(4, |_, _| 0.0, false) }, &TessParams::default()).unwrap();
+    ctx.set_visibility(id, true).unwrap();
+    ctx.set_highlight(id, true).unwrap();
+    ctx.set_current_view(plan_view(16));
+    let outlined = |ctx: &mut SceneContext| {
+        ctx.render_current().unwrap();
+        ctx.render_target(ViewportId::MAIN).unwrap().pixels().contains(&HighlightStyle::default().highlight_color)
+    };
+    assert!(outlined(&mut ctx));
+    ctx.add_lod_mesh(id, quad(Vec2::ZERO, Vec2::ONE, 0.0), 0.1).unwrap();
+    assert!(outlined(&mut ctx));
+    ctx.add_decimated_lod(id, DecimateTarget::Triangles(2)).unwrap();
+    assert!(outlined(&mut ctx));
+    ctx.clear_lods(id).unwrap();
+    assert!(outlined(&mut ctx));
+}
+
+/// `cells` x `cells` grid over the unit square with height `z(x, y)`; with
+/// `seam`, vertices at x = 0.5 are duplicated with different UVs per side.
+fn height_field(cells: u32, z: impl Fn(f32, f32) -> f32, seam: bool) -> MeshData {
+    let mut mesh = MeshData::default();
+    let mut index = HashMap::new();
+    let mut vertex = |i: u32, j: u32, side: bool, mesh: &mut MeshData| {
+        let (x, y) = (i as f32 / cells as f32, j as f32 / cells as f32);
+        let side = seam && x == 0.5 && side;
+        *index.entry((i, j, side)).or_insert_with(|| {
+            let uv = Vec2::new(if side { x + 1.0 } else { x }, y);
+            mesh.vertices.push(Vertex { position: Vec3::new(x, y, z(x, y)), normal: Vec3::Z, uv: Some(uv) });
+            mesh.vertices.len() as u32 - 1
+        })
+    };
+    for j in 0..cells {
+        for i in 0..cells {
+            // Cells right of the seam use the second copy of seam vertices.
+            let right = (i as f32 + 0.5) / cells as f32 > 0.5;
+            let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)].map(|(i, j)| vertex(i, j, right, &mut mesh));
+            mesh.indices.extend([corners[0], corners[1], corners[2], corners[0], corners[2], corners[3]]);
+        }
+    }
+    mesh
+}
+
+fn bump(x: f32, y: f32) -> f32 {
+    0.2 * (std::f32::consts::PI * x).sin() * (std::f32::consts::PI * y).sin()
+}
+
+/// Largest distance from a vertex of `original` to the nearest triangle of `simplified`.
+fn surface_deviation(original: &MeshData, simplified: &MeshData) -> f32 {
+    let triangles: Vec<[Vec3; 3]> = simplified.indices.chunks_exact(3).map(|t| [0, 1, 2].map(|k| simplified.vertices[t[k] as usize].position)).collect();
+    let distance = |p: Vec3, [a, b, c]: [Vec3; 3]| {
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        let projected = p - normal * normal.dot(p - a);
+        if [(a, b), (b, c), (c, a)].iter().all(|&(s, e)| (e - s).cross(projected - s).dot(normal) >= -1e-6) {
+            return (p - projected).length();
+        }
+        let segment = |s: Vec3, e: Vec3| (p - (s + (e - s) * ((p - s).dot(e - s) / (e - s).length_squared()).clamp(0.0, 1.0))).length();
+        segment(a, b).min(segment(b, c)).min(segment(c, a))
+    };
+    original.vertices.iter().map(|v| triangles.iter().map(|&tri| distance(v.position, tri)).fold(f32::INFINITY, f32::min)).fold(0.0, f32::max)
+}
+
+#[test]
+fn decimation_removes_flat_detail_without_error() {
+    let flat = height_field(16, |_, _| 0.0, false);
+    let (mesh, report) = decimate(&flat, DecimateTarget::MaxError(0.0)).unwrap();
+    assert_eq!((report.triangles_before, report.triangles, report.error), (512, 2, 0.0));
+    // Only the corners of the square survive, straight boundaries folded away.
+    let mut corners: Vec<[f32; 2]> = mesh.vertices.iter().map(|v| v.position.truncate().to_array()).collect();
+    corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(corners, [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]]);
+    assert!(decimate(&flat, DecimateTarget::MaxError(f32::NAN)).is_err());
+}
+
+#[test]
+fn decimation_meets_error_and_triangle_targets() {
+    let bumpy = height_field(24, bump, false);
+    let mut previous = usize::MAX;
+    for max_error in [0.001, 0.005, 0.02] {
+        let (mesh, report) = decimate(&bumpy, DecimateTarget::MaxError(max_error)).unwrap();
+        assert!(report.error <= max_error, "{report:?}");
+        assert!(surface_deviation(&bumpy, &mesh) <= report.error, "{report:?}");
+        assert!(report.triangles < previous && report.triangles == mesh.indices.len() / 3);
+        previous = report.triangles;
+    }
+    let (mesh, report) = decimate(&bumpy, DecimateTarget::Triangles(100)).unwrap();
+    assert!((99..=100).contains(&report.triangles), "{report:?}");
+    assert!(report.error > 0.02 && surface_deviation(&bumpy, &mesh) <= report.error, "{report:?}");
+    // The boundary keeps its square outline.
+    let bounds = mesh.bounds().unwrap();
+    assert_eq!((bounds.min.truncate(), bounds.max.truncate()), (Vec2::ZERO, Vec2::ONE));
+}
+
+#[test]
+fn decimation_keeps_uv_seams_and_polylines() {
+    let mut seamed = height_field(16, bump, true);
+    seamed.polylines.push(vec![0, 1, 2]);
+    let (mesh, report) = decimate(&seamed, DecimateTarget::Triangles(40)).unwrap();
+    // Boundary collapses remove one triangle, interior ones two.
+    assert!((39..=40).contains(&report.triangles), "{report:?}");
+    for tri in mesh.indices.chunks_exact(3) {
+        let corners = [0, 1, 2].map(|k| &mesh.vertices[tri[k] as usize]);
+        // No triangle crosses the seam, and each side keeps its own UVs along it.
+        let left = corners.iter().all(|v| v.position.x <= 0.5);
+        let right = corners.iter().all(|v| v.position.x >= 0.5);
+        assert!(left || right);
+        for v in corners.iter().filter(|v| v.position.x == 0.5) {
+            assert_eq!(v.uv.unwrap().x, if left { 0.5 } else { 1.5 });
+        }
+    }
+    let seam = |mesh: &MeshData, u: f32| mesh.vertices.iter().filter(|v| v.position.x == 0.5 && v.uv.unwrap().x == u).count();
+    assert_eq!(seam(&mesh, 0.5), seam(&mesh, 1.5));
+    let polyline: Vec<Vec3> = mesh.polylines[0].iter().map(|&i| mesh.vertices[i as usize].position).collect();
+    assert_eq!(polyline, seamed.polylines[0].iter().map(|&i| seamed.vertices[i as usize].position).collect::<Vec<_>>());
+}
+
+#[test]
+fn decimated_lods_use_the_achieved_error() {
+    let mut ctx = SceneContext::new();
+    let id = ctx.submit_shape(None, &DummyShape { mesh: height_field(24, bump, false) }, &TessParams::default()).unwrap();
+    let report = ctx.add_decimated_lod(id, DecimateTarget::MaxError(TessParams::default().max_error * 5.0)).unwrap();
+    assert_eq!(ctx.lod_errors(id).unwrap(), [report.error]);
+    assert!(report.triangles < report.triangles_before);
+    assert!(matches!(ctx.add_decimated_lod(EntityId(99), DecimateTarget::Triangles(10)), Err(SceneError::UnknownEntity(99))));
+}
+
+#[test]
+fn flat_decimated_levels_follow_the_positive_error_rule() {
+    let mut ctx = SceneContext::new();
+    let id = ctx.submit_shape(None, &DummyShape { mesh: height_field(4, |_, _| 0.0, false) }, &TessParams::default()).unwrap();
+    let report = ctx.add_decimated_lod(id, DecimateTarget::Triangles(2)).unwrap();
+    assert_eq!((report.triangles, report.error), (2, 0.0));
+    assert_eq!(ctx.lod_errors(id).unwrap(), [lod::MIN_LOD_ERROR]);
+
+    // A level added by hand is held to the same rule.
+    let flat = quad(Vec2::ZERO, Vec2::ONE, 0.0);
+    assert!(matches!(ctx.add_lod_mesh(id, flat.clone(), 0.0), Err(SceneError::InvalidState(_))));
+    ctx.add_lod_mesh(id, flat, lod::MIN_LOD_ERROR).unwrap();
+    assert_eq!(ctx.lod_errors(id).unwrap(), [lod::MIN_LOD_ERROR; 2]);
+}
+
+/// A national-grid site where f32 spacing is half a metre.
+const SITE: DVec3 = DVec3::new(500_000.123, 6_000_000.456, 12.0);
+
+fn rendered(ctx: &mut SceneContext) -> Vec<String> {
+    ctx.render_current().unwrap();
+    ascii(ctx.render_target(ViewportId::MAIN).unwrap())
+}
+
+#[test]
+fn entities_far_from_zero_render_like_entities_at_it() {
+    let mut reference = SceneContext::new();
+    submit_visible(&mut reference, simple_triangle(), Transform::from_trs(Vec3::new(0.25, 0.0, 0.0), Quat::IDENTITY, Vec3::ONE));
+    reference.set_current_view(plan_view(16));
+
+    let mut ctx = SceneContext::new();
+    ctx.set_rebase_origin(SITE).unwrap();
+    let id = submit_visible(&mut ctx, simple_triangle(), Transform::identity());
+    ctx.set_origin(id, SITE + DVec3::new(0.25, 0.0, 0.0)).unwrap();
+    ctx.set_current_view(plan_view(16));
+    assert_eq!(rendered(&mut ctx), rendered(&mut reference));
+    assert_eq!(ctx.get_state(id).unwrap().origin, SITE + DVec3::new(0.25, 0.0, 0.0));
+    assert!(matches!(ctx.set_origin(EntityId(99), SITE), Err(SceneError::UnknownEntity(99))));
+    assert!(ctx.set_origin(id, DVec3::NAN).is_err());
+}
+
+#[test]
+fn rebasing_keeps_the_picture_and_world_positions() {
+    let mut ctx = SceneContext::new();
+    let id = submit_visible(&mut ctx, simple_triangle(), Transform::identity());
+    ctx.set_origin(id, SITE).unwrap();
+    ctx.set_rebase_origin(SITE).unwrap();
+    ctx.set_current_view(plan_view(16));
+    ctx.save_view("site").unwrap();
+    ctx.add_clip_plane(ClipPlane { normal: Vec3::X, distance: 0.3 }).unwrap();
+    let before = rendered(&mut ctx);
+    let hit = ctx.pick(ViewportId::MAIN, Vec2::new(6.5, 10.5)).unwrap().unwrap();
+
+    // Views and section planes follow the move, so nothing visibly changes.
+    ctx.set_rebase_origin(SITE + DVec3::new(-2.0, 1.5, 0.0)).unwrap();
+    assert_eq!(rendered(&mut ctx), before);
+    assert!((ctx.section().planes[0].distance - 2.3).abs() < 1e-6);
+    let moved = ctx.pick(ViewportId::MAIN, Vec2::new(6.5, 10.5)).unwrap().unwrap();
+    assert_eq!(moved.entity_id, id);
+    assert!(ctx.to_world(moved.point).distance(SITE + hit.point.as_dvec3()) < 1e-6);
+    ctx.restore_view("site").unwrap();
+    assert_eq!(rendered(&mut ctx), before);
+}
+
+#[test]
+fn scene_coordinates_keep_millimetres_at_any_site() {
+    let mut ctx = SceneContext::new();
+    let point = SITE + DVec3::new(3.001, -4.002, 0.5);
+    // Rounded straight to f32 the millimetres are gone.
+    assert!((point.as_vec3().as_dvec3() - point).length() > 0.01);
+    ctx.set_rebase_origin(SITE).unwrap();
+    let scene = ctx.to_scene(point);
+    assert!((scene.as_dvec3() - DVec3::new(3.001, -4.002, 0.5)).length() < 1e-6);
+    assert!((ctx.to_world(scene) - point).length() < 1e-6);
+    assert_eq!(ctx.rebase_origin(), SITE);
+}
+
+const OBJ_BOX_AND_PANEL: &str = "\
+# unit box without normals, then a textured panel
+o box
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+f 1 4 3 2
+f 5 6 7 8
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+g panel
+v 0 0 2
+v 2 0 2
+v 2 1 2
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 2
+f -3/-3/1 -2/-2/1 -1/-1/1
+l 9 10 11
+";
+
+#[test]
+fn imports_with_a_bad_group_add_no_entities() {
+    let mut ctx = SceneContext::new();
+    let mut meshes = parse_obj(OBJ_BOX_AND_PANEL).unwrap();
+    meshes[1].mesh.indices.push(99);
+    assert!(matches!(ctx.submit_imported(meshes), Err(SceneError::InvalidState(_))));
+    // The box group ahead of the bad one did not become an entity either.
+    for (_, id) in SceneContext::new().import_obj(OBJ_BOX_AND_PANEL).unwrap() {
+        assert!(matches!(ctx.get_state(id), Err(SceneError::UnknownEntity(_))));
+    }
+}
+
+#[test]
+fn obj_groups_become_meshes_with_generated_normals() {
+    let meshes = parse_obj(OBJ_BOX_AND_PANEL).unwrap();
+    assert_eq!(meshes.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["box", "panel"]);
+
+    // Box edges are creases, so each face gets its own flat, outward normals.
+    let cube = &meshes[0].mesh;
+    assert_eq!(cube.indices.len(), 36);
+    assert_eq!(cube.vertices.len(), 24);
+    let centre = Vec3::splat(0.5);
+    for tri in cube.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| &cube.vertices[tri[k] as usize]);
+        let face = (b.position - a.position).cross(c.position - a.position).normalize();
+        assert!(a.normal.abs_diff_eq(face, 1e-6) && b.normal == a.normal && c.normal == a.normal);
+        assert!(face.dot(a.position - centre) > 0.0);
+    }
+
+    let panel = &meshes[1].mesh;
+    assert_eq!(panel.indices, [0, 1, 2]);
+    assert_eq!(panel.vertices[2].uv, Some(Vec2::ONE));
+    assert_eq!(panel.vertices[0].normal, Vec3::Z);
+    assert_eq!(panel.polylines, [vec![3, 4, 5]]);
+    assert_eq!(panel.vertices[5].position, Vec3::new(2.0, 1.0, 2.0));
+}
+
+#[test]
+fn obj_texture_coordinates_take_one_to_three_numbers() {
+    let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.25\nvt 0.5 0.75\nvt 1 1 0\nf 1/1 2/2 3/3\n";
+    let uvs: Vec<_> = parse_obj(source).unwrap()[0].mesh.vertices.iter().map(|v| v.uv).collect();
+    assert_eq!(uvs, [Some(Vec2::new(0.25, 0.0)), Some(Vec2::new(0.5, 0.75)), Some(Vec2::ONE)]);
+    assert!(parse_obj("vt\n").is_err());
+}
+
+#[test]
+fn obj_smooths_normals_across_shallow_edges() {
+    // Two faces folded by 10 degrees share smoothed normals along the fold.
+    let fold = 10f32.to_radians();
+    let source = format!("v 0 0 0\nv 0 1 0\nv -1 0 0\nv {} 0 {}\nf 1 2 3\nf 1 4 2\n", fold.cos(), fold.sin());
+    let mesh = &parse_obj(&source).unwrap()[0].mesh;
+    assert_eq!(mesh.vertices.len(), 4);
+    let shared = mesh.vertices.iter().find(|v| v.position == Vec3::Y).unwrap();
+    assert!((shared.normal.angle_between(Vec3::Z) - fold / 2.0).abs() < 1e-4);
+}
+
+#[test]
+fn obj_errors_report_the_line() {
+    let line = |source: &str| match parse_obj(source) {
+        Err(SceneError::Parse { line, .. }) => line,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(line("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n"), 4);
+    assert_eq!(line("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 0\n"), 4);
+    assert_eq!(line("v 0 0\n"), 1);
+    assert_eq!(line("v 0 0 0\nv 1 0 0\nf 1 2\n"), 3);
+    assert_eq!(line("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/1 2/1 3/1\n"), 4);
+    assert!(parse_obj("# nothing but comments\nmtllib a.mtl\n").unwrap().is_empty());
+}
+
+fn binary_stl(facets: &[[Vec3; 4]]) -> Vec<u8> {
+    let mut bytes = b"solid exported part".to_vec();
+    bytes.resize(80, 0);
+    bytes.extend((facets.len() as u32).to_le_bytes());
+    for facet in facets {
+        for value in facet.iter().flat_map(|v| v.to_array()) {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0, 0]);
+    }
+    bytes
+}
+
+#[test]
+fn stl_binary_and_ascii_solids() {
+    let (a, b, c, d) = (Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::new(1.0, 1.0, 0.0));
+    let bytes = binary_stl(&[[Vec3::Z, a, b, d], [Vec3::ZERO, a, d, c]]);
+    let meshes = parse_stl(&bytes).unwrap();
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].name, "exported part");
+    // The zero normal comes from the winding, so the square welds to 4 vertices.
+    assert_eq!(meshes[0].mesh.vertices.len(), 4);
+    assert!(meshes[0].mesh.vertices.iter().all(|v| v.normal == Vec3::Z));
+
+    let ascii = "solid first\n facet normal 0 0 0\n  outer loop\n   vertex 0 0 0\n   vertex 0 1 0\n   vertex 1 0 0\n  endloop\n endfacet\nendsolid first\n\
+                 solid second\n facet normal 0 0 1\n  outer loop\n   vertex 0 0 5\n   vertex 1 0 5\n   vertex 0 1 5\n  endloop\n endfacet\nendsolid second\n";
+    let meshes = parse_stl(ascii.as_bytes()).unwrap();
+    assert_eq!(meshes.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["first", "second"]);
+    assert_eq!(meshes[0].mesh.vertices[0].normal, -Vec3::Z);
+    assert_eq!(meshes[1].mesh.vertices[0].position.z, 5.0);
+}
+
+#[test]
+fn stl_errors_report_line_or_byte_offset() {
+    let mut bytes = binary_stl(&[[Vec3::Z, Vec3::ZERO, Vec3::X, Vec3::Y]; 2]);
+    // The second facet's first vertex y coordinate.
+    bytes[84 + 50 + 16..84 + 50 + 20].copy_from_slice(&f32::NAN.to_le_bytes());
+    assert!(matches!(parse_stl(&bytes), Err(SceneError::ParseBinary { offset: 150, .. })));
+    bytes.truncate(150);
+    assert!(matches!(parse_stl(&bytes), Err(SceneError::ParseBinary { offset: 150, .. })));
+    assert!(matches!(parse_stl(&[0; 40]), Err(SceneError::ParseBinary { offset: 40, .. })));
+
+    let ascii = "solid s\n facet normal 0 0 1\n  outer loop\n   vertex 0 0\n";
+    assert!(matches!(parse_stl(ascii.as_bytes()), Err(SceneError::Parse { line: 4, .. })));
+    let unclosed = "solid s\n facet normal 0 0 1\n  outer loop\n   vertex 0 0 0\n   vertex 1 0 0\n   vertex 0 1 0\n  endloop\n endfacet\n";
+    assert!(matches!(parse_stl(unclosed.as_bytes()), Err(SceneError::Parse { line: 8, .. })));
+}
+
+#[test]
+fn imports_submit_one_entity_per_group() {
+    let mut ctx = SceneContext::new();
+    let imported = ctx.import_obj(OBJ_BOX_AND_PANEL).unwrap();
+    assert_eq!(imported.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["box", "panel"]);
+    for (_, id) in &imported {
+        let state = ctx.get_state(*id).unwrap();
+        assert!(state.has_mesh && state.visual.is_empty());
+    }
+    assert!(ctx.import_obj("v 0 0 0\nf 1 1 1\nf 1 2 3\n").is_err());
+    assert_eq!(ctx.scene_bounds(), None);
+    assert_eq!(ctx.import_stl(&binary_stl(&[[Vec3::Z, Vec3::ZERO, Vec3::X, Vec3::Y]])).unwrap().len(), 1);
+}
+
+#[test]
+fn gltf_export_shares_meshes_and_materials() {
+    let mut ctx = SceneContext::new();
+    let at = |x: f32| Transform::from_trs(Vec3::new(x, 0.0, 0.0), Quat::IDENTITY, Vec3::ONE);
+    let first = submit_visible(&mut ctx, split_cube(), at(0.0));
+    let second = submit_visible(&mut ctx, split_cube(), at(3.0));
+    let glass = submit_visible(&mut ctx, simple_triangle(), at(6.0));
+    ctx.set_appearance(glass, Appearance { color: [255, 0, 0], opacity: 0.5, ..Appearance::default() }).unwrap();
+    let hidden = submit_visible(&mut ctx, quad(Vec2::ZERO, Vec2::ONE, 0.0), at(9.0));
+    ctx.set_visibility(hidden, false).unwrap();
+
+    let document = ctx.export_gltf(&ExportOptions::default());
+    let json = &document.json;
+    assert_eq!(json["asset"]["version"], "2.0");
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes[..3].iter().map(|node| node["extras"]["entity_id"].as_u64().unwrap()).collect::<Vec<_>>(), [first.0, second.0, glass.0]);
+    // The two cubes share one mesh; the triangle has its own, with a blended material.
+    assert_eq!(nodes[0]["mesh"], 0);
+    assert_eq!(nodes[1]["mesh"], 0);
+    assert_eq!(nodes[2]["mesh"], 1);
+    assert_eq!(json["meshes"].as_array().unwrap().len(), 2);
+    assert_eq!(nodes[1]["matrix"], serde_json::json!(at(3.0).matrix.to_cols_array()));
+    let materials = json["materials"].as_array().unwrap();
+    assert_eq!(materials.len(), 2);
+    assert_eq!(materials[1]["alphaMode"], "BLEND");
+    assert_eq!(materials[1]["pbrMetallicRoughness"]["baseColorFactor"], serde_json::json!([1.0, 0.0, 0.0, 0.5]));
+    let position = &json["accessors"][json["meshes"][1]["primitives"][0]["attributes"]["POSITION"].as_u64().unwrap() as usize];
+    assert_eq!(position["count"], 3);
+    assert_eq!(position["max"], serde_json::json!([1.0, 1.0, 0.0]));
+    assert_eq!(json["buffers"][0]["byteLength"].as_u64().unwrap() as usize, document.bin.len());
+
+    ctx.set_selected(second, true).unwrap();
+    let selected = ctx.export_gltf(&ExportOptions { selected_only: true });
+    assert_eq!(selected.json["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(selected.json["nodes"][0]["extras"]["entity_id"], second.0);
+}
+
+#[test]
+fn gltf_containers_round_trip() {
+    let mut ctx = SceneContext::new();
+    let id = submit_visible(&mut ctx, simple_triangle(), Transform::identity());
+    ctx.set_origin(id, SITE).unwrap();
+    ctx.set_rebase_origin(SITE).unwrap();
+    let document = ctx.export_gltf(&ExportOptions::default());
+    // Entities sit in scene space under a root carrying the rebase origin in
+    // f64, itself under a root turning the Z-up scene to glTF's +Y up.
+    let nodes = document.json["nodes"].as_array().unwrap();
+    assert!(nodes[0].get("matrix").is_none());
+    assert_eq!(document.json["scenes"][0]["nodes"], serde_json::json!([2]));
+    assert_eq!(nodes[1]["children"], serde_json::json!([0]));
+    assert_eq!(nodes[1]["translation"], serde_json::json!(SITE.to_array()));
+    assert_eq!(nodes[2]["children"], serde_json::json!([1]));
+    let rotation = Quat::from_array(serde_json::from_value(nodes[2]["rotation"].clone()).unwrap());
+    assert!((rotation * Vec3::Z).abs_diff_eq(Vec3::Y, 1e-6));
+
+    let mut view = ctx.current_view();
+    view.camera.up = UpAxis::Y;
+    ctx.set_current_view(view);
+    assert_eq!(ctx.export_gltf(&ExportOptions::default()).json["scenes"][0]["nodes"], serde_json::json!([1]));
+
+    let glb = document.to_glb();
+    let word = |at: usize| u32::from_le_bytes(glb[at..at + 4].try_into().unwrap()) as usize;
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!(word(8), glb.len());
+    let json_len = word(12);
+    assert_eq!(json_len % 4, 0);
+    let parsed: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+    assert_eq!(parsed, document.json);
+    let bin_len = word(20 + json_len);
+    assert_eq!(&glb[28 + json_len..28 + json_len + document.bin.len()], document.bin.as_slice());
+    assert_eq!(bin_len, document.bin.len().next_multiple_of(4));
+
+    let gltf: serde_json::Value = serde_json::from_str(&document.to_gltf("scene.bin")).unwrap();
+    assert_eq!(gltf["buffers"][0]["uri"], "scene.bin");
+    let empty = SceneContext::new().export_gltf(&ExportOptions::default());
+    assert!(empty.json.get("buffers").is_none() && empty.bin.is_empty());
+}
+
+fn textured_strip() -> MeshData {
+    let mut mesh = height_field(3, bump, false);
+    mesh.polylines.push(vec![0, 1, 2, 3]);
+    mesh
+}
+
+#[test]
+fn packed_meshes_round_trip() {
+    let mesh = textured_strip();
+    let bytes = encode_mesh(&mesh, &PackOptions::default());
+    let decoded = decode_mesh(&bytes).unwrap();
+    assert_eq!(format!("{decoded:?}"), format!("{mesh:?}"));
+    // Well under half the size of the same mesh as JSON.
+    assert!(bytes.len() * 2 < serde_json::to_vec(&mesh).unwrap().len());
+
+    let mut normals = mesh.clone();
+    let tilted = [Vec3::Z, -Vec3::Z, Vec3::X, Vec3::new(0.3, -0.5, -0.8).normalize(), Vec3::new(-1.0, -1.0, -1.0).normalize()];
+    for (vertex, normal) in normals.vertices.iter_mut().zip(tilted.iter().cycle()) {
+        vertex.normal = *normal;
+    }
+    let lossy = PackOptions { quantize_positions: true, octahedral_normals: true };
+    let packed = encode_mesh(&normals, &lossy);
+    let decoded = decode_mesh(&packed).unwrap();
+    assert!(packed.len() < bytes.len());
+    let extent = normals.bounds().unwrap().size();
+    for (a, b) in normals.vertices.iter().zip(&decoded.vertices) {
+        assert!(((a.position - b.position).abs() - extent / 65535.0).max_element() <= 1e-6);
+        assert!(a.normal.angle_between(b.normal) < 1e-3);
+        assert_eq!(a.uv, b.uv);
+    }
+    assert_eq!(decoded.indices, normals.indices);
+    assert_eq!(decoded.polylines, normals.polylines);
+}
+
+#[test]
+fn packed_errors_match_the_json_path() {
+    let bytes = encode_mesh(&textured_strip(), &PackOptions::default());
+    let offset = |bytes: &[u8]| match decode_mesh(bytes) {
+        Err(SceneError::ParseBinary { offset, .. }) => offset,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(offset(&bytes[..10]), 0);
+    assert_eq!(offset(b"glTF\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"), 0);
+    // Truncated positions are reported where the section starts.
+    assert_eq!(offset(&bytes[..40]), 20);
+    let mut trailing = bytes.clone();
+    trailing.extend([0; 4]);
+    assert_eq!(offset(&trailing), bytes.len());
+
+    // Out-of-range indices fail the same validation as submitted JSON meshes.
+    let mut broken = simple_triangle();
+    broken.indices[2] = 7;
+    let packed = decode_mesh(&encode_mesh(&broken, &PackOptions::default())).unwrap_err();
+    let mut ctx = SceneContext::new();
+    let submitted = ctx.submit_shape(None, &DummyShape { mesh: broken }, &TessParams::default()).unwrap_err();
+    assert_eq!(packed.to_string(), submitted.to_string());
+    assert!(matches!(submitted, SceneError::InvalidState(_)));
+}
+
+#[test]
+fn vertex_updates_upload_only_the_changed_range() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(plan_view(16));
+    let preview = visible_triangle(&mut ctx);
+    let other = visible_triangle(&mut ctx);
+    ctx.set_visibility(other, false).unwrap();
+    ctx.render_current().unwrap();
+    assert_ne!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(13, 11), BACKGROUND);
+
+    // Rubber-band style: drag one corner on each mouse move.
+    let corner = |x: f32| Vertex { position: Vec3::new(x, 0.0, 0.0), normal: Vec3::Z, uv: None };
+    ctx.update_vertices(preview, 1..2, &[corner(0.5)]).unwrap();
+    ctx.update_vertices(preview, 1..2, &[corner(0.25)]).unwrap();
+    let report = ctx.sync_gpu().unwrap();
+    assert_eq!(report, SyncReport { geometry: 1, uploaded_bytes: VERTEX_BYTES, ..SyncReport::default() });
+    ctx.render_current().unwrap();
+    assert_eq!(ctx.render_target(ViewportId::MAIN).unwrap().pixel(13, 11), BACKGROUND);
+    assert_eq!(ctx.feature_edges(preview).unwrap().boundary.len(), 3);
+
+    // Updates spanning two ranges upload their hull.
+    ctx.update_vertices(preview, 0..1, &[corner(0.0)]).unwrap();
+    ctx.update_indices(preview, 0..3, &[0, 2, 1]).unwrap();
+    ctx.update_vertices(preview, 2..3, &[Vertex { position: Vec3::Y, normal: Vec3::Z, uv: None }]).unwrap();
+    assert_eq!(ctx.sync_gpu().unwrap().uploaded_bytes, 3 * VERTEX_BYTES + 3 * INDEX_BYTES);
+
+    assert!(matches!(ctx.update_vertices(preview, 2..4, &[corner(0.0), corner(1.0)]), Err(SceneError::InvalidState(_))));
+    assert!(matches!(ctx.update_vertices(preview, 0..2, &[corner(0.0)]), Err(SceneError::InvalidState(_))));
+    assert!(ctx.update_vertices(preview, 0..1, &[corner(f32::NAN)]).is_err());
+    assert!(matches!(ctx.update_indices(preview, 0..1, &[3]), Err(SceneError::InvalidState("mesh index out of range"))));
+    assert!(matches!(ctx.update_indices(EntityId(99), 0..0, &[]), Err(SceneError::UnknownEntity(99))));
+    assert_eq!(ctx.sync_gpu().unwrap(), SyncReport::default());
+}
+
+#[test]
+fn vertex_updates_fall_back_to_full_uploads() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    let corner = Vertex { position: Vec3::new(0.5, 0.0, 0.0), normal: Vec3::Z, uv: None };
+    // Not yet uploaded: the patch joins the pending full upload.
+    ctx.update_vertices(id, 1..2, std::slice::from_ref(&corner)).unwrap();
+    let triangle_bytes = 3 * VERTEX_BYTES + 3 * INDEX_BYTES;
+    assert_eq!(ctx.sync_gpu().unwrap().uploaded_bytes, triangle_bytes);
+
+    // Levels of detail made from the old shape are dropped with a full upload.
+    ctx.add_lod_mesh(id, simple_triangle(), 0.1).unwrap();
+    ctx.sync_gpu().unwrap();
+    ctx.update_vertices(id, 1..2, &[corner]).unwrap();
+    assert!(ctx.lod_errors(id).unwrap().is_empty());
+    assert_eq!(ctx.sync_gpu().unwrap().uploaded_bytes, triangle_bytes);
+    ctx.add_lod_mesh(id, simple_triangle(), 0.1).unwrap();
+    ctx.sync_gpu().unwrap();
+    ctx.update_indices(id, 0..3, &[0, 2, 1]).unwrap();
+    assert!(ctx.lod_errors(id).unwrap().is_empty());
+    assert_eq!(ctx.sync_gpu().unwrap().uploaded_bytes, triangle_bytes);
+}
+
+#[test]
+fn overlay_draws_on_top_and_stays_out_of_the_world() {
+    let mut ctx = SceneContext::new();
+    let id = visible_triangle(&mut ctx);
+    ctx.set_current_view(plan_view(16));
+    let before = rendered(&mut ctx);
+
+    // Below the triangle, yet drawn over it.
+    let red = [255, 0, 0, 255];
+    let preview = ctx.add_overlay(OverlayItem::Preview { mesh: quad(Vec2::ZERO, Vec2::splat(0.5), -1.0), color: red }).unwrap();
+    let grip = ctx.add_overlay(OverlayItem::Grip { position: Vec3::new(0.5, 0.5, 0.0), hot: true }).unwrap();
+    ctx.render_current().unwrap();
+    let params = ctx.current_view().params();
+    let inside = params.to_screen(Vec3::new(0.1, 0.1, 0.0)).unwrap().as_uvec2();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    assert_eq!(target.pixel(inside.x, inside.y), red);
+    let centre = params.to_screen(Vec3::new(0.5, 0.5, 0.0)).unwrap().as_uvec2();
+    assert_eq!(target.pixel(centre.x, centre.y), HOT_GRIP_COLOR);
+
+    // Entity picks, ids, state and exports never see it.
+    let cursor = params.to_screen(Vec3::new(0.1, 0.1, 0.0)).unwrap();
+    assert_eq!(ctx.pick(ViewportId::MAIN, cursor).unwrap().unwrap().entity_id, id);
+    let hit = ctx.pick_overlay(ViewportId::MAIN, cursor).unwrap().unwrap();
+    assert_eq!(hit.overlay_id, preview);
+    assert!((hit.point - Vec3::new(0.1, 0.1, -1.0)).length() < 0.05);
+    assert_eq!(ctx.pick_overlay(ViewportId::MAIN, params.to_screen(Vec3::new(0.5, 0.5, 0.0)).unwrap() + Vec2::splat(2.0)).unwrap().unwrap().overlay_id, grip);
+    assert_eq!(ctx.export_gltf(&ExportOptions::default()).json["nodes"].as_array().unwrap().iter().filter(|node| node.get("mesh").is_some()).count(), 1);
+    assert_eq!(ctx.overlay().items().count(), 2);
+
+    // One call when the command ends.
+    ctx.clear_overlay();
+    assert!(ctx.overlay().is_empty());
+    assert_eq!(ctx.pick_overlay(ViewportId::MAIN, cursor).unwrap(), None);
+    assert_eq!(rendered(&mut ctx), before);
+    assert_eq!(visible_triangle(&mut ctx).0, id.0 + 1);
+}
+
+#[test]
+fn rubber_bands_and_markers_draw_in_pixels() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(plan_view(32));
+    let band = ctx.add_overlay(OverlayItem::RubberBand { from: Vec2::new(4.0, 4.0), to: Vec2::new(28.0, 20.0), crossing: false }).unwrap();
+    ctx.render_current().unwrap();
+    let edge = |ctx: &SceneContext| (4..28).map(|x| ctx.render_target(ViewportId::MAIN).unwrap().pixel(x, 4)).collect::<Vec<_>>();
+    assert!(edge(&ctx).iter().all(|p| *p == WINDOW_COLOR));
+    let fill = ctx.render_target(ViewportId::MAIN).unwrap().pixel(16, 12);
+    assert!(fill != BACKGROUND && fill != WINDOW_COLOR);
+
+    // Crossing windows are dashed.
+    ctx.update_overlay(band, OverlayItem::RubberBand { from: Vec2::new(28.0, 20.0), to: Vec2::new(4.0, 4.0), crossing: true }).unwrap();
+    ctx.render_current().unwrap();
+    let dashed = edge(&ctx);
+    assert!(dashed.contains(&CROSSING_COLOR) && dashed.iter().any(|p| *p != CROSSING_COLOR));
+    // Bands are never picked.
+    assert_eq!(ctx.pick_overlay(ViewportId::MAIN, Vec2::new(16.0, 12.0)).unwrap(), None);
+
+    ctx.remove_overlay(band).unwrap();
+    let marker = ctx.add_overlay(OverlayItem::SnapMarker { position: Vec3::new(0.25, 0.25, 0.0), glyph: MarkerGlyph::Square, color: [255, 255, 0, 255] }).unwrap();
+    ctx.render_current().unwrap();
+    let marked = ctx.render_target(ViewportId::MAIN).unwrap().pixels().iter().filter(|p| **p == [255, 255, 0, 255]).count();
+    // Four edges of MARKER_SIZE_PX, however far the view is zoomed.
+    assert!(marked as f32 >= 3.0 * MARKER_SIZE_PX);
+    assert_eq!(ctx.pick_overlay(ViewportId::MAIN, Vec2::new(16.0 + MARKER_SIZE_PX * 0.5 - 1.0, 16.0)).unwrap().unwrap().overlay_id, marker);
+    assert_eq!(ctx.pick_overlay(ViewportId::MAIN, Vec2::new(16.0 + MARKER_SIZE_PX, 16.0)).unwrap(), None);
+
+    assert!(matches!(ctx.remove_overlay(band), Err(SceneError::UnknownOverlay(_))));
+    assert!(matches!(ctx.update_overlay(band, OverlayItem::Grip { position: Vec3::ZERO, hot: false }), Err(SceneError::UnknownOverlay(_))));
+    assert!(ctx.add_overlay(OverlayItem::Grip { position: Vec3::NAN, hot: false }).is_err());
+
+    // Corners far off screen are rejected; guides through far points are clipped.
+    let far = Vec2::new(1.0e9, 4.0);
+    assert!(matches!(ctx.add_overlay(OverlayItem::RubberBand { from: Vec2::ZERO, to: far, crossing: true }), Err(SceneError::InvalidState(_))));
+    ctx.add_overlay(OverlayItem::RubberBand { from: Vec2::new(4.0, 4.0), to: Vec2::new(MAX_BAND_PX, 20.0), crossing: true }).unwrap();
+    ctx.add_overlay(OverlayItem::GuideLine { from: Vec3::ZERO, to: Vec3::new(1.0e12, 1.0e12, 0.0), color: [255, 0, 255, 255] }).unwrap();
+    let started = std::time::Instant::now();
+    ctx.render_current().unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    assert!(ctx.render_target(ViewportId::MAIN).unwrap().pixels().contains(&[255, 0, 255, 255]));
+}
+
+/// 100×100 pixel plan of (0,0)–(10,10), ten pixels per unit.
+fn snap_view() -> View {
+    let camera = Camera::look_at(Vec3::new(5.0, 5.0, 10.0), Vec3::new(5.0, 5.0, 0.0), UpAxis::Z).orthographic(10.0);
+    View::new(camera, UVec2::splat(100))
+}
+
+fn polylines(lines: &[Vec<Vec3>]) -> MeshData {
+    let mut mesh = MeshData::default();
+    for line in lines {
+        let start = mesh.vertices.len() as u32;
+        mesh.vertices.extend(line.iter().map(|p| Vertex { position: *p, normal: Vec3::Z, uv: None }));
+        mesh.polylines.push((start..start + line.len() as u32).collect());
+    }
+    mesh
+}
+
+fn arc_points(center: Vec3, radius: f32, from: f32, to: f32, segments: usize) -> Vec<Vec3> {
+    (0..=segments).map(|i| center + Vec2::from_angle(from + (to - from) * i as f32 / segments as f32).extend(0.0) * radius).collect()
+}
+
+fn snap_at(ctx: &SceneContext, x: f32, y: f32, from: Option<Vec3>) -> PointInputResult {
+    let cursor = ctx.current_view().params().to_screen(Vec3::new(x, y, 0.0)).unwrap();
+    ctx.point_input(ViewportId::MAIN, cursor, from, None).unwrap()
+}
+
+fn assert_snap(result: PointInputResult, kind: Option<SnapKind>, point: Vec3) {
+    assert_eq!(result.snap_type, kind, "{result:?}");
+    assert!(result.snapped.distance(point) < 1e-3, "{result:?} is not at {point}");
+}
+
+#[test]
+fn snaps_follow_priority_within_the_aperture() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    let lines = vec![vec![Vec3::new(1.0, 5.0, 0.0), Vec3::new(9.0, 5.0, 0.0)], vec![Vec3::new(7.0, 1.0, 0.0), Vec3::new(7.0, 9.0, 0.0)]];
+    submit_visible(&mut ctx, polylines(&lines), Transform::identity());
+
+    // An endpoint 8 px away beats the nearest point right under the cursor.
+    assert_snap(snap_at(&ctx, 1.8, 5.0, None), Some(SnapKind::Endpoint), Vec3::new(1.0, 5.0, 0.0));
+    assert_snap(snap_at(&ctx, 5.05, 5.05, None), Some(SnapKind::Midpoint), Vec3::new(5.0, 5.0, 0.0));
+    assert_snap(snap_at(&ctx, 7.3, 5.2, None), Some(SnapKind::Intersection), Vec3::new(7.0, 5.0, 0.0));
+    assert_snap(snap_at(&ctx, 2.5, 5.3, None), Some(SnapKind::Nearest), Vec3::new(2.5, 5.0, 0.0));
+    // Nothing within 10 px: the cursor on the work plane.
+    let free = snap_at(&ctx, 3.0, 8.0, None);
+    assert_snap(free, None, Vec3::new(3.0, 8.0, 0.0));
+    assert_eq!(free.raw, free.snapped);
+
+    ctx.set_snap_settings(SnapSettings { endpoint: false, ..SnapSettings::default() }).unwrap();
+    assert_snap(snap_at(&ctx, 1.8, 5.0, None), Some(SnapKind::Nearest), Vec3::new(1.8, 5.0, 0.0));
+    ctx.set_snap_settings(SnapSettings { aperture_px: 5.0, ..SnapSettings::default() }).unwrap();
+    assert_snap(snap_at(&ctx, 1.8, 5.0, None), Some(SnapKind::Nearest), Vec3::new(1.8, 5.0, 0.0));
+    assert!(ctx.set_snap_settings(SnapSettings { aperture_px: 0.0, ..SnapSettings::default() }).is_err());
+    assert!(ctx.point_input(ViewportId::MAIN, Vec2::NAN, None, None).is_err());
+    assert_eq!(SnapKind::ALL.iter().map(|kind| kind.glyph()).collect::<std::collections::HashSet<_>>().len(), SnapKind::ALL.len());
+}
+
+#[test]
+fn circles_snap_to_center_perpendicular_and_tangent() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    let centre = Vec3::new(5.0, 5.0, 0.0);
+    let lines = vec![arc_points(centre, 2.0, 0.0, std::f32::consts::TAU, 32), vec![Vec3::new(1.0, 1.0, 0.0), Vec3::new(9.0, 1.0, 0.0)]];
+    submit_visible(&mut ctx, polylines(&lines), Transform::identity());
+
+    // Hovering the circle offers its centre; its vertices are not endpoints.
+    assert_snap(snap_at(&ctx, 7.0, 5.0, None), Some(SnapKind::Center), centre);
+    let from = Vec3::new(3.0, 4.0, 0.0);
+    assert_snap(snap_at(&ctx, 3.2, 1.1, Some(from)), Some(SnapKind::Perpendicular), Vec3::new(3.0, 1.0, 0.0));
+    assert_snap(snap_at(&ctx, 3.2, 1.1, None), Some(SnapKind::Nearest), Vec3::new(3.2, 1.0, 0.0));
+
+    ctx.set_snap_settings(SnapSettings { center: false, ..SnapSettings::default() }).unwrap();
+    let from = Vec3::new(9.0, 5.0, 0.0);
+    let tangent = Vec3::new(6.0, 5.0 + 3f32.sqrt(), 0.0);
+    assert_snap(snap_at(&ctx, 6.1, 6.7, Some(from)), Some(SnapKind::Tangent), tangent);
+    assert_snap(snap_at(&ctx, 7.05, 5.05, Some(from)), Some(SnapKind::Perpendicular), Vec3::new(7.0, 5.0, 0.0));
+    assert_eq!(snap_at(&ctx, 6.1, 6.7, None).snap_type, Some(SnapKind::Nearest));
+    // No tangents from inside the circle.
+    assert_eq!(snap_at(&ctx, 6.1, 6.7, Some(Vec3::new(5.5, 5.0, 0.0))).snap_type, Some(SnapKind::Nearest));
+}
+
+#[test]
+fn arcs_and_mesh_edges_snap_and_respect_sections() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    let centre = Vec3::new(5.0, 5.0, 0.0);
+    let arc = vec![arc_points(centre, 3.0, 0.0, std::f32::consts::PI, 16)];
+    submit_visible(&mut ctx, polylines(&arc), Transform::identity());
+    assert_snap(snap_at(&ctx, 7.95, 5.05, None), Some(SnapKind::Endpoint), Vec3::new(8.0, 5.0, 0.0));
+    assert_snap(snap_at(&ctx, 5.05, 7.95, None), Some(SnapKind::Midpoint), Vec3::new(5.0, 8.0, 0.0));
+    let vertex = arc[0][3];
+    assert_snap(snap_at(&ctx, vertex.x, vertex.y, None), Some(SnapKind::Center), centre);
+
+    // Triangle meshes snap to their boundary, not the diagonal; the raw
+    // point is the surface under the cursor.
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    submit_visible(&mut ctx, quad(Vec2::ZERO, Vec2::splat(4.0), 1.0), Transform::identity());
+    let inside = snap_at(&ctx, 2.0, 2.0, None);
+    assert_snap(inside, None, Vec3::new(2.0, 2.0, 1.0));
+    assert_snap(snap_at(&ctx, 3.95, 3.9, None), Some(SnapKind::Endpoint), Vec3::new(4.0, 4.0, 1.0));
+    ctx.add_clip_plane(ClipPlane { normal: Vec3::X, distance: 3.0 }).unwrap();
+    assert_eq!(snap_at(&ctx, 3.95, 3.9, None).snap_type, None);
+}
+
+#[test]
+fn constraints_pick_the_enabled_direction_nearest_the_cursor() {
+    let camera = snap_view().camera;
+    let base = Vec3::new(2.0, 2.0, 0.0);
+    let guide = Some([Vec3::ZERO, Vec3::new(1.0, 1.0, 0.0)]);
+    use ConstraintKind::{Ortho, Parallel, Perpendicular};
+    // Per cursor offset: the constraints from closest to farthest, and where each puts the point.
+    let cases = [
+        (Vec3::new(3.0, 0.5, 0.0), [(Ortho, Vec3::new(5.0, 2.0, 0.0)), (Parallel, Vec3::new(3.75, 3.75, 0.0)), (Perpendicular, Vec3::new(3.25, 0.75, 0.0))]),
+        (Vec3::new(2.0, 1.8, 0.0), [(Parallel, Vec3::new(3.9, 3.9, 0.0)), (Ortho, Vec3::new(4.0, 2.0, 0.0)), (Perpendicular, Vec3::new(2.1, 1.9, 0.0))]),
+        (Vec3::new(-1.5, 1.7, 0.0), [(Perpendicular, Vec3::new(0.4, 3.6, 0.0)), (Ortho, Vec3::new(2.0, 3.7, 0.0)), (Parallel, Vec3::new(2.1, 2.1, 0.0))]),
+    ];
+    for bits in 0..8 {
+        let settings = ConstraintSettings { ortho: bits & 1 != 0, parallel: bits & 2 != 0, perpendicular: bits & 4 != 0 };
+        let on = |kind: ConstraintKind| match kind {
+            Ortho => settings.ortho,
+            Parallel => settings.parallel,
+            Perpendicular => settings.perpendicular,
+        };
+        for (offset, ranked) in cases {
+            let result = constraint::constrain(&settings, &camera, base, base + offset, guide);
+            match ranked.iter().find(|(kind, _)| on(*kind)) {
+                Some((kind, point)) => {
+                    let result = result.unwrap_or_else(|| panic!("{settings:?} {offset}"));
+                    assert_eq!(result.kind, *kind, "{settings:?} {offset}");
+                    assert!(result.point.distance(*point) < 1e-5, "{settings:?} {offset}: {result:?}");
+                    assert!(result.direction.dot(offset) > 0.0 && result.distance_to_line(base, result.point) < 1e-5);
+                }
+                None => assert_eq!(result, None, "{settings:?} {offset}"),
+            }
+            // Without a guide only Ortho is left.
+            let unguided = constraint::constrain(&settings, &camera, base, base + offset, None);
+            assert_eq!(unguided.map(|c| c.kind), settings.ortho.then_some(Ortho), "{settings:?} {offset}");
+            // Nothing to hold on the base point itself.
+            assert_eq!(constraint::constrain(&settings, &camera, base, base, guide), None);
+        }
+    }
+}
+
+#[test]
+fn snapping_under_a_constraint_stays_on_its_line() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    let lines = vec![vec![Vec3::new(6.0, 1.5, 0.0), Vec3::new(6.0, 9.0, 0.0)], vec![Vec3::new(4.0, 0.0, 0.0), Vec3::new(4.0, 9.0, 0.0)], vec![Vec3::new(8.0, 1.0, 0.0), Vec3::new(9.0, 3.0, 0.0)]];
+    submit_visible(&mut ctx, polylines(&lines), Transform::identity());
+    ctx.set_constraint_settings(ConstraintSettings { ortho: true, ..ConstraintSettings::default() });
+    let from = Some(Vec3::new(1.0, 1.0, 0.0));
+
+    // The endpoint at (6, 1.5) is within the aperture but off the line.
+    let held = snap_at(&ctx, 6.05, 1.4, from);
+    assert_snap(held, None, Vec3::new(6.05, 1.0, 0.0));
+    assert!((held.raw.y - 1.4).abs() < 1e-5);
+    assert_eq!(held.constraint, Some(ConstraintKind::Ortho));
+    // Where the line crosses an edge, and endpoints on it, still snap.
+    let crossing = snap_at(&ctx, 4.2, 1.6, from);
+    assert_snap(crossing, Some(SnapKind::Intersection), Vec3::new(4.0, 1.0, 0.0));
+    assert_snap(snap_at(&ctx, 8.1, 1.3, from), Some(SnapKind::Endpoint), Vec3::new(8.0, 1.0, 0.0));
+    // Vertical once the cursor is more above than beside the base.
+    let vertical = snap_at(&ctx, 1.3, 7.0, from);
+    assert_snap(vertical, None, Vec3::new(1.0, 7.0, 0.0));
+
+    // Parallel to a guide, and without a base point no constraint at all.
+    ctx.set_constraint_settings(ConstraintSettings { parallel: true, ..ConstraintSettings::default() });
+    let cursor = ctx.current_view().params().to_screen(Vec3::new(2.5, 2.4, 0.0)).unwrap();
+    let guide = Some([Vec3::ZERO, Vec3::new(1.0, 1.0, 0.0)]);
+    let parallel = ctx.point_input(ViewportId::MAIN, cursor, from, guide).unwrap();
+    assert_snap(parallel, None, Vec3::new(2.45, 2.45, 0.0));
+    assert_eq!(parallel.constraint, Some(ConstraintKind::Parallel));
+    assert_eq!(ctx.point_input(ViewportId::MAIN, cursor, None, guide).unwrap().constraint, None);
+    assert!(ctx.point_input(ViewportId::MAIN, cursor, from, Some([Vec3::ZERO, Vec3::NAN])).is_err());
+}
+
+fn tracked_at(ctx: &SceneContext, x: f32, y: f32, from: Option<Vec3>) -> (PointInputResult, Vec<GuideLine>) {
+    let cursor = ctx.current_view().params().to_screen(Vec3::new(x, y, 0.0)).unwrap();
+    ctx.tracked_point_input(ViewportId::MAIN, cursor, from, None).unwrap()
+}
+
+#[test]
+fn polar_tracking_pulls_the_cursor_onto_increments() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    let from = Some(Vec3::new(2.0, 2.0, 0.0));
+    // Off by default, like constraints.
+    assert!(tracked_at(&ctx, 5.0, 5.2, from).1.is_empty());
+    let polar = TrackingSettings { polar: true, ..TrackingSettings::default() };
+    ctx.set_tracking_settings(polar).unwrap();
+
+    let (point, guides) = tracked_at(&ctx, 5.0, 5.2, from);
+    assert_snap(point, None, Vec3::new(5.1, 5.1, 0.0));
+    assert_eq!(guides.len(), 1);
+    assert_eq!((guides[0].kind, guides[0].angle_deg, guides[0].from), (TrackingKind::Polar, 45.0, Vec3::new(2.0, 2.0, 0.0)));
+    assert_eq!(snap_at(&ctx, 5.0, 5.2, from), point);
+    // 26° is more than the capture distance off both 0° and 45°.
+    let (free, guides) = tracked_at(&ctx, 5.0, 3.5, from);
+    assert_eq!((free.snapped, guides.len()), (free.raw, 0));
+    // Rays start at the base point.
+    assert!(tracked_at(&ctx, 0.0, 0.1, from).1.iter().all(|g| g.angle_deg == 225.0));
+
+    ctx.set_tracking_settings(TrackingSettings { polar_increment_deg: 30.0, ..polar }).unwrap();
+    let (sin, cos) = 30f32.to_radians().sin_cos();
+    let on_ray = Vec3::new(2.0 + 3.0 * cos, 2.0 + 3.0 * sin, 0.0);
+    // A little off the ray, square to it.
+    let (thirty, guides) = tracked_at(&ctx, on_ray.x - 0.1 * sin, on_ray.y + 0.1 * cos, from);
+    assert_snap(thirty, None, on_ray);
+    assert_eq!(guides[0].angle_deg, 30.0);
+    ctx.set_tracking_settings(TrackingSettings::default()).unwrap();
+    assert!(tracked_at(&ctx, 5.0, 5.2, from).1.is_empty());
+    // Every increment casts a ray and every pair of rays is searched for crossings.
+    assert!(ctx.set_tracking_settings(TrackingSettings { polar_increment_deg: 0.0, ..polar }).is_err());
+    assert!(ctx.set_tracking_settings(TrackingSettings { polar_increment_deg: 0.5, ..polar }).is_err());
+    assert!(ctx.set_tracking_settings(TrackingSettings { polar_increment_deg: MIN_POLAR_INCREMENT_DEG, ..polar }).is_ok());
+    assert!(ctx.set_tracking_settings(TrackingSettings { max_points: 0, ..polar }).is_err());
+    assert!(ctx.set_tracking_settings(TrackingSettings { max_points: MAX_TRACKING_POINTS + 1, ..polar }).is_err());
+    assert!(ctx.set_tracking_settings(TrackingSettings { max_points: MAX_TRACKING_POINTS, ..polar }).is_ok());
+}
+
+#[test]
+fn object_tracking_reports_crossings_and_draws_guides() {
+    let mut ctx = SceneContext::new();
+    ctx.set_current_view(snap_view());
+    let tracking = TrackingSettings { object_tracking: true, ..TrackingSettings::default() };
+    ctx.set_tracking_settings(tracking).unwrap();
+    assert!(ctx.acquire_tracking_point(Vec3::new(2.0, 6.0, 0.0)).unwrap());
+    assert!(ctx.acquire_tracking_point(Vec3::new(7.0, 2.0, 0.0)).unwrap());
+    assert!(!ctx.acquire_tracking_point(Vec3::new(7.0, 2.0, 0.0)).unwrap());
+
+    let (crossing, guides) = tracked_at(&ctx, 7.05, 5.95, None);
+    assert_snap(crossing, None, Vec3::new(7.0, 6.0, 0.0));
+    let angles: Vec<f32> = guides.iter().map(|g| g.angle_deg).collect();
+    assert_eq!(angles, [0.0, 90.0]);
+    assert!(guides.iter().all(|g| g.kind == TrackingKind::Object && g.to == Vec3::new(7.0, 6.0, 0.0)));
+    let (along, guides) = tracked_at(&ctx, 4.0, 6.1, None);
+    assert_snap(along, None, Vec3::new(4.0, 6.0, 0.0));
+    assert_eq!(guides.len(), 1);
+
+    // Guides draw dashed from the tracking point to the edge of the view.
+    for guide in &guides {
+        ctx.add_overlay(guide.overlay_item()).unwrap();
+    }
+    ctx.render_current().unwrap();
+    let target = ctx.render_target(ViewportId::MAIN).unwrap();
+    let guide_columns: Vec<u32> = (0..100).filter(|x| (39..=40).any(|y| target.pixel(*x, y) == GUIDE_COLOR)).collect();
+    assert!(guide_columns.first().is_some_and(|x| (19..=20).contains(x)) && guide_columns.last().is_some_and(|x| *x >= 90), "{guide_columns:?}");
+    assert!(guide_columns.len() < 80);
+
+    // Snaps win over tracking.
+    let line = vec![vec![Vec3::new(4.0, 6.0, 0.0), Vec3::new(4.0, 9.0, 0.0)]];
+    submit_visible(&mut ctx, polylines(&line), Transform::identity());
+    let (snapped, guides) = tracked_at(&ctx, 4.0, 6.1, None);
+    assert_eq!((snapped.snap_type, guides.len()), (Some(SnapKind::Endpoint), 0));
+
+    ctx.set_tracking_settings(TrackingSettings { max_points: 1, ..tracking }).unwrap();
+    assert_eq!(ctx.tracking_points(), [Vec3::new(7.0, 2.0, 0.0)]);
+    assert!(ctx.release_tracking_point(Vec3::new(7.0, 2.0, 0.0)));
+    ctx.acquire_tracking_point(Vec3::ONE).unwrap();
+    ctx.clear_tracking();
+    assert!(ctx.tracking_points().is_empty());
+    assert!(ctx.acquire_tracking_point(Vec3::NAN).is_err());
+}
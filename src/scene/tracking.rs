@@ -0,0 +1,204 @@
+//! Polar and object snap tracking.
+//!
+//! Acquired points cast horizontal and vertical alignment rays, and the
+//! previous point of a command casts rays at every polar increment; both are
+//! in the screen's plane. A cursor within the capture distance of a ray is
+//! pulled onto it, and where two rays from different points cross near the
+//! cursor that crossing wins. The rays used come back as guide lines the
+//! overlay can draw.
+
+use glam::{Vec2, Vec3};
+
+use crate::scene::{
+    camera::{Camera, CameraParams},
+    color::Rgba,
+    error::{SceneError, SceneResult},
+    overlay::OverlayItem,
+    snap::closest_params,
+};
+
+pub const GUIDE_COLOR: Rgba = [0, 200, 120, 255];
+/// Finest polar increment; each one casts a ray.
+pub const MIN_POLAR_INCREMENT_DEG: f32 = 1.0;
+/// Most acquired points; crossings are searched between every pair of rays.
+pub const MAX_TRACKING_POINTS: usize = 32;
+
+/// Which tracking modes are on; both off by default, like constraints.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TrackingSettings {
+    /// Rays from the previous point at every `polar_increment_deg`.
+    pub polar: bool,
+    pub polar_increment_deg: f32,
+    /// Horizontal and vertical rays from acquired points.
+    pub object_tracking: bool,
+    /// How close to a ray, in pixels, the cursor must come to be pulled onto it.
+    pub capture_px: f32,
+    /// Acquired points kept; the oldest goes first.
+    pub max_points: usize,
+}
+
+impl Default for TrackingSettings {
+    fn default() -> Self {
+        Self { polar: false, polar_increment_deg: 45.0, object_tracking: false, capture_px: 6.0, max_points: 7 }
+    }
+}
+
+impl TrackingSettings {
+    pub fn validate(&self) -> SceneResult<()> {
+        if !(MIN_POLAR_INCREMENT_DEG..=180.0).contains(&self.polar_increment_deg) {
+            return Err(SceneError::InvalidState("polar increment must be within [1, 180] degrees"));
+        }
+        if !self.capture_px.is_finite() || self.capture_px <= 0.0 {
+            return Err(SceneError::InvalidState("tracking capture distance must be positive"));
+        }
+        if self.max_points == 0 {
+            return Err(SceneError::InvalidState("at least one tracking point must be kept"));
+        }
+        if self.max_points > MAX_TRACKING_POINTS {
+            return Err(SceneError::InvalidState("tracking points kept must not exceed the tracker limit"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackingKind {
+    Polar,
+    Object,
+}
+
+/// Ray from a tracking point to where the cursor was placed on it.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GuideLine {
+    pub from: Vec3,
+    pub to: Vec3,
+    /// Counter-clockwise from screen right.
+    pub angle_deg: f32,
+    pub kind: TrackingKind,
+}
+
+impl GuideLine {
+    /// Dashed overlay ray from `from` through `to` to the edge of the viewport.
+    pub fn overlay_item(&self) -> OverlayItem {
+        OverlayItem::GuideLine { from: self.from, to: self.to, color: GUIDE_COLOR }
+    }
+}
+
+/// Points acquired for object tracking, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tracker {
+    points: Vec<Vec3>,
+}
+
+impl Tracker {
+    /// Adds `point` unless already acquired; returns whether it was added.
+    pub fn acquire(&mut self, point: Vec3, max_points: usize) -> bool {
+        if self.points.contains(&point) {
+            return false;
+        }
+        self.points.push(point);
+        self.limit(max_points);
+        true
+    }
+
+    /// Drops the oldest points beyond `max_points`.
+    pub fn limit(&mut self, max_points: usize) {
+        let excess = self.points.len().saturating_sub(max_points);
+        self.points.drain(..excess);
+    }
+
+    pub fn release(&mut self, point: Vec3) -> bool {
+        let before = self.points.len();
+        self.points.retain(|p| *p != point);
+        self.points.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+}
+
+/// Cursor placed by tracking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tracked {
+    pub point: Vec3,
+    /// One guide on a ray, two at a crossing.
+    pub guides: Vec<GuideLine>,
+}
+
+struct Ray {
+    origin: Vec3,
+    direction: Vec3,
+    angle_deg: f32,
+    kind: TrackingKind,
+}
+
+impl Ray {
+    fn guide(&self, to: Vec3) -> GuideLine {
+        GuideLine { from: self.origin, to, angle_deg: self.angle_deg, kind: self.kind }
+    }
+}
+
+/// Pulls `raw`, the cursor's scene point, onto a tracking ray or crossing
+/// within the capture distance of `cursor`, or `None` when none is that close.
+pub fn track(settings: &TrackingSettings, tracker: &Tracker, camera: &Camera, params: &CameraParams, cursor: Vec2, raw: Vec3, from: Option<Vec3>) -> Option<Tracked> {
+    let (right, up, forward) = (camera.right(), camera.screen_up(), camera.forward());
+    let ray = |origin: Vec3, angle_deg: f32, kind: TrackingKind| {
+        let (sin, cos) = angle_deg.to_radians().sin_cos();
+        Ray { origin, direction: right * cos + up * sin, angle_deg, kind }
+    };
+    let mut rays = Vec::new();
+    if settings.object_tracking {
+        for point in tracker.points() {
+            rays.extend([0.0, 90.0, 180.0, 270.0].map(|angle| ray(*point, angle, TrackingKind::Object)));
+        }
+    }
+    if let Some(from) = from.filter(|_| settings.polar) {
+        let steps = (360.0 / settings.polar_increment_deg).ceil() as usize;
+        rays.extend((0..steps).map(|k| k as f32 * settings.polar_increment_deg).filter(|angle| *angle < 360.0).map(|angle| ray(from, angle, TrackingKind::Polar)));
+    }
+    let distance = |point: Vec3| params.to_screen(point).map(|p| p.distance(cursor));
+
+    // Rays the cursor is close to, with the point it lands on.
+    let active: Vec<(&Ray, Vec3, f32)> = rays
+        .iter()
+        .filter_map(|ray| {
+            let t = (raw - ray.origin).dot(ray.direction);
+            let point = ray.origin + ray.direction * t;
+            let d = distance(point)?;
+            (t > 0.0 && d <= settings.capture_px).then_some((ray, point, d))
+        })
+        .collect();
+    let mut crossing: Option<(Vec3, [&Ray; 2], f32)> = None;
+    for (i, (a, _, _)) in active.iter().enumerate() {
+        for (b, _, _) in &active[i + 1..] {
+            if a.origin == b.origin {
+                continue;
+            }
+            // Crossing in the screen plane through `a`'s origin.
+            let shifted = b.origin - forward * (b.origin - a.origin).dot(forward);
+            let Some((s, t)) = closest_params(a.origin, a.direction, shifted, b.direction) else {
+                continue;
+            };
+            let point = a.origin + a.direction * s;
+            let Some(d) = distance(point).filter(|d| s > 0.0 && t > 0.0 && *d <= settings.capture_px) else {
+                continue;
+            };
+            if crossing.as_ref().is_none_or(|best| d < best.2) {
+                crossing = Some((point, [*a, *b], d));
+            }
+        }
+    }
+    if let Some((point, [a, b], _)) = crossing {
+        let shifted = point + forward * (b.origin - point).dot(forward);
+        return Some(Tracked { point, guides: vec![a.guide(point), b.guide(shifted)] });
+    }
+    let (ray, point, _) = active.into_iter().min_by(|a, b| a.2.total_cmp(&b.2))?;
+    Some(Tracked { point, guides: vec![ray.guide(point)] })
+}
@@ -1,10 +1,28 @@
-use glam::{Mat4, Quat, Vec3};
+use glam::{Mat3, Mat4, Quat, Vec3, Vec4};
+
+use crate::scene::error::{SceneError, SceneResult};
+
+/// Relative determinant below which the linear part is treated as singular.
+///
+/// The determinant is normalised by the column lengths so that uniformly tiny
+/// (millimetre) or huge (kilometre) scales are not mistaken for degeneracy.
+const SINGULAR_EPSILON: f32 = 1e-6;
 
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Transform {
     pub matrix: Mat4,
 }
 
+/// Winding order of front-facing triangles after the model transform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum FrontFace {
+    /// Counter-clockwise triangles face the viewer (the mesh convention).
+    #[default]
+    Ccw,
+    /// Clockwise triangles face the viewer; the transform mirrors the mesh.
+    Cw,
+}
+
 impl Transform {
     pub fn identity() -> Self {
         Self {
@@ -16,4 +34,49 @@ impl Transform {
         let matrix = Mat4::from_scale_rotation_translation(scale, rotation, translation);
         Self { matrix }
     }
+
+    /// Checks that the matrix is usable as a model matrix.
+    ///
+    /// Rejects non-finite values and singular linear parts. With `affine_only`
+    /// the bottom row must also be `(0, 0, 0, 1)`.
+    pub fn validate(&self, affine_only: bool) -> SceneResult<()> {
+        if !self.matrix.is_finite() {
+            return Err(SceneError::InvalidTransform("matrix contains non-finite values"));
+        }
+        if affine_only && self.matrix.row(3) != Vec4::W {
+            return Err(SceneError::InvalidTransform("matrix is not affine (bottom row must be 0, 0, 0, 1)"));
+        }
+        let linear = Mat3::from_mat4(self.matrix);
+        let scale = linear.x_axis.length() * linear.y_axis.length() * linear.z_axis.length();
+        if scale == 0.0 || (linear.determinant() / scale).abs() < SINGULAR_EPSILON {
+            return Err(SceneError::InvalidTransform("matrix is singular (zero or degenerate scale)"));
+        }
+        Ok(())
+    }
+
+    /// Determinant of the upper-left 3x3 (linear) part.
+    pub fn determinant(&self) -> f32 {
+        Mat3::from_mat4(self.matrix).determinant()
+    }
+
+    /// True when the transform flips handedness (negative determinant).
+    pub fn is_mirrored(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    pub fn front_face(&self) -> FrontFace {
+        if self.is_mirrored() {
+            FrontFace::Cw
+        } else {
+            FrontFace::Ccw
+        }
+    }
+
+    /// Inverse-transpose of the linear part, for transforming normals.
+    ///
+    /// Fails with `InvalidTransform` instead of producing NaNs.
+    pub fn normal_matrix(&self) -> SceneResult<Mat3> {
+        self.validate(false)?;
+        Ok(Mat3::from_mat4(self.matrix).inverse().transpose())
+    }
 }
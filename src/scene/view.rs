@@ -0,0 +1,105 @@
+use glam::{UVec2, Vec3};
+
+use crate::scene::{
+    bounds::Aabb,
+    camera::{Camera, CameraParams, UpAxis},
+    error::{SceneError, SceneResult},
+    raster::MAX_TARGET_EDGE,
+};
+
+/// Rejects viewports with an edge longer than the largest render target.
+pub fn validate_viewport(viewport: UVec2) -> SceneResult<()> {
+    if viewport.max_element() > MAX_TARGET_EDGE {
+        return Err(SceneError::InvalidState("viewport edges must not exceed the largest render target"));
+    }
+    Ok(())
+}
+
+/// A navigable camera together with the viewport it is rendered into.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct View {
+    pub camera: Camera,
+    pub viewport: UVec2,
+}
+
+impl View {
+    pub fn new(camera: Camera, viewport: UVec2) -> Self {
+        Self { camera, viewport }
+    }
+
+    pub fn validate(&self) -> SceneResult<()> {
+        validate_viewport(self.viewport)?;
+        self.camera.validate()
+    }
+
+    /// This view with its viewport clamped to what a render target can hold.
+    pub(crate) fn capped(self) -> Self {
+        Self { viewport: self.viewport.min(UVec2::splat(MAX_TARGET_EDGE)), ..self }
+    }
+
+    pub fn params(&self) -> CameraParams {
+        self.camera.params(self.viewport)
+    }
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self::new(Camera::default(), UVec2::new(800, 600))
+    }
+}
+
+/// Built-in named views. They are computed from the scene bounds on restore
+/// and cannot be overwritten by user views.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandardView {
+    Top,
+    Front,
+    Right,
+    Iso,
+}
+
+impl StandardView {
+    pub const ALL: [StandardView; 4] = [StandardView::Top, StandardView::Front, StandardView::Right, StandardView::Iso];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StandardView::Top => "Top",
+            StandardView::Front => "Front",
+            StandardView::Right => "Right",
+            StandardView::Iso => "Iso",
+        }
+    }
+
+    /// Case-insensitive lookup by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|view| view.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Viewing direction (eye towards target) for the given up axis.
+    pub fn direction(self, up: UpAxis) -> Vec3 {
+        // Expressed for Z-up: +Y is north (Front looks north), +X is east.
+        let z_up = match self {
+            StandardView::Top => Vec3::NEG_Z,
+            StandardView::Front => Vec3::Y,
+            StandardView::Right => Vec3::NEG_X,
+            StandardView::Iso => Vec3::new(-1.0, 1.0, -1.0).normalize(),
+        };
+        match up {
+            UpAxis::Z => z_up,
+            UpAxis::Y => Vec3::new(z_up.x, z_up.z, -z_up.y),
+        }
+    }
+
+    /// Camera looking along this view's direction, fitted to `bounds` when given.
+    ///
+    /// Projection, clip planes and depth mode are taken from `base`.
+    pub fn camera(self, base: &Camera, bounds: Option<&Aabb>, viewport: UVec2) -> Camera {
+        let mut camera = *base;
+        let distance = base.distance().max(camera.near * 2.0);
+        camera.eye = camera.target - self.direction(base.up) * distance;
+        if let Some(bounds) = bounds {
+            camera.zoom_extents(bounds, viewport);
+        }
+        camera
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use crate::scene::{id::EntityId, raster::RenderTarget, view::View};
+
+/// Identifier of a viewport within one `SceneContext`. Never recycled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct ViewportId(pub u32);
+
+impl ViewportId {
+    /// The viewport that always exists and backs the "current view".
+    pub const MAIN: ViewportId = ViewportId(0);
+}
+
+/// Lineweight scale of a 96 dpi screen.
+pub const DEFAULT_PIXELS_PER_MM: f32 = 96.0 / 25.4;
+
+/// How a viewport draws its entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Shaded,
+    /// Triangle edges only, without hidden-line removal.
+    Wireframe,
+    /// Shaded surfaces with depth-tested edges on top.
+    ShadedWithEdges,
+    /// Depth-tested edges over surfaces filled with the background colour.
+    HiddenLine,
+    /// Translucent surfaces without depth writes, so everything shows through.
+    XRay,
+}
+
+#[derive(Debug)]
+pub(crate) struct Viewport {
+    pub view: View,
+    pub display_mode: DisplayMode,
+    /// Per-viewport visibility: `true` shows, `false` hides regardless of `VisualFlags::VISIBLE`.
+    pub visibility: HashMap<EntityId, bool>,
+    pub cull_back_faces: bool,
+    /// Edge passes draw feature edges instead of every triangle edge.
+    pub feature_wireframe: bool,
+    /// Plot scale for lineweights: pixels per millimetre of plotted width.
+    pub pixels_per_mm: f32,
+    /// Level of detail last drawn per entity, the starting point for hysteresis.
+    pub lod: HashMap<EntityId, usize>,
+    pub target: RenderTarget,
+    /// Set when the target no longer matches the view or display settings.
+    pub stale: bool,
+}
+
+impl Viewport {
+    pub fn new(view: View) -> Self {
+        Self {
+            view,
+            display_mode: DisplayMode::default(),
+            visibility: HashMap::new(),
+            cull_back_faces: false,
+            feature_wireframe: false,
+            pixels_per_mm: DEFAULT_PIXELS_PER_MM,
+            lod: HashMap::new(),
+            target: RenderTarget::new(view.viewport),
+            stale: true,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use bitflags::bitflags;
 
+use crate::scene::color::Rgba;
+
 bitflags! {
     /// Visual state for an entity.
     #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -39,3 +41,30 @@ bitflags! {
         const VISUAL = 0b0100;
     }
 }
+
+/// How SELECTED and HIGHLIGHTED entities stand out, applied in the Visual sync stage.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HighlightStyle {
+    /// Surface tint and outline of selected entities.
+    pub selection_color: Rgba,
+    /// Outline of highlighted (pre-selected) entities.
+    pub highlight_color: Rgba,
+    /// Outline thickness in pixels; `0` disables outlines.
+    pub outline_px: f32,
+    /// Draw outlines over everything instead of depth testing them.
+    pub draw_on_top: bool,
+    /// Share of `highlight_color` mixed into highlighted surfaces, `0..=1`.
+    pub hover_tint: f32,
+}
+
+impl Default for HighlightStyle {
+    fn default() -> Self {
+        Self {
+            selection_color: [64, 156, 255, 255],
+            highlight_color: [255, 196, 64, 255],
+            outline_px: 2.0,
+            draw_on_top: true,
+            hover_tint: 0.3,
+        }
+    }
+}
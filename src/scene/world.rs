@@ -1,13 +1,23 @@
 use std::collections::HashMap;
 
-use glam::{Mat3, Mat4};
+use glam::{DVec3, Mat3, Mat4};
 
-use crate::scene::{id::EntityId, mesh::MeshData, transform::Transform, visual::VisualFlags};
+use crate::scene::{
+    appearance::Appearance,
+    bounds::Aabb,
+    id::EntityId,
+    lod::LodLevel,
+    mesh::MeshData,
+    transform::{FrontFace, Transform},
+    visual::VisualFlags,
+};
 
 #[derive(Debug, Default)]
 pub struct SceneWorld {
     pub(crate) entities: HashMap<EntityId, EntityRecord>,
     pub(crate) next_id: u64,
+    /// World point that scene space (cameras, sections, picking) is measured from.
+    pub(crate) rebase_origin: DVec3,
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +27,27 @@ pub(crate) struct EntityRecord {
     pub mesh: Option<MeshData>,
     pub model_matrix: Mat4,
     pub normal_matrix: Mat3,
+    /// Flipped to `Cw` for mirroring transforms so culling stays correct.
+    pub front_face: FrontFace,
+    pub appearance: Appearance,
+    /// Coarser tessellations by increasing error; `mesh` is the finest.
+    pub lods: Vec<LodLevel>,
+    /// World position of the local origin, in f64 for large site coordinates.
+    pub origin: DVec3,
+}
+
+impl EntityRecord {
+    /// Recomputes `model_matrix`, which maps into scene space (world minus
+    /// `rebase`). The f64 origin is rebased before it is rounded to f32.
+    pub fn update_model(&mut self, rebase: DVec3) {
+        let offset = (self.origin - rebase).as_vec3();
+        self.model_matrix = Mat4::from_translation(offset) * self.transform.matrix;
+    }
+
+    /// World-space bounds of the mesh under the current model matrix.
+    pub fn world_bounds(&self) -> Option<Aabb> {
+        self.mesh.as_ref()?.bounds().map(|b| b.transformed(&self.model_matrix))
+    }
 }
 
 impl SceneWorld {
@@ -24,6 +55,7 @@ impl SceneWorld {
         Self {
             entities: HashMap::new(),
             next_id: 1,
+            rebase_origin: DVec3::ZERO,
         }
     }
 
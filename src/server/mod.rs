@@ -2,35 +2,115 @@ pub mod models;
 
 use std::sync::Arc;
 
-use axum::{extract::State, response::IntoResponse, routing::{delete, get, post}, Json, Router};
+use axum::{body::Bytes, extract::{DefaultBodyLimit, FromRequest, Multipart, Query, Request, State}, response::IntoResponse, routing::{delete, get, post}, Json, Router};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use glam::{DVec3, UVec2, Vec2, Vec3};
 use tokio::sync::Mutex;
 
-use crate::scene::{camera::CameraParams, context::SceneContext, error::SceneError, id::EntityId, transform::Transform};
+use crate::scene::{camera::CameraParams, constraint::ConstraintSettings, context::SceneContext, decimate::{DecimateTarget, DecimationReport}, error::SceneError, export::ExportOptions, id::EntityId, lod::LodPolicy, overlay::{OverlayId, OverlayItem}, packed::decode_mesh, section::Section, slice::{PlanCut, PlanSlice}, snap::SnapSettings, tracking::TrackingSettings, transform::Transform, view::{validate_viewport, View}, viewport::ViewportId, visual::HighlightStyle};
 
 use self::models::*;
 
 pub type SharedContext = Arc<Mutex<SceneContext>>;
 
+/// Largest request body of the mesh upload and file import routes, 256 MiB.
+/// Every other route keeps axum's 2 MB default.
+pub const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
 pub fn command_server(ctx: SharedContext) -> Router {
     Router::new()
-        .route("/api/entity", post(submit_entity))
+        .route("/api/entity", post(submit_entity).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)))
         .route("/api/entity/:id", delete(remove_entity))
+        .route("/api/entity/:id/decimate", post(decimate_entity))
+        .route("/api/entity/:id/vertices", post(update_vertices))
+        .route("/api/entity/:id/indices", post(update_indices))
+        .route("/api/import/obj", post(import_obj).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)))
+        .route("/api/import/stl", post(import_stl).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)))
         .route("/api/select", post(select))
         .route("/api/highlight", post(highlight))
+        .route("/api/highlight_style", get(get_highlight_style).post(set_highlight_style))
+        .route("/api/lod_policy", get(get_lod_policy).post(set_lod_policy))
         .route("/api/visibility", post(visibility))
         .route("/api/transform", post(transform))
+        .route("/api/origin", post(origin))
+        .route("/api/rebase_origin", get(get_rebase_origin).post(set_rebase_origin))
+        .route("/api/appearance", post(appearance))
         .route("/api/render", post(render))
+        .route("/api/camera/zoom_extents", post(zoom_extents))
+        .route("/api/camera/zoom_window", post(zoom_window))
+        .route("/api/viewports", get(list_viewports).post(create_viewport))
+        .route("/api/viewports/:id", delete(remove_viewport))
+        .route("/api/viewports/:id/view", post(set_viewport_view))
+        .route("/api/viewports/:id/display_mode", post(set_display_mode))
+        .route("/api/viewports/:id/visibility", post(set_viewport_visibility))
+        .route("/api/viewports/:id/plot_scale", post(set_plot_scale))
+        .route("/api/linetypes", get(list_linetypes).post(load_linetypes))
+        .route("/api/section", get(get_section).post(set_section))
+        .route("/api/overlay", post(add_overlay).delete(clear_overlay))
+        .route("/api/overlay/:id", post(update_overlay).delete(remove_overlay))
+        .route("/api/pick", post(pick))
+        .route("/api/snap", post(snap))
+        .route("/api/snap_settings", get(get_snap_settings).post(set_snap_settings))
+        .route("/api/constraint_settings", get(get_constraint_settings).post(set_constraint_settings))
+        .route("/api/tracking_settings", get(get_tracking_settings).post(set_tracking_settings))
+        .route("/api/tracking", get(tracking_points).delete(clear_tracking))
+        .route("/api/tracking/acquire", post(acquire_tracking_point))
+        .route("/api/tracking/release", post(release_tracking_point))
+        .route("/api/slice", post(slice_plan))
+        .route("/api/view", get(get_view).post(set_view))
+        .route("/api/views", get(list_views))
+        .route("/api/views/save", post(save_view))
+        .route("/api/views/restore", post(restore_view))
+        .route("/api/views/:name", delete(delete_view))
         .route("/api/state/:id", get(get_state))
         .route("/api/screenshot", get(screenshot))
+        .route("/api/export", get(export))
         .with_state(ctx)
 }
 
-async fn submit_entity(State(ctx): State<SharedContext>, Json(req): Json<SubmitEntityRequest>) -> Result<Json<SubmitEntityResponse>, ApiError> {
+/// Takes JSON, or a packed mesh as the raw body or as the `mesh` part of a
+/// multipart form, whose optional `entity_id` part overrides the query.
+async fn submit_entity(State(ctx): State<SharedContext>, Query(query): Query<SubmitEntityQuery>, request: Request) -> Result<Json<SubmitEntityResponse>, ApiError> {
+    let content_type = request.headers().get(axum::http::header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    let binary = |mesh: &[u8], entity_id: Option<u64>| -> Result<SubmitEntityRequest, ApiError> {
+        let mesh = decode_mesh(mesh).map_err(ApiError::from)?;
+        Ok(SubmitEntityRequest { shape: ShapePayload { mesh }, tess_params: None, entity_id, lods: Vec::new() })
+    };
+    let req = if content_type.starts_with("application/octet-stream") {
+        let body = Bytes::from_request(request, &()).await.map_err(|rejection| ApiError::rejected(rejection.status(), rejection.body_text()))?;
+        binary(&body, query.entity_id)?
+    } else if content_type.starts_with("multipart/form-data") {
+        let mut form = Multipart::from_request(request, &()).await.map_err(|rejection| ApiError::rejected(rejection.status(), rejection.body_text()))?;
+        let (mut mesh, mut entity_id) = (None, query.entity_id);
+        while let Some(field) = form.next_field().await.map_err(|err| ApiError::rejected(err.status(), err.body_text()))? {
+            match field.name() {
+                Some("mesh") => mesh = Some(field.bytes().await.map_err(|err| ApiError::rejected(err.status(), err.body_text()))?),
+                Some("entity_id") => {
+                    let text = field.text().await.map_err(|err| ApiError::rejected(err.status(), err.body_text()))?;
+                    let id = text.trim().parse().map_err(|_| ApiError::rejected(axum::http::StatusCode::BAD_REQUEST, format!("invalid entity_id `{text}`")))?;
+                    entity_id = Some(id);
+                }
+                _ => {}
+            }
+        }
+        let mesh = mesh.ok_or_else(|| ApiError::rejected(axum::http::StatusCode::BAD_REQUEST, "multipart form has no `mesh` part".to_owned()))?;
+        binary(&mesh, entity_id)?
+    } else {
+        let Json(req) = Json::<SubmitEntityRequest>::from_request(request, &()).await.map_err(|rejection| ApiError::rejected(rejection.status(), rejection.body_text()))?;
+        req
+    };
+    // Submission is all or nothing, so check the levels before touching the entity.
+    for level in &req.lods {
+        level.validate().map_err(ApiError::from)?;
+    }
     let mut ctx = ctx.lock().await;
     let shape = req.shape.into_shape();
     let id = ctx
         .submit_shape(req.entity_id.map(EntityId), &shape, &req.tess_params.unwrap_or_default())
         .map_err(ApiError::from)?;
+    for level in req.lods {
+        ctx.add_lod_mesh(id, level.mesh, level.error).map_err(ApiError::from)?;
+    }
     Ok(Json(SubmitEntityResponse { entity_id: id.0 }))
 }
 
@@ -40,6 +120,57 @@ async fn remove_entity(State(ctx): State<SharedContext>, axum::extract::Path(id)
     Ok(Json(EmptyResponse {}))
 }
 
+/// Adds a simplified level of detail and reports the triangles and error it reached.
+async fn decimate_entity(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    Json(req): Json<DecimateTarget>,
+) -> Result<Json<DecimationReport>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    Ok(Json(ctx.add_decimated_lod(EntityId(id), req).map_err(ApiError::from)?))
+}
+
+async fn update_vertices(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    Json(req): Json<UpdateVerticesRequest>,
+) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let range = req.start..req.start.saturating_add(req.vertices.len());
+    ctx.update_vertices(EntityId(id), range, &req.vertices).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn update_indices(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    Json(req): Json<UpdateIndicesRequest>,
+) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let range = req.start..req.start.saturating_add(req.indices.len());
+    ctx.update_indices(EntityId(id), range, &req.indices).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn import_obj(State(ctx): State<SharedContext>, body: axum::body::Bytes) -> Result<Json<ImportResponse>, ApiError> {
+    let source = std::str::from_utf8(&body)
+        .map_err(|err| SceneError::ParseBinary { offset: err.valid_up_to(), message: "OBJ source is not UTF-8".to_owned() })
+        .map_err(ApiError::from)?;
+    let mut ctx = ctx.lock().await;
+    let entities = ctx.import_obj(source).map_err(ApiError::from)?;
+    Ok(Json(import_response(entities)))
+}
+
+async fn import_stl(State(ctx): State<SharedContext>, body: axum::body::Bytes) -> Result<Json<ImportResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let entities = ctx.import_stl(&body).map_err(ApiError::from)?;
+    Ok(Json(import_response(entities)))
+}
+
+fn import_response(entities: Vec<(String, EntityId)>) -> ImportResponse {
+    ImportResponse { entities: entities.into_iter().map(|(name, id)| ImportedEntity { name, entity_id: id.0 }).collect() }
+}
+
 async fn select(State(ctx): State<SharedContext>, Json(req): Json<FlagRequest>) -> Result<Json<EmptyResponse>, ApiError> {
     let mut ctx = ctx.lock().await;
     ctx.set_selected(EntityId(req.entity_id), req.value).map_err(ApiError::from)?;
@@ -52,6 +183,28 @@ async fn highlight(State(ctx): State<SharedContext>, Json(req): Json<FlagRequest
     Ok(Json(EmptyResponse {}))
 }
 
+async fn get_highlight_style(State(ctx): State<SharedContext>) -> Json<HighlightStyle> {
+    let ctx = ctx.lock().await;
+    Json(ctx.highlight_style())
+}
+
+async fn set_highlight_style(State(ctx): State<SharedContext>, Json(req): Json<HighlightStyle>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_highlight_style(req).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn get_lod_policy(State(ctx): State<SharedContext>) -> Json<LodPolicy> {
+    let ctx = ctx.lock().await;
+    Json(ctx.lod_policy())
+}
+
+async fn set_lod_policy(State(ctx): State<SharedContext>, Json(req): Json<LodPolicy>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_lod_policy(req).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
 async fn visibility(State(ctx): State<SharedContext>, Json(req): Json<FlagRequest>) -> Result<Json<EmptyResponse>, ApiError> {
     let mut ctx = ctx.lock().await;
     ctx.set_visibility(EntityId(req.entity_id), req.value).map_err(ApiError::from)?;
@@ -65,11 +218,326 @@ async fn transform(State(ctx): State<SharedContext>, Json(req): Json<TransformRe
     Ok(Json(EmptyResponse {}))
 }
 
+async fn origin(State(ctx): State<SharedContext>, Json(req): Json<OriginRequest>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_origin(EntityId(req.entity_id), DVec3::from_array(req.origin)).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn get_rebase_origin(State(ctx): State<SharedContext>) -> Json<RebaseOriginPayload> {
+    let ctx = ctx.lock().await;
+    Json(RebaseOriginPayload { origin: ctx.rebase_origin().to_array() })
+}
+
+async fn set_rebase_origin(State(ctx): State<SharedContext>, Json(req): Json<RebaseOriginPayload>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_rebase_origin(DVec3::from_array(req.origin)).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn appearance(State(ctx): State<SharedContext>, Json(req): Json<AppearanceRequest>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_appearance(EntityId(req.entity_id), req.appearance).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
 async fn render(State(ctx): State<SharedContext>, Json(req): Json<RenderRequest>) -> Result<Json<RenderResponse>, ApiError> {
+    if let Some(camera) = &req.camera {
+        validate_viewport(camera.viewport.into()).map_err(ApiError::from)?;
+    }
+    let mut ctx = ctx.lock().await;
+    let viewport = ViewportId(req.viewport.unwrap_or(ViewportId::MAIN.0));
+    match req.camera {
+        Some(camera) => ctx.render_viewport_with(viewport, &CameraParams::from(camera)),
+        None => ctx.render_viewport(viewport),
+    }
+    .map_err(ApiError::from)?;
+    let frame = ctx.last_frame().ok_or(ApiError::from(SceneError::InvalidState("render did not record a frame")))?;
+    Ok(Json(RenderResponse {
+        frame_id: frame.frame_id,
+        sync: frame.sync,
+        stats: frame.stats,
+    }))
+}
+
+async fn list_viewports(State(ctx): State<SharedContext>) -> Result<Json<ViewportListResponse>, ApiError> {
+    let ctx = ctx.lock().await;
+    let viewports = ctx
+        .viewport_ids()
+        .into_iter()
+        .map(|id| {
+            Ok(ViewportInfo {
+                viewport_id: id.0,
+                view: ViewPayload::from(ctx.viewport_view(id)?),
+                display_mode: ctx.display_mode(id)?,
+            })
+        })
+        .collect::<Result<_, SceneError>>()
+        .map_err(ApiError::from)?;
+    Ok(Json(ViewportListResponse { viewports }))
+}
+
+async fn create_viewport(State(ctx): State<SharedContext>, Json(req): Json<ViewPayload>) -> Result<Json<CreateViewportResponse>, ApiError> {
+    let view = View::from(req);
+    view.validate().map_err(ApiError::from)?;
+    let mut ctx = ctx.lock().await;
+    let id = ctx.create_viewport(view);
+    Ok(Json(CreateViewportResponse { viewport_id: id.0 }))
+}
+
+async fn remove_viewport(State(ctx): State<SharedContext>, axum::extract::Path(id): axum::extract::Path<u32>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.remove_viewport(ViewportId(id)).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn set_viewport_view(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u32>,
+    Json(req): Json<ViewPayload>,
+) -> Result<Json<EmptyResponse>, ApiError> {
+    let view = View::from(req);
+    view.validate().map_err(ApiError::from)?;
+    let mut ctx = ctx.lock().await;
+    ctx.set_viewport_view(ViewportId(id), view).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn set_display_mode(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u32>,
+    Json(req): Json<DisplayModeRequest>,
+) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_display_mode(ViewportId(id), req.display_mode).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn set_plot_scale(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u32>,
+    Json(req): Json<PlotScaleRequest>,
+) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_plot_scale(ViewportId(id), req.pixels_per_mm).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn list_linetypes(State(ctx): State<SharedContext>) -> Json<LinetypeListResponse> {
+    let ctx = ctx.lock().await;
+    Json(LinetypeListResponse { linetypes: ctx.linetype_names() })
+}
+
+/// Loads `.lin` definitions and returns the names they defined.
+async fn load_linetypes(State(ctx): State<SharedContext>, Json(req): Json<LoadLinetypesRequest>) -> Result<Json<LinetypeListResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let linetypes = ctx.load_linetypes(&req.source).map_err(ApiError::from)?;
+    Ok(Json(LinetypeListResponse { linetypes }))
+}
+
+async fn get_section(State(ctx): State<SharedContext>) -> Json<Section> {
+    let ctx = ctx.lock().await;
+    Json(ctx.section().clone())
+}
+
+async fn set_section(State(ctx): State<SharedContext>, Json(req): Json<Section>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_section(req).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn pick(State(ctx): State<SharedContext>, Json(req): Json<PickRequest>) -> Result<Json<PickResponse>, ApiError> {
+    let ctx = ctx.lock().await;
+    let viewport = ViewportId(req.viewport.unwrap_or(ViewportId::MAIN.0));
+    let cursor = Vec2::from(req.screen_pos);
+    // Overlay items are drawn on top, so they win whenever they are asked for and hit.
+    if req.include_overlay {
+        if let Some(hit) = ctx.pick_overlay(viewport, cursor).map_err(ApiError::from)? {
+            return Ok(Json(PickResponse {
+                entity_id: None,
+                point: Some(hit.point.to_array()),
+                world_point: Some(ctx.to_world(hit.point).to_array()),
+                overlay_id: Some(hit.overlay_id.0),
+            }));
+        }
+    }
+    let hit = ctx.pick(viewport, cursor).map_err(ApiError::from)?;
+    Ok(Json(PickResponse {
+        entity_id: hit.map(|hit| hit.entity_id.0),
+        point: hit.map(|hit| hit.point.to_array()),
+        world_point: hit.map(|hit| ctx.to_world(hit.point).to_array()),
+        overlay_id: None,
+    }))
+}
+
+/// Snapped point input under a pixel, as a command would receive it.
+async fn snap(State(ctx): State<SharedContext>, Json(req): Json<SnapRequest>) -> Result<Json<SnapResponse>, ApiError> {
+    let ctx = ctx.lock().await;
+    let viewport = ViewportId(req.viewport.unwrap_or(ViewportId::MAIN.0));
+    let guide = req.guide.map(|ends| ends.map(Vec3::from));
+    let (result, guides) = ctx.tracked_point_input(viewport, Vec2::from(req.screen_pos), req.from.map(Vec3::from), guide).map_err(ApiError::from)?;
+    Ok(Json(SnapResponse { result, guides }))
+}
+
+async fn get_snap_settings(State(ctx): State<SharedContext>) -> Json<SnapSettings> {
+    let ctx = ctx.lock().await;
+    Json(ctx.snap_settings())
+}
+
+async fn set_snap_settings(State(ctx): State<SharedContext>, Json(req): Json<SnapSettings>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_snap_settings(req).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn get_constraint_settings(State(ctx): State<SharedContext>) -> Json<ConstraintSettings> {
+    let ctx = ctx.lock().await;
+    Json(ctx.constraint_settings())
+}
+
+async fn set_constraint_settings(State(ctx): State<SharedContext>, Json(req): Json<ConstraintSettings>) -> Json<EmptyResponse> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_constraint_settings(req);
+    Json(EmptyResponse {})
+}
+
+async fn get_tracking_settings(State(ctx): State<SharedContext>) -> Json<TrackingSettings> {
+    let ctx = ctx.lock().await;
+    Json(ctx.tracking_settings())
+}
+
+async fn set_tracking_settings(State(ctx): State<SharedContext>, Json(req): Json<TrackingSettings>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_tracking_settings(req).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+fn tracking_response(ctx: &SceneContext) -> Json<TrackingPointsResponse> {
+    Json(TrackingPointsResponse { points: ctx.tracking_points().iter().map(|p| p.to_array()).collect() })
+}
+
+async fn tracking_points(State(ctx): State<SharedContext>) -> Json<TrackingPointsResponse> {
+    tracking_response(&*ctx.lock().await)
+}
+
+async fn acquire_tracking_point(State(ctx): State<SharedContext>, Json(req): Json<TrackingPointRequest>) -> Result<Json<TrackingPointsResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.acquire_tracking_point(Vec3::from(req.point)).map_err(ApiError::from)?;
+    Ok(tracking_response(&ctx))
+}
+
+async fn release_tracking_point(State(ctx): State<SharedContext>, Json(req): Json<TrackingPointRequest>) -> Json<TrackingPointsResponse> {
+    let mut ctx = ctx.lock().await;
+    ctx.release_tracking_point(Vec3::from(req.point));
+    tracking_response(&ctx)
+}
+
+async fn clear_tracking(State(ctx): State<SharedContext>) -> Json<TrackingPointsResponse> {
+    let mut ctx = ctx.lock().await;
+    ctx.clear_tracking();
+    tracking_response(&ctx)
+}
+
+async fn add_overlay(State(ctx): State<SharedContext>, Json(item): Json<OverlayItem>) -> Result<Json<OverlayResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let id = ctx.add_overlay(item).map_err(ApiError::from)?;
+    Ok(Json(OverlayResponse { overlay_id: id.0 }))
+}
+
+async fn update_overlay(State(ctx): State<SharedContext>, axum::extract::Path(id): axum::extract::Path<u32>, Json(item): Json<OverlayItem>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.update_overlay(OverlayId(id), item).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn remove_overlay(State(ctx): State<SharedContext>, axum::extract::Path(id): axum::extract::Path<u32>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.remove_overlay(OverlayId(id)).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+/// Ends a command: every preview, band, marker and grip goes at once.
+async fn clear_overlay(State(ctx): State<SharedContext>) -> Json<EmptyResponse> {
+    let mut ctx = ctx.lock().await;
+    ctx.clear_overlay();
+    Json(EmptyResponse {})
+}
+
+/// Plan cut of the visible model: section loops and below-cut outlines per entity.
+async fn slice_plan(State(ctx): State<SharedContext>, Json(req): Json<PlanCut>) -> Result<Json<PlanSlice>, ApiError> {
+    let ctx = ctx.lock().await;
+    Ok(Json(ctx.slice_plan(&req).map_err(ApiError::from)?))
+}
+
+async fn set_viewport_visibility(
+    State(ctx): State<SharedContext>,
+    axum::extract::Path(id): axum::extract::Path<u32>,
+    Json(req): Json<ViewportVisibilityRequest>,
+) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.set_viewport_visibility(ViewportId(id), EntityId(req.entity_id), req.visible).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn get_view(State(ctx): State<SharedContext>) -> Json<ViewPayload> {
+    let ctx = ctx.lock().await;
+    Json(ViewPayload::from(ctx.current_view()))
+}
+
+async fn set_view(State(ctx): State<SharedContext>, Json(req): Json<ViewPayload>) -> Result<Json<EmptyResponse>, ApiError> {
+    let view = View::from(req);
+    view.validate().map_err(ApiError::from)?;
     let mut ctx = ctx.lock().await;
-    let camera = CameraParams { view: req.camera.view.into(), proj: req.camera.proj.into(), viewport: req.camera.viewport.into() };
-    ctx.render(&camera).map_err(ApiError::from)?;
-    Ok(Json(RenderResponse { frame_id: 0 }))
+    ctx.set_current_view(view);
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn list_views(State(ctx): State<SharedContext>) -> Json<ViewListResponse> {
+    let ctx = ctx.lock().await;
+    Json(ViewListResponse { views: ctx.view_names() })
+}
+
+async fn save_view(State(ctx): State<SharedContext>, Json(req): Json<ViewNameRequest>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.save_view(&req.name).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn restore_view(State(ctx): State<SharedContext>, Json(req): Json<ViewNameRequest>) -> Result<Json<ViewPayload>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let viewport = ViewportId(req.viewport.unwrap_or(ViewportId::MAIN.0));
+    let view = ctx.restore_viewport_view(viewport, &req.name).map_err(ApiError::from)?;
+    Ok(Json(ViewPayload::from(view)))
+}
+
+async fn delete_view(State(ctx): State<SharedContext>, axum::extract::Path(name): axum::extract::Path<String>) -> Result<Json<EmptyResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    ctx.delete_view(&name).map_err(ApiError::from)?;
+    Ok(Json(EmptyResponse {}))
+}
+
+async fn zoom_extents(State(ctx): State<SharedContext>, Json(req): Json<ZoomExtentsRequest>) -> Result<Json<CameraResponse>, ApiError> {
+    validate_viewport(req.viewport.into()).map_err(ApiError::from)?;
+    req.camera.validate().map_err(ApiError::from)?;
+    let ctx = ctx.lock().await;
+    let bounds = if req.selection_only {
+        ctx.selection_bounds().ok_or(SceneError::ResourceMissing("no selected entities to fit"))?
+    } else {
+        ctx.scene_bounds().ok_or(SceneError::ResourceMissing("no visible entities to fit"))?
+    };
+    let viewport = UVec2::from(req.viewport);
+    let mut camera = req.camera;
+    camera.zoom_extents(&bounds, viewport);
+    Ok(Json(CameraResponse { camera, params: camera.params(viewport).into() }))
+}
+
+async fn zoom_window(Json(req): Json<ZoomWindowRequest>) -> Result<Json<CameraResponse>, ApiError> {
+    validate_viewport(req.viewport.into()).map_err(ApiError::from)?;
+    req.camera.validate().map_err(ApiError::from)?;
+    let viewport = UVec2::from(req.viewport);
+    let mut camera = req.camera;
+    camera.zoom_window(req.min.into(), req.max.into(), viewport);
+    Ok(Json(CameraResponse { camera, params: camera.params(viewport).into() }))
 }
 
 async fn get_state(State(ctx): State<SharedContext>, axum::extract::Path(id): axum::extract::Path<u64>) -> Result<Json<StateResponse>, ApiError> {
@@ -79,13 +547,37 @@ async fn get_state(State(ctx): State<SharedContext>, axum::extract::Path(id): ax
         visual: VisualPayload::from(state.visual),
         transform: MatrixPayload::from(state.transform.matrix),
         has_mesh: state.has_mesh,
+        mirrored: state.mirrored,
+        appearance: state.appearance,
+        origin: state.origin.to_array(),
     }))
 }
 
-async fn screenshot(State(_ctx): State<SharedContext>) -> Result<Json<ScreenshotResponse>, ApiError> {
-    // Placeholder: real implementation should capture a PNG.
+async fn export(State(ctx): State<SharedContext>, Query(query): Query<ExportQuery>) -> impl IntoResponse {
+    let ctx = ctx.lock().await;
+    let document = ctx.export_gltf(&ExportOptions { selected_only: query.selected_only });
+    let (content_type, file, body) = match query.format {
+        ExportFormat::Glb => ("model/gltf-binary", "scene.glb", document.to_glb()),
+        ExportFormat::Gltf => ("model/gltf+json", "scene.gltf", document.to_gltf_embedded().into_bytes()),
+    };
+    let disposition = format!("attachment; filename=\"{file}\"");
+    ([(axum::http::header::CONTENT_TYPE, content_type.to_owned()), (axum::http::header::CONTENT_DISPOSITION, disposition)], body)
+}
+
+async fn screenshot(State(ctx): State<SharedContext>, Query(query): Query<ScreenshotQuery>) -> Result<Json<ScreenshotResponse>, ApiError> {
+    let mut ctx = ctx.lock().await;
+    let (frame_id, png) = match query.frame_id {
+        Some(frame_id) => (frame_id, ctx.frame_screenshot(frame_id).map_err(ApiError::from)?),
+        None => {
+            let viewport = ViewportId(query.viewport.unwrap_or(ViewportId::MAIN.0));
+            let png = ctx.screenshot(viewport).map_err(ApiError::from)?;
+            let frame = ctx.latest_frame(viewport).ok_or(ApiError::from(SceneError::InvalidState("screenshot did not record a frame")))?;
+            (frame.frame_id, png)
+        }
+    };
     Ok(Json(ScreenshotResponse {
-        image_base64: String::new(),
+        frame_id,
+        image_base64: BASE64_STANDARD.encode(png),
     }))
 }
 
@@ -104,6 +596,11 @@ pub struct ErrorBody {
 }
 
 impl ApiError {
+    /// A request the extractors could not read.
+    fn rejected(status: axum::http::StatusCode, message: String) -> Self {
+        Self::new(status, "InvalidRequest", message)
+    }
+
     fn new(status: axum::http::StatusCode, code: &'static str, message: String) -> Self {
         Self {
             status,
@@ -119,8 +616,15 @@ impl From<SceneError> for ApiError {
     fn from(err: SceneError) -> Self {
         match err {
             SceneError::UnknownEntity(id) => ApiError::new(axum::http::StatusCode::NOT_FOUND, "UnknownEntity", format!("unknown entity {id}")),
+            SceneError::UnknownViewport(id) => ApiError::new(axum::http::StatusCode::NOT_FOUND, "UnknownViewport", format!("unknown viewport {id}")),
+            SceneError::UnknownFrame(id) => ApiError::new(axum::http::StatusCode::NOT_FOUND, "UnknownFrame", format!("unknown frame {id}")),
+            SceneError::UnknownOverlay(id) => ApiError::new(axum::http::StatusCode::NOT_FOUND, "UnknownOverlay", format!("unknown overlay item {id}")),
+            SceneError::UnknownView(name) => ApiError::new(axum::http::StatusCode::NOT_FOUND, "UnknownView", format!("unknown view {name}")),
             SceneError::ResourceMissing(msg) => ApiError::new(axum::http::StatusCode::BAD_REQUEST, "ResourceMissing", msg.to_string()),
             SceneError::InvalidState(msg) => ApiError::new(axum::http::StatusCode::BAD_REQUEST, "InvalidState", msg.to_string()),
+            SceneError::InvalidTransform(msg) => ApiError::new(axum::http::StatusCode::BAD_REQUEST, "InvalidTransform", msg.to_string()),
+            SceneError::Parse { line, message } => ApiError::new(axum::http::StatusCode::BAD_REQUEST, "ParseError", format!("line {line}: {message}")),
+            SceneError::ParseBinary { offset, message } => ApiError::new(axum::http::StatusCode::BAD_REQUEST, "ParseError", format!("byte {offset}: {message}")),
             SceneError::Io(e) => ApiError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Io", e.to_string()),
             SceneError::Backend(msg) => ApiError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Backend", msg.to_string()),
         }
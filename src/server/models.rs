@@ -1,6 +1,18 @@
 use glam::{Mat4, UVec2};
 
-use crate::scene::{mesh::MeshData, tessellation::TessParams, visual::VisualFlags};
+use crate::scene::{
+    appearance::Appearance,
+    camera::{Camera, CameraParams},
+    frame::{FrameStats, SyncReport},
+    lod::LodLevel,
+    mesh::{MeshData, Vertex},
+    snap::PointInputResult,
+    tessellation::TessParams,
+    tracking::GuideLine,
+    view::View,
+    viewport::DisplayMode,
+    visual::VisualFlags,
+};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct SubmitEntityRequest {
@@ -8,6 +20,17 @@ pub struct SubmitEntityRequest {
     #[serde(default)]
     pub tess_params: Option<TessParams>,
     pub entity_id: Option<u64>,
+    /// Coarser pre-tessellated meshes with their geometric error.
+    #[serde(default)]
+    pub lods: Vec<LodLevel>,
+}
+
+/// Options of a binary (`application/octet-stream` or multipart) submission,
+/// whose body is a packed mesh.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct SubmitEntityQuery {
+    #[serde(default)]
+    pub entity_id: Option<u64>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -15,6 +38,33 @@ pub struct SubmitEntityResponse {
     pub entity_id: u64,
 }
 
+/// Replaces `vertices.len()` vertices starting at `start`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct UpdateVerticesRequest {
+    pub start: usize,
+    pub vertices: Vec<Vertex>,
+}
+
+/// Replaces `indices.len()` triangle indices starting at `start`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct UpdateIndicesRequest {
+    pub start: usize,
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ImportedEntity {
+    /// OBJ group or STL solid name.
+    pub name: String,
+    pub entity_id: u64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ImportResponse {
+    /// One entity per group or solid, in file order.
+    pub entities: Vec<ImportedEntity>,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct FlagRequest {
     pub entity_id: u64,
@@ -29,12 +79,103 @@ pub struct TransformRequest {
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct RenderRequest {
-    pub camera: CameraPayload,
+    /// Explicit camera; the viewport's own view is rendered when omitted.
+    #[serde(default)]
+    pub camera: Option<CameraPayload>,
+    /// Target viewport; the main viewport when omitted.
+    #[serde(default)]
+    pub viewport: Option<u32>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct RenderResponse {
     pub frame_id: u64,
+    pub sync: SyncReport,
+    pub stats: FrameStats,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ZoomExtentsRequest {
+    pub camera: Camera,
+    pub viewport: ViewportPayload,
+    /// Fit the selected entities instead of everything visible.
+    #[serde(default)]
+    pub selection_only: bool,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ZoomWindowRequest {
+    pub camera: Camera,
+    pub viewport: ViewportPayload,
+    /// Window corners in pixels, (0,0) at the top left.
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CameraResponse {
+    pub camera: Camera,
+    pub params: CameraPayload,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ViewPayload {
+    pub camera: Camera,
+    pub viewport: ViewportPayload,
+}
+
+impl From<View> for ViewPayload {
+    fn from(view: View) -> Self {
+        Self { camera: view.camera, viewport: view.viewport.into() }
+    }
+}
+
+impl From<ViewPayload> for View {
+    fn from(payload: ViewPayload) -> Self {
+        View::new(payload.camera, payload.viewport.into())
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ViewNameRequest {
+    pub name: String,
+    /// Viewport to restore into; the main viewport when omitted.
+    #[serde(default)]
+    pub viewport: Option<u32>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CreateViewportResponse {
+    pub viewport_id: u32,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ViewportInfo {
+    pub viewport_id: u32,
+    pub view: ViewPayload,
+    pub display_mode: DisplayMode,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ViewportListResponse {
+    pub viewports: Vec<ViewportInfo>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DisplayModeRequest {
+    pub display_mode: DisplayMode,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ViewportVisibilityRequest {
+    pub entity_id: u64,
+    /// `null` removes the override.
+    pub visible: Option<bool>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ViewListResponse {
+    pub views: Vec<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -42,10 +183,63 @@ pub struct StateResponse {
     pub visual: VisualPayload,
     pub transform: MatrixPayload,
     pub has_mesh: bool,
+    #[serde(default)]
+    pub mirrored: bool,
+    #[serde(default)]
+    pub appearance: Appearance,
+    /// World position of the entity's local origin.
+    #[serde(default)]
+    pub origin: [f64; 3],
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct OriginRequest {
+    pub entity_id: u64,
+    pub origin: [f64; 3],
+}
+
+/// World point scene coordinates are measured from.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RebaseOriginPayload {
+    pub origin: [f64; 3],
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AppearanceRequest {
+    pub entity_id: u64,
+    pub appearance: Appearance,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub selected_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Binary glTF.
+    #[default]
+    Glb,
+    /// glTF JSON with the buffer embedded as a data URI.
+    Gltf,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ScreenshotQuery {
+    #[serde(default)]
+    pub viewport: Option<u32>,
+    /// Recent frame to return instead of the viewport's current image.
+    #[serde(default)]
+    pub frame_id: Option<u64>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ScreenshotResponse {
+    pub frame_id: u64,
     pub image_base64: String,
 }
 
@@ -97,6 +291,22 @@ pub struct CameraPayload {
     pub viewport: ViewportPayload,
 }
 
+impl From<CameraParams> for CameraPayload {
+    fn from(params: CameraParams) -> Self {
+        Self {
+            view: params.view.into(),
+            proj: params.proj.into(),
+            viewport: params.viewport.into(),
+        }
+    }
+}
+
+impl From<CameraPayload> for CameraParams {
+    fn from(payload: CameraPayload) -> Self {
+        CameraParams::new(payload.view.into(), payload.proj.into(), payload.viewport.into())
+    }
+}
+
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct ViewportPayload(pub [u32; 2]);
 
@@ -128,3 +338,85 @@ impl From<VisualFlags> for VisualPayload {
         }
     }
 }
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LoadLinetypesRequest {
+    /// Contents of an AutoCAD `.lin` file.
+    pub source: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LinetypeListResponse {
+    pub linetypes: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PlotScaleRequest {
+    pub pixels_per_mm: f32,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PickRequest {
+    /// Viewport to pick in; the main viewport when omitted.
+    #[serde(default)]
+    pub viewport: Option<u32>,
+    /// Window pixel position, origin top-left.
+    pub screen_pos: [f32; 2],
+    /// Also hit overlay items, which take precedence over entities.
+    #[serde(default)]
+    pub include_overlay: bool,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PickResponse {
+    /// `None` when nothing (or only cut-away geometry) is under the cursor.
+    pub entity_id: Option<u64>,
+    /// Hit point in scene space.
+    pub point: Option<[f32; 3]>,
+    /// The same point in world coordinates.
+    #[serde(default)]
+    pub world_point: Option<[f64; 3]>,
+    /// Overlay item hit instead of an entity.
+    #[serde(default)]
+    pub overlay_id: Option<u32>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SnapRequest {
+    /// Viewport to snap in; the main viewport when omitted.
+    #[serde(default)]
+    pub viewport: Option<u32>,
+    /// Window pixel position, origin top-left.
+    pub screen_pos: [f32; 2],
+    /// Previous point of the command, in scene space.
+    #[serde(default)]
+    pub from: Option<[f32; 3]>,
+    /// Segment Parallel and Perpendicular constraints follow.
+    #[serde(default)]
+    pub guide: Option<[[f32; 3]; 2]>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SnapResponse {
+    #[serde(flatten)]
+    pub result: PointInputResult,
+    /// Tracking rays the point was placed on, for the client to draw.
+    #[serde(default)]
+    pub guides: Vec<GuideLine>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TrackingPointRequest {
+    pub point: [f32; 3],
+}
+
+/// Points acquired for object tracking, oldest first.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TrackingPointsResponse {
+    pub points: Vec<[f32; 3]>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct OverlayResponse {
+    pub overlay_id: u32,
+}
@@ -3,7 +3,11 @@ use std::sync::Arc;
 use axum::{body::Body, http::Request};
 use tower::ServiceExt;
 
-use crate::{scene::tessellation::TessParams, server::{command_server, models::ShapePayload}, scene::mesh::{MeshData, Vertex}};
+use crate::{
+    scene::{appearance::Appearance, camera::Camera, section::{ClipPlane, Section}, tessellation::TessParams},
+    server::{command_server, models::{CameraResponse, CreateViewportResponse, ImportResponse, LinetypeListResponse, OverlayResponse, PickResponse, RebaseOriginPayload, ScreenshotResponse, ShapePayload, StateResponse, TrackingPointsResponse, ViewListResponse, ViewportPayload, ZoomExtentsRequest}},
+    scene::mesh::{MeshData, Vertex},
+};
 
 fn sample_mesh() -> MeshData {
     MeshData {
@@ -13,10 +17,12 @@ fn sample_mesh() -> MeshData {
             Vertex { position: (0.0, 1.0, 0.0).into(), normal: (0.0, 0.0, 1.0).into(), uv: None },
         ],
         indices: vec![0, 1, 2],
+        ..MeshData::default()
     }
 }
 
 #[tokio::test]
+#[allow(clippy::bool_assert_comparison)]
 async fn http_submit_then_state() {
     let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
     let app = command_server(ctx.clone());
@@ -25,6 +31,7 @@ async fn http_submit_then_state() {
         shape: ShapePayload { mesh: sample_mesh() },
         tess_params: Some(TessParams::default()),
         entity_id: None,
+        lods: Vec::new(),
     }).unwrap();
 
     let response = app
@@ -49,3 +56,657 @@ async fn http_submit_then_state() {
     assert!(state.has_mesh);
     assert_eq!(state.visual.visible, false);
 }
+
+#[tokio::test]
+async fn http_zoom_extents_fits_visible_scene() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let req_body = serde_json::to_vec(&ZoomExtentsRequest {
+        camera: Camera::default(),
+        viewport: ViewportPayload([640, 480]),
+        selection_only: false,
+    })
+    .unwrap();
+    let zoom = |body: Vec<u8>| Request::post("/api/camera/zoom_extents").header("content-type", "application/json").body(Body::from(body)).unwrap();
+
+    let empty = app.clone().oneshot(zoom(req_body.clone())).await.unwrap();
+    assert_eq!(empty.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    {
+        let mut ctx = ctx.lock().await;
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+    }
+    let response = app.clone().oneshot(zoom(req_body)).await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let fitted: CameraResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(fitted.camera.target, glam::Vec3::new(0.5, 0.5, 0.0));
+
+    // Cameras that would produce NaN matrices are refused.
+    let degenerate = Camera { near: 0.0, ..Camera::default() };
+    let body = serde_json::to_vec(&ZoomExtentsRequest { camera: degenerate, viewport: ViewportPayload([640, 480]), selection_only: false }).unwrap();
+    assert_eq!(app.clone().oneshot(zoom(body)).await.unwrap().status(), axum::http::StatusCode::BAD_REQUEST);
+    let window = serde_json::json!({ "camera": Camera { target: Camera::default().eye, ..Camera::default() }, "viewport": [640, 480], "min": [0.0, 0.0], "max": [10.0, 10.0] });
+    let request = Request::post("/api/camera/zoom_window").header("content-type", "application/json").body(Body::from(window.to_string())).unwrap();
+    assert_eq!(app.clone().oneshot(request).await.unwrap().status(), axum::http::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn http_render_without_camera_uses_saved_view() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let post = |uri: &str, body: serde_json::Value| Request::post(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+
+    let saved = app.clone().oneshot(post("/api/views/save", serde_json::json!({ "name": "Entrance" }))).await.unwrap();
+    assert!(saved.status().is_success());
+    let restored = app.clone().oneshot(post("/api/views/restore", serde_json::json!({ "name": "Entrance" }))).await.unwrap();
+    assert!(restored.status().is_success());
+    let unknown = app.clone().oneshot(post("/api/views/restore", serde_json::json!({ "name": "Nowhere" }))).await.unwrap();
+    assert_eq!(unknown.status(), axum::http::StatusCode::NOT_FOUND);
+
+    let rendered = app.clone().oneshot(post("/api/render", serde_json::json!({}))).await.unwrap();
+    assert!(rendered.status().is_success());
+    let bytes = axum::body::to_bytes(rendered.into_body(), usize::MAX).await.unwrap();
+    let stats = &serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()["stats"];
+    for field in ["entities_considered", "entities_culled", "entities_drawn", "triangles", "lines", "sync_micros", "raster_micros"] {
+        assert!(stats[field].is_u64(), "{field} missing from {stats}");
+    }
+
+    let listed = app.clone().oneshot(Request::get("/api/views").body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(listed.into_body(), usize::MAX).await.unwrap();
+    let views: ViewListResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(views.views.last().map(String::as_str), Some("Entrance"));
+}
+
+#[tokio::test]
+async fn http_screenshot_per_viewport_returns_png() {
+    use base64::{prelude::BASE64_STANDARD, Engine};
+
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let view = serde_json::json!({ "camera": Camera::default(), "viewport": [32, 24] });
+    let created = app
+        .clone()
+        .oneshot(Request::post("/api/viewports").header("content-type", "application/json").body(Body::from(view.to_string())).unwrap())
+        .await
+        .unwrap();
+    assert!(created.status().is_success());
+    let bytes = axum::body::to_bytes(created.into_body(), usize::MAX).await.unwrap();
+    let created: CreateViewportResponse = serde_json::from_slice(&bytes).unwrap();
+
+    let shot = app
+        .clone()
+        .oneshot(Request::get(format!("/api/screenshot?viewport={}", created.viewport_id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(shot.status().is_success());
+    let bytes = axum::body::to_bytes(shot.into_body(), usize::MAX).await.unwrap();
+    let shot: ScreenshotResponse = serde_json::from_slice(&bytes).unwrap();
+    let png = BASE64_STANDARD.decode(shot.image_base64).unwrap();
+    let info = png::Decoder::new(png.as_slice()).read_info().unwrap().info().clone();
+    assert_eq!((info.width, info.height), (32, 24));
+
+    let again = app
+        .clone()
+        .oneshot(Request::get(format!("/api/screenshot?frame_id={}", shot.frame_id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(again.into_body(), usize::MAX).await.unwrap();
+    let again: ScreenshotResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(again.frame_id, shot.frame_id);
+    assert_eq!(BASE64_STANDARD.decode(again.image_base64).unwrap(), png);
+    let unknown = app.clone().oneshot(Request::get("/api/screenshot?frame_id=999").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(unknown.status(), axum::http::StatusCode::NOT_FOUND);
+
+    let missing = app.clone().oneshot(Request::get("/api/screenshot?viewport=99").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(missing.status(), axum::http::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn http_oversized_viewports_are_rejected() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let post = |uri: &str, body: serde_json::Value| Request::post(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+    let view = serde_json::json!({ "camera": Camera::default(), "viewport": [70000, 70000] });
+    for uri in ["/api/viewports", "/api/view", "/api/viewports/0/view"] {
+        let response = app.clone().oneshot(post(uri, view.clone())).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST, "{uri}");
+    }
+    let params = Camera::default().params(glam::UVec2::splat(70000));
+    let render = serde_json::json!({ "camera": crate::server::models::CameraPayload::from(params) });
+    let response = app.clone().oneshot(post("/api/render", render)).await.unwrap();
+    assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    assert_eq!(ctx.lock().await.current_view().viewport, glam::UVec2::new(800, 600));
+}
+
+#[tokio::test]
+async fn http_degenerate_views_are_rejected() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let post = |uri: &str, body: serde_json::Value| Request::post(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+    let camera = Camera::default();
+    for broken in [Camera { target: camera.eye, ..camera }, Camera { far: camera.near * 0.5, ..camera }, camera.orthographic(0.0)] {
+        let view = serde_json::json!({ "camera": broken, "viewport": [640, 480] });
+        for uri in ["/api/viewports", "/api/view", "/api/viewports/0/view"] {
+            let response = app.clone().oneshot(post(uri, view.clone())).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST, "{uri} {broken:?}");
+        }
+    }
+    // Nothing was stored, so saving and rendering the current view stays finite.
+    assert_eq!(ctx.lock().await.current_view().camera, camera);
+    assert!(app.clone().oneshot(post("/api/views/save", serde_json::json!({ "name": "Kept" }))).await.unwrap().status().is_success());
+}
+
+#[tokio::test]
+async fn http_appearance_updates_state_without_geometry() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let id = {
+        let mut ctx = ctx.lock().await;
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+        ctx.sync_gpu().unwrap();
+        id
+    };
+
+    let appearance = Appearance { color: [200, 40, 40], opacity: 0.5, linetype: "Dashed".to_owned(), lineweight: 0.5 };
+    let body = serde_json::json!({ "entity_id": id.0, "appearance": appearance });
+    let response = app
+        .clone()
+        .oneshot(Request::post("/api/appearance").header("content-type", "application/json").body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(ctx.lock().await.dirty_flags(), crate::scene::visual::DirtyFlags::VISUAL);
+
+    let state = app.clone().oneshot(Request::get(format!("/api/state/{}", id.0)).body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(state.into_body(), usize::MAX).await.unwrap();
+    let state: crate::server::models::StateResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(state.appearance, appearance);
+
+    let invalid = serde_json::json!({ "entity_id": id.0, "appearance": Appearance { opacity: 1.5, ..Appearance::default() } });
+    let response = app
+        .clone()
+        .oneshot(Request::post("/api/appearance").header("content-type", "application/json").body(Body::from(invalid.to_string())).unwrap())
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+}
+
+#[tokio::test]
+async fn http_load_linetypes_reports_parse_errors() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let load = |source: &str| {
+        let body = serde_json::json!({ "source": source }).to_string();
+        app.clone().oneshot(Request::post("/api/linetypes").header("content-type", "application/json").body(Body::from(body)).unwrap())
+    };
+
+    let response = load("*RAIL,Rail\nA,.5,-.1,0,-.1").await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let loaded: LinetypeListResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(loaded.linetypes, ["RAIL"]);
+
+    let response = load("*BROKEN\nA,.5,-x").await.unwrap();
+    assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(error.to_string().contains("line 2"));
+}
+
+#[tokio::test]
+async fn http_section_cuts_away_picked_geometry() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let id = {
+        let mut ctx = ctx.lock().await;
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+        let camera = Camera::look_at((0.25, 0.25, 5.0).into(), (0.25, 0.25, 0.0).into(), crate::scene::camera::UpAxis::Z).orthographic(1.0);
+        ctx.set_current_view(crate::scene::view::View::new(camera, glam::UVec2::splat(16)));
+        id
+    };
+    let pick = || async {
+        let body = serde_json::json!({ "screen_pos": [6.5, 6.5] }).to_string();
+        let response = app.clone().oneshot(Request::post("/api/pick").header("content-type", "application/json").body(Body::from(body)).unwrap()).await.unwrap();
+        assert!(response.status().is_success());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<PickResponse>(&bytes).unwrap()
+    };
+    assert_eq!(pick().await.entity_id, Some(id.0));
+
+    let section = Section { planes: vec![ClipPlane::horizontal(-0.5)], ..Section::default() };
+    let body = serde_json::to_string(&section).unwrap();
+    let response = app.clone().oneshot(Request::post("/api/section").header("content-type", "application/json").body(Body::from(body)).unwrap()).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(pick().await.entity_id, None);
+
+    let response = app.clone().oneshot(Request::get("/api/section").body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<Section>(&bytes).unwrap(), section);
+
+    let too_many = Section { planes: vec![ClipPlane::horizontal(0.0); 7], ..Section::default() };
+    let body = serde_json::to_string(&too_many).unwrap();
+    let response = app.clone().oneshot(Request::post("/api/section").header("content-type", "application/json").body(Body::from(body)).unwrap()).await.unwrap();
+    assert!(response.status().is_client_error());
+}
+
+#[tokio::test]
+async fn http_slice_returns_plan_per_entity() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let id = {
+        let mut ctx = ctx.lock().await;
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+        id
+    };
+    let slice = |body: serde_json::Value| app.clone().oneshot(Request::post("/api/slice").header("content-type", "application/json").body(Body::from(body.to_string())).unwrap());
+
+    let response = slice(serde_json::json!({ "elevation": 0.0, "cut_height": 1.2 })).await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let plan: crate::scene::slice::PlanSlice = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(plan.entities.len(), 1);
+    assert_eq!(plan.entities[0].entity_id, id);
+    // The flat triangle lies below the cut and is drawn in projection.
+    assert_eq!(plan.entities[0].below.len(), 3);
+
+    let response = slice(serde_json::json!({ "elevation": 0.0, "cut_height": 1.2, "view_depth": -2.0 })).await.unwrap();
+    assert!(response.status().is_client_error());
+}
+
+#[tokio::test]
+async fn http_lods_submit_decimate_and_policy() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let submit = |lods: serde_json::Value| {
+        let body = serde_json::json!({ "shape": { "mesh": sample_mesh() }, "lods": lods });
+        app.clone().oneshot(Request::post("/api/entity").header("content-type", "application/json").body(Body::from(body.to_string())).unwrap())
+    };
+    let response = submit(serde_json::json!([{ "mesh": sample_mesh(), "error": 0.05 }])).await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let id: crate::server::models::SubmitEntityResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(ctx.lock().await.lod_errors(crate::scene::id::EntityId(id.entity_id)).unwrap(), [0.05]);
+
+    let response = submit(serde_json::json!([{ "mesh": sample_mesh(), "error": -1.0 }])).await.unwrap();
+    assert!(response.status().is_client_error());
+    // The rejected level kept the entity from being created at all.
+    assert!(ctx.lock().await.lod_errors(crate::scene::id::EntityId(id.entity_id + 1)).is_err());
+    // An update with a bad later level leaves the entity and its levels as they were.
+    let lods = serde_json::json!([{ "mesh": sample_mesh(), "error": 0.1 }, { "mesh": sample_mesh(), "error": 0.0 }]);
+    let body = serde_json::json!({ "entity_id": id.entity_id, "shape": { "mesh": sample_mesh() }, "lods": lods });
+    let response = app.clone().oneshot(Request::post("/api/entity").header("content-type", "application/json").body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    assert!(response.status().is_client_error());
+    assert_eq!(ctx.lock().await.lod_errors(crate::scene::id::EntityId(id.entity_id)).unwrap(), [0.05]);
+
+    let decimate = |target: serde_json::Value| {
+        app.clone().oneshot(Request::post(format!("/api/entity/{}/decimate", id.entity_id)).header("content-type", "application/json").body(Body::from(target.to_string())).unwrap())
+    };
+    let response = decimate(serde_json::json!({ "MaxError": 0.001 })).await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let report: crate::scene::decimate::DecimationReport = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!((report.triangles_before, report.triangles, report.error), (1, 1, 0.0));
+    // Nothing could be collapsed, so no level was added.
+    assert_eq!(ctx.lock().await.lod_errors(crate::scene::id::EntityId(id.entity_id)).unwrap(), [0.05]);
+    assert!(decimate(serde_json::json!({ "MaxError": -1.0 })).await.unwrap().status().is_client_error());
+
+    let policy = serde_json::json!({ "pixel_threshold": 2.0, "hysteresis": 0.1 });
+    let response = app.clone().oneshot(Request::post("/api/lod_policy").header("content-type", "application/json").body(Body::from(policy.to_string())).unwrap()).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(ctx.lock().await.lod_policy().pixel_threshold, 2.0);
+}
+
+#[tokio::test]
+async fn http_origins_pick_in_world_coordinates() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let id = {
+        let mut ctx = ctx.lock().await;
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+        id
+    };
+    let site = [500_000.123, 6_000_000.456, 12.0];
+    let post = |uri: &'static str, body: serde_json::Value| {
+        let app = app.clone();
+        async move { app.oneshot(Request::post(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap()).await.unwrap() }
+    };
+    assert!(post("/api/origin", serde_json::json!({ "entity_id": id.0, "origin": site })).await.status().is_success());
+    assert!(post("/api/rebase_origin", serde_json::json!({ "origin": site })).await.status().is_success());
+    assert!(post("/api/origin", serde_json::json!({ "entity_id": 99, "origin": site })).await.status().is_client_error());
+
+    let response = app.clone().oneshot(Request::get("/api/rebase_origin").body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<RebaseOriginPayload>(&bytes).unwrap().origin, site);
+    let response = app.clone().oneshot(Request::get(format!("/api/state/{}", id.0)).body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<StateResponse>(&bytes).unwrap().origin, site);
+
+    {
+        // Cameras are in scene space, so the view set after rebasing looks at the site.
+        let camera = Camera::look_at((0.25, 0.25, 5.0).into(), (0.25, 0.25, 0.0).into(), crate::scene::camera::UpAxis::Z).orthographic(1.0);
+        ctx.lock().await.set_current_view(crate::scene::view::View::new(camera, glam::UVec2::splat(16)));
+    }
+    let response = post("/api/pick", serde_json::json!({ "screen_pos": [6.5, 6.5] })).await;
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let hit = serde_json::from_slice::<PickResponse>(&bytes).unwrap();
+    assert_eq!(hit.entity_id, Some(id.0));
+    let (point, world) = (hit.point.unwrap(), hit.world_point.unwrap());
+    for axis in 0..3 {
+        assert!((world[axis] - site[axis] - point[axis] as f64).abs() < 1e-6);
+    }
+}
+
+#[tokio::test]
+async fn http_import_uploads_obj_and_stl() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let upload = |uri: &'static str, body: Vec<u8>| {
+        let app = app.clone();
+        async move { app.oneshot(Request::post(uri).header("content-type", "application/octet-stream").body(Body::from(body)).unwrap()).await.unwrap() }
+    };
+    let obj = "o left\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\no right\nv 2 0 0\nf 2 4 3\n";
+    let response = upload("/api/import/obj", obj.as_bytes().to_vec()).await;
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let imported = serde_json::from_slice::<ImportResponse>(&bytes).unwrap();
+    assert_eq!(imported.entities.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["left", "right"]);
+    assert!(ctx.lock().await.get_state(crate::scene::id::EntityId(imported.entities[1].entity_id)).unwrap().has_mesh);
+
+    let stl = "solid s\n facet normal 0 0 1\n  outer loop\n   vertex 0 0 0\n   vertex 1 0 0\n  endloop\n endfacet\nendsolid s\n";
+    let response = upload("/api/import/stl", stl.as_bytes().to_vec()).await;
+    assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(error["code"], "ParseError");
+    assert!(error["message"].as_str().unwrap().starts_with("line 7"));
+    let response = upload("/api/import/obj", vec![b'v', b' ', 0xff]).await;
+    assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn http_import_accepts_files_over_two_megabytes() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let mut obj = String::from("o strip\n");
+    for i in 0..30_000 {
+        let x = i as f32 * 0.001;
+        obj += &format!("v {x:.6} 0.000000 0.000000\nv {:.6} 0.000000 0.000000\nv {x:.6} 1.000000 0.000000\nf -3 -2 -1\n", x + 0.001);
+    }
+    assert!(obj.len() > 2 * 1024 * 1024);
+    let response = app.oneshot(Request::post("/api/import/obj").header("content-type", "text/plain").body(Body::from(obj)).unwrap()).await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<ImportResponse>(&bytes).unwrap().entities.len(), 1);
+}
+
+#[tokio::test]
+async fn http_export_downloads_gltf() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    {
+        let mut ctx = ctx.lock().await;
+        for selected in [false, true] {
+            let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+            ctx.set_visibility(id, true).unwrap();
+            ctx.set_selected(id, selected).unwrap();
+        }
+    }
+    let download = |uri: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+            assert!(response.status().is_success());
+            let headers = response.headers().clone();
+            (headers, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+        }
+    };
+    let (headers, glb) = download("/api/export").await;
+    assert_eq!(headers["content-type"], "model/gltf-binary");
+    assert!(headers["content-disposition"].to_str().unwrap().contains("scene.glb"));
+    assert_eq!(&glb[..4], b"glTF");
+
+    let (headers, gltf) = download("/api/export?format=gltf&selected_only=true").await;
+    assert_eq!(headers["content-type"], "model/gltf+json");
+    let json: serde_json::Value = serde_json::from_slice(&gltf).unwrap();
+    // The selected entity under the root turning the scene +Y up.
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+    // Both entities share the mesh; only the selected one is exported.
+    assert_eq!(json["meshes"].as_array().unwrap().len(), 1);
+    assert!(json["buffers"][0]["uri"].as_str().unwrap().starts_with("data:application/octet-stream;base64,"));
+}
+
+#[tokio::test]
+async fn http_submit_accepts_packed_meshes() {
+    use crate::scene::packed::{encode_mesh, PackOptions};
+
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let submit = |uri: &'static str, content_type: String, body: Vec<u8>| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(Request::post(uri).header("content-type", content_type).body(Body::from(body)).unwrap()).await.unwrap();
+            let status = response.status();
+            (status, serde_json::from_slice::<serde_json::Value>(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap())
+        }
+    };
+    let packed = encode_mesh(&sample_mesh(), &PackOptions { quantize_positions: true, octahedral_normals: true });
+    let (status, body) = submit("/api/entity?entity_id=7", "application/octet-stream".to_owned(), packed.clone()).await;
+    assert!(status.is_success());
+    assert_eq!(body["entity_id"], 7);
+
+    let boundary = "mesh-upload";
+    let mut form = format!("--{boundary}\r\nContent-Disposition: form-data; name=\"entity_id\"\r\n\r\n8\r\n--{boundary}\r\nContent-Disposition: form-data; name=\"mesh\"; filename=\"part.rcmb\"\r\nContent-Type: application/octet-stream\r\n\r\n").into_bytes();
+    form.extend(&packed);
+    form.extend(format!("\r\n--{boundary}--\r\n").into_bytes());
+    let (status, body) = submit("/api/entity", format!("multipart/form-data; boundary={boundary}"), form).await;
+    assert!(status.is_success());
+    assert_eq!(body["entity_id"], 8);
+    assert!(ctx.lock().await.get_state(crate::scene::id::EntityId(8)).unwrap().has_mesh);
+
+    // A bad index is rejected the same way whichever encoding carried it.
+    let mut broken = sample_mesh();
+    broken.indices[0] = 99;
+    let (status, binary) = submit("/api/entity", "application/octet-stream".to_owned(), encode_mesh(&broken, &PackOptions::default())).await;
+    assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    let json = serde_json::json!({ "shape": { "mesh": broken }, "entity_id": null }).to_string().into_bytes();
+    let (_, from_json) = submit("/api/entity", "application/json".to_owned(), json).await;
+    assert_eq!(binary, from_json);
+    let (status, truncated) = submit("/api/entity", "application/octet-stream".to_owned(), packed[..30].to_vec()).await;
+    assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    assert_eq!(truncated["code"], "ParseError");
+}
+
+#[tokio::test]
+async fn http_submit_accepts_packed_meshes_over_two_megabytes() {
+    use crate::scene::packed::{encode_mesh, PackOptions};
+
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let vertices: Vec<Vertex> = (0..120_000).map(|i| Vertex { position: glam::Vec3::new((i / 2) as f32 * 0.01, (i % 2) as f32, 0.0), normal: glam::Vec3::Z, uv: None }).collect();
+    let indices = (0..vertices.len() as u32 - 2).flat_map(|i| if i % 2 == 0 { [i, i + 1, i + 2] } else { [i + 1, i, i + 2] }).collect();
+    let packed = encode_mesh(&MeshData { vertices, indices, ..MeshData::default() }, &PackOptions::default());
+    assert!(packed.len() > 2 * 1024 * 1024);
+
+    let response = app.clone().oneshot(Request::post("/api/entity").header("content-type", "application/octet-stream").body(Body::from(packed.clone())).unwrap()).await.unwrap();
+    assert!(response.status().is_success());
+    let boundary = "mesh-upload";
+    let mut form = format!("--{boundary}\r\nContent-Disposition: form-data; name=\"mesh\"; filename=\"strip.rcmb\"\r\nContent-Type: application/octet-stream\r\n\r\n").into_bytes();
+    form.extend(&packed);
+    form.extend(format!("\r\n--{boundary}--\r\n").into_bytes());
+    let content_type = format!("multipart/form-data; boundary={boundary}");
+    let response = app.clone().oneshot(Request::post("/api/entity").header("content-type", content_type).body(Body::from(form)).unwrap()).await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn http_updates_patch_vertices_and_indices() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let id = ctx.lock().await.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+    ctx.lock().await.sync_gpu().unwrap();
+    let post = |uri: String, body: serde_json::Value| {
+        let app = app.clone();
+        async move { app.oneshot(Request::post(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap()).await.unwrap().status() }
+    };
+    let vertex = Vertex { position: (2.0, 0.0, 0.0).into(), normal: (0.0, 0.0, 1.0).into(), uv: None };
+    let status = post(format!("/api/entity/{}/vertices", id.0), serde_json::json!({ "start": 1, "vertices": [vertex] })).await;
+    assert!(status.is_success());
+    let status = post(format!("/api/entity/{}/indices", id.0), serde_json::json!({ "start": 0, "indices": [0, 2, 1] })).await;
+    assert!(status.is_success());
+    let report = ctx.lock().await.sync_gpu().unwrap();
+    assert_eq!((report.geometry, report.uploaded_bytes), (1, 32 + 3 * 4));
+
+    let status = post(format!("/api/entity/{}/indices", id.0), serde_json::json!({ "start": 2, "indices": [0, 1] })).await;
+    assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    let status = post("/api/entity/99/vertices".to_owned(), serde_json::json!({ "start": 0, "vertices": [] })).await;
+    assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn http_overlay_items_pick_only_when_asked() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    let id = {
+        let mut ctx = ctx.lock().await;
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: sample_mesh() }.into_shape(), &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+        let camera = Camera::look_at((0.25, 0.25, 5.0).into(), (0.25, 0.25, 0.0).into(), crate::scene::camera::UpAxis::Z).orthographic(1.0);
+        ctx.set_current_view(crate::scene::view::View::new(camera, glam::UVec2::splat(16)));
+        id
+    };
+    let send = |method: &'static str, uri: String, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            let request = Request::builder().method(method).uri(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+        }
+    };
+    let (status, bytes) = send("POST", "/api/overlay".to_owned(), serde_json::json!({ "kind": "grip", "position": [0.25, 0.25, 0.0], "hot": false })).await;
+    assert!(status.is_success());
+    let grip = serde_json::from_slice::<OverlayResponse>(&bytes).unwrap().overlay_id;
+    let (status, _) = send("POST", format!("/api/overlay/{grip}"), serde_json::json!({ "kind": "grip", "position": [0.25, 0.25, 0.0], "hot": true })).await;
+    assert!(status.is_success());
+
+    let (_, bytes) = send("POST", "/api/pick".to_owned(), serde_json::json!({ "screen_pos": [8.0, 8.0] })).await;
+    let hit = serde_json::from_slice::<PickResponse>(&bytes).unwrap();
+    assert_eq!((hit.entity_id, hit.overlay_id), (Some(id.0), None));
+    let (_, bytes) = send("POST", "/api/pick".to_owned(), serde_json::json!({ "screen_pos": [8.0, 8.0], "include_overlay": true })).await;
+    let hit = serde_json::from_slice::<PickResponse>(&bytes).unwrap();
+    assert_eq!((hit.entity_id, hit.overlay_id, hit.point), (None, Some(grip), Some([0.25, 0.25, 0.0])));
+
+    let (status, _) = send("DELETE", format!("/api/overlay/{grip}"), serde_json::Value::Null).await;
+    assert!(status.is_success());
+    let (status, _) = send("DELETE", format!("/api/overlay/{grip}"), serde_json::Value::Null).await;
+    assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    send("POST", "/api/overlay".to_owned(), serde_json::json!({ "kind": "rubber_band", "from": [0.0, 0.0], "to": [4.0, 4.0], "crossing": true })).await;
+    let (status, _) = send("DELETE", "/api/overlay".to_owned(), serde_json::Value::Null).await;
+    assert!(status.is_success());
+    assert!(ctx.lock().await.overlay().is_empty());
+}
+
+#[tokio::test]
+async fn http_snap_reports_point_input() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    {
+        let mut ctx = ctx.lock().await;
+        let line = MeshData {
+            vertices: vec![
+                Vertex { position: (1.0, 5.0, 0.0).into(), normal: (0.0, 0.0, 1.0).into(), uv: None },
+                Vertex { position: (9.0, 5.0, 0.0).into(), normal: (0.0, 0.0, 1.0).into(), uv: None },
+            ],
+            polylines: vec![vec![0, 1]],
+            ..MeshData::default()
+        };
+        let id = ctx.submit_shape(None, &ShapePayload { mesh: line }.into_shape(), &TessParams::default()).unwrap();
+        ctx.set_visibility(id, true).unwrap();
+        let camera = Camera::look_at((5.0, 5.0, 10.0).into(), (5.0, 5.0, 0.0).into(), crate::scene::camera::UpAxis::Z).orthographic(10.0);
+        ctx.set_current_view(crate::scene::view::View::new(camera, glam::UVec2::splat(100)));
+    }
+    let post = |uri: &'static str, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(Request::post(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap()).await.unwrap();
+            let status = response.status();
+            (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+        }
+    };
+    // (1.5, 5) is 5 px right of the endpoint.
+    let (_, bytes) = post("/api/snap", serde_json::json!({ "screen_pos": [15.0, 50.0] })).await;
+    let result = serde_json::from_slice::<serde_json::Value>(&bytes).unwrap();
+    assert_eq!(result["snap_type"], "endpoint");
+    assert_eq!(result["snapped"], serde_json::json!([1.0, 5.0, 0.0]));
+
+    let (status, _) = post("/api/snap_settings", serde_json::json!({ "endpoint": false })).await;
+    assert!(status.is_success());
+    let response = app.clone().oneshot(Request::get("/api/snap_settings").body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let settings = serde_json::from_slice::<serde_json::Value>(&bytes).unwrap();
+    assert_eq!((settings["endpoint"].as_bool(), settings["midpoint"].as_bool()), (Some(false), Some(true)));
+    let (_, bytes) = post("/api/snap", serde_json::json!({ "screen_pos": [15.0, 50.0], "from": [1.5, 8.0, 0.0] })).await;
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()["snap_type"], "perpendicular");
+
+    // Ortho from (1.5, 8) holds the cursor to the vertical through it.
+    let (status, _) = post("/api/constraint_settings", serde_json::json!({ "ortho": true })).await;
+    assert!(status.is_success());
+    let (_, bytes) = post("/api/snap", serde_json::json!({ "screen_pos": [17.0, 48.0], "from": [1.5, 8.0, 0.0] })).await;
+    let result = serde_json::from_slice::<serde_json::Value>(&bytes).unwrap();
+    assert_eq!((&result["constraint"], &result["snap_type"]), (&serde_json::json!("ortho"), &serde_json::json!("intersection")));
+    assert_eq!(result["snapped"], serde_json::json!([1.5, 5.0, 0.0]));
+    let response = app.clone().oneshot(Request::get("/api/constraint_settings").body(Body::empty()).unwrap()).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&bytes).unwrap(), serde_json::json!({ "ortho": true, "parallel": false, "perpendicular": false }));
+
+    let (status, _) = post("/api/snap_settings", serde_json::json!({ "aperture_px": -1.0 })).await;
+    assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    let (status, _) = post("/api/snap", serde_json::json!({ "screen_pos": [15.0, 50.0], "viewport": 9 })).await;
+    assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn http_tracking_acquires_points_and_returns_guides() {
+    let ctx = Arc::new(tokio::sync::Mutex::new(crate::scene::context::SceneContext::new()));
+    let app = command_server(ctx.clone());
+    {
+        let camera = Camera::look_at((5.0, 5.0, 10.0).into(), (5.0, 5.0, 0.0).into(), crate::scene::camera::UpAxis::Z).orthographic(10.0);
+        ctx.lock().await.set_current_view(crate::scene::view::View::new(camera, glam::UVec2::splat(100)));
+    }
+    let send = |method: &'static str, uri: &'static str, body: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            let request = Request::builder().method(method).uri(uri).header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap())
+        }
+    };
+    let (status, _) = send("POST", "/api/tracking_settings", serde_json::json!({ "object_tracking": true })).await;
+    assert!(status.is_success());
+    let (_, bytes) = send("POST", "/api/tracking/acquire", serde_json::json!({ "point": [2.0, 6.0, 0.0] })).await;
+    assert_eq!(serde_json::from_slice::<TrackingPointsResponse>(&bytes).unwrap().points, [[2.0, 6.0, 0.0]]);
+    // Pixel (40, 41) is (4, 5.9): onto the horizontal through the acquired point.
+    let (_, bytes) = send("POST", "/api/snap", serde_json::json!({ "screen_pos": [40.0, 41.0] })).await;
+    let result = serde_json::from_slice::<serde_json::Value>(&bytes).unwrap();
+    assert_eq!(result["snapped"], serde_json::json!([4.0, 6.0, 0.0]));
+    assert_eq!(result["guides"][0]["kind"], "object");
+
+    let (status, _) = send("POST", "/api/tracking_settings", serde_json::json!({ "object_tracking": false })).await;
+    assert!(status.is_success());
+    let (_, bytes) = send("POST", "/api/snap", serde_json::json!({ "screen_pos": [40.0, 41.0] })).await;
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()["guides"], serde_json::json!([]));
+    let (status, _) = send("POST", "/api/tracking_settings", serde_json::json!({ "capture_px": 0.0 })).await;
+    assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+    let (_, bytes) = send("POST", "/api/tracking/release", serde_json::json!({ "point": [2.0, 6.0, 0.0] })).await;
+    assert!(serde_json::from_slice::<TrackingPointsResponse>(&bytes).unwrap().points.is_empty());
+    send("POST", "/api/tracking/acquire", serde_json::json!({ "point": [1.0, 1.0, 0.0] })).await;
+    let (_, bytes) = send("DELETE", "/api/tracking", serde_json::Value::Null).await;
+    assert!(serde_json::from_slice::<TrackingPointsResponse>(&bytes).unwrap().points.is_empty());
+}